@@ -0,0 +1,547 @@
+//! CSS, JavaScript and HTML minification shared by `publish-spa-wasm` and
+//! `logseq-publisher-rust`
+
+/// Minify CSS by removing comments and insignificant whitespace.
+///
+/// Strings, unquoted `url(...)` values and `/*! ... */` notices are copied
+/// verbatim, and spaces that carry meaning (descendant selectors, `calc()`
+/// operators, `and (` in media queries) are kept.
+pub fn minify_css(css: &str) -> String {
+    let chars: Vec<char> = css.chars().collect();
+    let mut out = String::with_capacity(css.len());
+    let mut pending_space = false;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        if c == '/' && chars.get(i + 1) == Some(&'*') {
+            let end = find_comment_end(&chars, i + 2);
+            if chars.get(i + 2) == Some(&'!') {
+                push_css_space(&mut out, pending_space, c);
+                out.extend(&chars[i..end]);
+                pending_space = false;
+            } else {
+                // A comment separates tokens like whitespace does
+                pending_space = true;
+            }
+            i = end;
+            continue;
+        }
+
+        if c.is_whitespace() {
+            pending_space = true;
+            i += 1;
+            continue;
+        }
+
+        push_css_space(&mut out, pending_space, c);
+        pending_space = false;
+
+        match c {
+            '"' | '\'' => i = copy_string(&chars, i, &mut out),
+            '(' if out.len() >= 3 && out.as_bytes()[out.len() - 3..].eq_ignore_ascii_case(b"url") => {
+                out.push(c);
+                i = copy_url(&chars, i + 1, &mut out);
+            }
+            _ => {
+                // The last declaration in a block needs no semicolon
+                if c == '}' && out.ends_with(';') {
+                    out.pop();
+                }
+                out.push(c);
+                i += 1;
+            }
+        }
+    }
+
+    out
+}
+
+/// Minify JavaScript by removing comments and insignificant whitespace.
+///
+/// Line breaks are kept wherever automatic semicolon insertion could depend
+/// on them, so code without semicolons keeps its meaning. Strings, template
+/// literals and regular expression literals are copied verbatim.
+pub fn minify_js(js: &str) -> String {
+    let chars: Vec<char> = js.chars().collect();
+    let mut out = String::with_capacity(js.len());
+    let mut pending_space = false;
+    let mut pending_newline = false;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+
+        if c == '/' && next == Some('/') {
+            while i < chars.len() && !is_line_terminator(chars[i]) {
+                i += 1;
+            }
+            continue;
+        }
+
+        if c == '/' && next == Some('*') {
+            let end = find_comment_end(&chars, i + 2);
+            if chars[i..end].iter().any(|c| is_line_terminator(*c)) {
+                pending_newline = true;
+            } else {
+                pending_space = true;
+            }
+            i = end;
+            continue;
+        }
+
+        if is_line_terminator(c) {
+            pending_newline = true;
+            i += 1;
+            continue;
+        }
+
+        if c.is_whitespace() {
+            pending_space = true;
+            i += 1;
+            continue;
+        }
+
+        push_js_separator(&mut out, pending_newline, pending_space, c);
+        pending_newline = false;
+        pending_space = false;
+
+        match c {
+            '"' | '\'' => i = copy_string(&chars, i, &mut out),
+            '`' => i = copy_template(&chars, i, &mut out),
+            '/' if regex_allowed(&out) => i = copy_regex(&chars, i, &mut out),
+            _ => {
+                out.push(c);
+                i += 1;
+            }
+        }
+    }
+
+    out
+}
+
+fn push_css_space(out: &mut String, pending_space: bool, next: char) {
+    let Some(prev) = out.chars().next_back() else {
+        return;
+    };
+    let no_space_after = matches!(prev, '{' | '}' | ';' | ',' | '>' | '~' | '(' | ':') || out.ends_with("*/");
+    let no_space_before = matches!(next, '{' | '}' | ';' | ',' | '>' | '~' | ')' | '!');
+    if pending_space && !no_space_after && !no_space_before {
+        out.push(' ');
+    }
+}
+
+fn push_js_separator(out: &mut String, pending_newline: bool, pending_space: bool, next: char) {
+    let Some(prev) = out.chars().next_back() else {
+        return;
+    };
+
+    if pending_newline {
+        // A line break after these, or before a closing bracket, never ends a statement early
+        let insignificant = matches!(prev, '{' | ';' | ',' | '(' | '[') || matches!(next, '}' | ')' | ']');
+        if !insignificant {
+            out.push('\n');
+        }
+        return;
+    }
+
+    let is_word = |c: char| c.is_alphanumeric() || c == '_' || c == '$' || !c.is_ascii();
+    let needs_space = (is_word(prev) && is_word(next))
+        // `a + +b`, `a - -b`
+        || (prev == next && matches!(prev, '+' | '-'))
+        // `a / /re/` would start a comment
+        || (prev == '/' && matches!(next, '/' | '*'))
+        // `1 .toString()` is not `1.toString()`
+        || (prev.is_ascii_digit() && next == '.');
+    if pending_space && needs_space {
+        out.push(' ');
+    }
+}
+
+fn is_line_terminator(c: char) -> bool {
+    matches!(c, '\n' | '\r' | '\u{2028}' | '\u{2029}')
+}
+
+/// Index just past the `*/` closing a comment whose body starts at `start`
+fn find_comment_end(chars: &[char], start: usize) -> usize {
+    (start..chars.len().saturating_sub(1))
+        .find(|&i| chars[i] == '*' && chars[i + 1] == '/')
+        .map_or(chars.len(), |i| i + 2)
+}
+
+/// Copy a quoted string starting at `start`, returning the index after it.
+/// An unterminated string ends at the line break.
+fn copy_string(chars: &[char], start: usize, out: &mut String) -> usize {
+    let quote = chars[start];
+    out.push(quote);
+    let mut i = start + 1;
+    while i < chars.len() {
+        let c = chars[i];
+        if c == '\\' {
+            out.extend(&chars[i..(i + 2).min(chars.len())]);
+            i += 2;
+            continue;
+        }
+        if is_line_terminator(c) {
+            return i;
+        }
+        out.push(c);
+        i += 1;
+        if c == quote {
+            return i;
+        }
+    }
+    chars.len()
+}
+
+/// Copy the body of an unquoted CSS `url(...)` up to and including `)`
+fn copy_url(chars: &[char], start: usize, out: &mut String) -> usize {
+    let mut i = start;
+    while i < chars.len() && chars[i].is_whitespace() {
+        i += 1;
+    }
+    if matches!(chars.get(i), Some('"' | '\'')) {
+        return i;
+    }
+    while i < chars.len() {
+        out.push(chars[i]);
+        i += 1;
+        if chars[i - 1] == ')' {
+            break;
+        }
+    }
+    i
+}
+
+/// Copy a template literal, including nested `${...}` expressions, verbatim
+fn copy_template(chars: &[char], start: usize, out: &mut String) -> usize {
+    out.push('`');
+    let mut i = start + 1;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => {
+                out.extend(&chars[i..(i + 2).min(chars.len())]);
+                i += 2;
+            }
+            '`' => {
+                out.push('`');
+                return i + 1;
+            }
+            '$' if chars.get(i + 1) == Some(&'{') => {
+                out.push_str("${");
+                i = copy_template_expression(chars, i + 2, out);
+            }
+            c => {
+                out.push(c);
+                i += 1;
+            }
+        }
+    }
+    i
+}
+
+fn copy_template_expression(chars: &[char], start: usize, out: &mut String) -> usize {
+    let mut depth = 1;
+    let mut i = start;
+    while i < chars.len() {
+        match chars[i] {
+            '"' | '\'' => i = copy_string(chars, i, out),
+            '`' => i = copy_template(chars, i, out),
+            c => {
+                out.push(c);
+                i += 1;
+                if c == '{' {
+                    depth += 1;
+                } else if c == '}' {
+                    depth -= 1;
+                    if depth == 0 {
+                        return i;
+                    }
+                }
+            }
+        }
+    }
+    i
+}
+
+/// Copy a regular expression literal body, up to and including the closing `/`
+fn copy_regex(chars: &[char], start: usize, out: &mut String) -> usize {
+    out.push('/');
+    let mut in_class = false;
+    let mut i = start + 1;
+    while i < chars.len() && !is_line_terminator(chars[i]) {
+        let c = chars[i];
+        if c == '\\' {
+            out.extend(&chars[i..(i + 2).min(chars.len())]);
+            i += 2;
+            continue;
+        }
+        out.push(c);
+        i += 1;
+        match c {
+            '[' => in_class = true,
+            ']' => in_class = false,
+            '/' if !in_class => return i,
+            _ => {}
+        }
+    }
+    i
+}
+
+/// Whether a `/` following the output so far starts a regular expression
+/// rather than a division
+fn regex_allowed(out: &str) -> bool {
+    let code = out.trim_end();
+    let Some(prev) = code.chars().next_back() else {
+        return true;
+    };
+    if "(,=:[!&|?{};+-*%<>~^}".contains(prev) {
+        return true;
+    }
+
+    let word: String = code
+        .chars()
+        .rev()
+        .take_while(|c| c.is_alphanumeric() || *c == '_' || *c == '$')
+        .collect::<Vec<_>>()
+        .into_iter()
+        .rev()
+        .collect();
+    matches!(
+        word.as_str(),
+        "return" | "typeof" | "instanceof" | "in" | "of" | "new" | "delete" | "void"
+            | "throw" | "case" | "do" | "else" | "yield" | "await"
+    )
+}
+
+/// Elements whose content is copied verbatim
+const RAW_TEXT_ELEMENTS: [&str; 4] = ["pre", "textarea", "script", "style"];
+
+/// Elements around which whitespace never renders
+const BLOCK_ELEMENTS: [&str; 43] = [
+    "!doctype", "html", "head", "body", "title", "meta", "link", "base", "noscript",
+    "div", "p", "ul", "ol", "li", "dl", "dt", "dd", "h1", "h2", "h3", "h4", "h5", "h6",
+    "nav", "article", "section", "header", "footer", "main", "aside", "blockquote",
+    "pre", "hr", "figure", "figcaption", "table", "thead", "tbody", "tfoot", "tr", "td",
+    "th", "form",
+];
+
+/// Minify HTML by removing comments and collapsing whitespace.
+///
+/// Whitespace runs become a single space, which keeps the gaps between inline
+/// elements, and are dropped entirely next to block-level tags. The contents of
+/// `<pre>`, `<textarea>`, `<script>` and `<style>` are left untouched, as are
+/// tag attributes and conditional comments.
+pub fn minify_html(html: &str) -> String {
+    let mut out = String::with_capacity(html.len());
+    let mut rest = html;
+    let mut pending_space = false;
+    // Whether the last token was a block-level tag (the document start counts as one)
+    let mut after_block = true;
+
+    while let Some(c) = rest.chars().next() {
+        if rest.starts_with("<!--") && !rest.starts_with("<!--[if") {
+            let end = rest.find("-->").map_or(rest.len(), |i| i + 3);
+            rest = &rest[end..];
+            continue;
+        }
+
+        let starts_tag = c == '<'
+            && rest[1..].starts_with(|next: char| next.is_ascii_alphabetic() || next == '/' || next == '!');
+        if starts_tag {
+            let tag_end = find_tag_end(rest);
+            let tag = &rest[..tag_end];
+            let name = tag_name(tag);
+            let is_block = BLOCK_ELEMENTS.contains(&name.as_str());
+
+            if pending_space && !is_block && !after_block {
+                out.push(' ');
+            }
+            pending_space = false;
+            after_block = is_block;
+            out.push_str(tag);
+            rest = &rest[tag_end..];
+
+            if !tag.starts_with("</") && RAW_TEXT_ELEMENTS.contains(&name.as_str()) {
+                let end = find_closing_tag(rest, &name);
+                out.push_str(&rest[..end]);
+                rest = &rest[end..];
+            }
+            continue;
+        }
+
+        if c.is_whitespace() {
+            pending_space = true;
+        } else {
+            if pending_space && !after_block {
+                out.push(' ');
+            }
+            pending_space = false;
+            after_block = false;
+            out.push(c);
+        }
+        rest = &rest[c.len_utf8()..];
+    }
+
+    out
+}
+
+/// Index just past the `>` ending the tag at the start of `html`, skipping quoted attribute values
+fn find_tag_end(html: &str) -> usize {
+    let mut quote = None;
+    for (i, c) in html.char_indices() {
+        match (quote, c) {
+            (Some(q), _) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '>') => return i + 1,
+            _ => {}
+        }
+    }
+    html.len()
+}
+
+/// Lowercase element name of a tag such as `<div class="x">` or `</div>`
+fn tag_name(tag: &str) -> String {
+    tag.trim_start_matches('<')
+        .trim_start_matches('/')
+        .chars()
+        .take_while(|c| !c.is_whitespace() && *c != '>' && *c != '/')
+        .collect::<String>()
+        .to_ascii_lowercase()
+}
+
+/// Index of the `</name` closing tag in `html`, or its length when missing
+fn find_closing_tag(html: &str, name: &str) -> usize {
+    let closing = format!("</{}", name);
+    html.match_indices("</")
+        .map(|(i, _)| i)
+        .find(|&i| {
+            html.as_bytes()[i..]
+                .get(..closing.len())
+                .is_some_and(|tag| tag.eq_ignore_ascii_case(closing.as_bytes()))
+        })
+        .unwrap_or(html.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_css_minification() {
+        let css = r#"
+        /* Layout */
+        body {
+            margin: 0;
+            padding: 0;
+        }
+        "#;
+
+        let minified = minify_css(css);
+        assert!(!minified.contains('\n'));
+        assert_eq!(minified, "body{margin:0;padding:0}");
+    }
+
+    #[test]
+    fn test_css_minification_preserves_meaning() {
+        let css = r#"/*! License notice */
+.note::before { content: "/* not a comment */  two  spaces"; }
+.nav a :hover, .a > .b { width: calc(100% - 2 * 1rem) !important; }
+@media screen and (max-width: 600px) { .c { background: url( data:image/png;base64,iVBO/*x*/RK ) } }
+"#;
+
+        let minified = minify_css(css);
+        assert_eq!(
+            minified,
+            "/*! License notice */.note::before{content:\"/* not a comment */  two  spaces\"}\
+             .nav a :hover,.a>.b{width:calc(100% - 2 * 1rem)!important}\
+             @media screen and (max-width:600px){.c{background:url(data:image/png;base64,iVBO/*x*/RK )}}"
+        );
+        assert_eq!(minify_css(&minified), minified);
+    }
+
+    #[test]
+    fn test_js_minification_preserves_meaning() {
+        let js = r#"// Setup
+const url = 'http://example.com' // trailing comment
+let total = a + +b
+const pattern = /\/\/[a-z/]+/g
+/* block
+   comment */
+const label = `sum: ${ total /* kept */ } // not a comment`
+if (total > 1) {
+    console.log(1 .toString())
+}
+[1, 2].forEach(n => n)
+"#;
+
+        let minified = minify_js(js);
+        assert_eq!(
+            minified,
+            "const url='http://example.com'\n\
+             let total=a+ +b\n\
+             const pattern=/\\/\\/[a-z/]+/g\n\
+             const label=`sum: ${ total /* kept */ } // not a comment`\n\
+             if(total>1){console.log(1 .toString())}\n\
+             [1,2].forEach(n=>n)"
+        );
+        assert_eq!(minify_js(&minified), minified);
+    }
+
+    #[test]
+    fn test_css_url_is_copied_verbatim_in_any_case() {
+        assert_eq!(
+            minify_css(".a { background: URL( \"x y.png\" ) , Url(a b.png) }"),
+            ".a{background:URL(\"x y.png\"),Url(a b.png)}"
+        );
+    }
+
+    #[test]
+    fn test_js_minification_keeps_literals_and_line_breaks() {
+        let js = r#"const s = "a  // b"; const t = 'c  /* d */'
+function f() {
+    return
+    s
+}
+x
+++y
+const ratio = total / count / 2
+const path = a.match(/'[^']*'/)
+"#;
+
+        let minified = minify_js(js);
+        // Strings and regular expressions that look like comments or quotes are untouched
+        assert!(minified.contains("const s=\"a  // b\""));
+        assert!(minified.contains("const t='c  /* d */'"));
+        assert!(minified.contains("a.match(/'[^']*'/)"));
+        // Line breaks that automatic semicolon insertion depends on survive
+        assert!(minified.contains("return\ns}"));
+        assert!(minified.contains("x\n++y"));
+        // Division isn't mistaken for a regular expression
+        assert!(minified.contains("const ratio=total/count/2"));
+    }
+
+    #[test]
+    fn test_html_minification() {
+        let html = "<!DOCTYPE html>\n<html>\n<head>\n  <title>T</title>\n  <!-- build info -->\n</head>\n<body>\n\
+            <ul>\n  <li><a href=\"a.html\" title=\"a > b\">A</a>   <em>b</em>\n  text</li>\n</ul>\n\
+            <pre>  keep\n    this </pre>\n<script>\nif (a)\n  b()\n</script>\n</body>\n</html>\n";
+
+        assert_eq!(
+            minify_html(html),
+            "<!DOCTYPE html><html><head><title>T</title></head><body>\
+             <ul><li><a href=\"a.html\" title=\"a > b\">A</a> <em>b</em> text</li></ul>\
+             <pre>  keep\n    this </pre><script>\nif (a)\n  b()\n</script></body></html>"
+        );
+        assert_eq!(minify_html(&minify_html(html)), minify_html(html));
+    }
+
+    #[test]
+    fn test_html_raw_text_closing_tag_in_any_case() {
+        let html = "<SCRIPT>\nlet a  =  '</p>'\n</Script>\n<p>  x  </p>";
+        assert_eq!(minify_html(html), "<SCRIPT>\nlet a  =  '</p>'\n</Script><p>x</p>");
+    }
+}
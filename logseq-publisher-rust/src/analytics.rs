@@ -0,0 +1,409 @@
+use crate::graph::Graph;
+use petgraph::graph::{DiGraph, NodeIndex};
+use petgraph::unionfind::UnionFind;
+use petgraph::visit::EdgeRef;
+use petgraph::Direction;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+const DAMPING: f64 = 0.85;
+const PAGERANK_TOLERANCE: f64 = 1e-10;
+const MAX_ITERATIONS: usize = 100;
+
+/// Directed page link graph, with one node per page path.
+///
+/// Nodes are added in path order and links are resolved to pages by path or
+/// by case-insensitive title, so the node indices (and everything computed
+/// from them) are the same however the pages were loaded. Links to pages that
+/// don't exist, self-links and repeated links are dropped.
+pub struct LinkGraph {
+    graph: DiGraph<String, ()>,
+    nodes: HashMap<String, NodeIndex>,
+}
+
+impl LinkGraph {
+    pub fn from_graph(source: &Graph) -> Self {
+        let mut pages: Vec<_> = source.pages().collect();
+        pages.sort_by(|a, b| a.path.cmp(&b.path));
+
+        let mut graph = DiGraph::new();
+        let mut nodes = HashMap::new();
+        let mut titles = HashMap::new();
+        for page in &pages {
+            nodes.insert(page.path.clone(), graph.add_node(page.path.clone()));
+            titles.entry(page.title.to_lowercase()).or_insert(page.path.as_str());
+        }
+
+        for page in &pages {
+            let from = nodes[&page.path];
+            for link in &page.links {
+                let target = nodes.get(link)
+                    .or_else(|| titles.get(&link.to_lowercase()).map(|path| &nodes[*path]));
+                if let Some(&to) = target {
+                    if to != from && graph.find_edge(from, to).is_none() {
+                        graph.add_edge(from, to, ());
+                    }
+                }
+            }
+        }
+
+        Self { graph, nodes }
+    }
+
+    /// The underlying petgraph graph; node weights are page paths
+    pub fn graph(&self) -> &DiGraph<String, ()> {
+        &self.graph
+    }
+
+    pub fn node(&self, path: &str) -> Option<NodeIndex> {
+        self.nodes.get(path).copied()
+    }
+
+    pub fn path(&self, node: NodeIndex) -> &str {
+        &self.graph[node]
+    }
+
+    /// PageRank of every node, indexed by node and summing to 1.
+    ///
+    /// Rank from pages without outgoing links is spread evenly over all pages.
+    pub fn pagerank(&self) -> Vec<f64> {
+        let n = self.graph.node_count();
+        if n == 0 {
+            return Vec::new();
+        }
+
+        let out_degree: Vec<usize> = self.graph.node_indices()
+            .map(|node| self.graph.edges_directed(node, Direction::Outgoing).count())
+            .collect();
+        let mut rank = vec![1.0 / n as f64; n];
+
+        for _ in 0..MAX_ITERATIONS {
+            let dangling: f64 = (0..n).filter(|&i| out_degree[i] == 0).map(|i| rank[i]).sum();
+            let base = (1.0 - DAMPING) / n as f64 + DAMPING * dangling / n as f64;
+
+            let mut next = vec![base; n];
+            for edge in self.graph.edge_references() {
+                let from = edge.source().index();
+                next[edge.target().index()] += DAMPING * rank[from] / out_degree[from] as f64;
+            }
+
+            let change: f64 = rank.iter().zip(&next).map(|(a, b)| (a - b).abs()).sum();
+            rank = next;
+            if change < PAGERANK_TOLERANCE {
+                break;
+            }
+        }
+
+        rank
+    }
+
+    /// Betweenness centrality of every node over shortest link paths
+    /// (Brandes' algorithm), normalized to `0.0..=1.0`.
+    pub fn betweenness(&self) -> Vec<f64> {
+        let n = self.graph.node_count();
+        let mut centrality = vec![0.0; n];
+
+        for source in self.graph.node_indices() {
+            let mut order = Vec::with_capacity(n);
+            let mut predecessors: Vec<Vec<usize>> = vec![Vec::new(); n];
+            let mut paths = vec![0.0; n];
+            let mut distance: Vec<Option<usize>> = vec![None; n];
+            paths[source.index()] = 1.0;
+            distance[source.index()] = Some(0);
+
+            let mut queue = VecDeque::from([source]);
+            while let Some(node) = queue.pop_front() {
+                let v = node.index();
+                order.push(v);
+                let next_distance = distance[v].map(|d| d + 1);
+                for neighbor in self.graph.neighbors_directed(node, Direction::Outgoing) {
+                    let w = neighbor.index();
+                    if distance[w].is_none() {
+                        distance[w] = next_distance;
+                        queue.push_back(neighbor);
+                    }
+                    if distance[w] == next_distance {
+                        paths[w] += paths[v];
+                        predecessors[w].push(v);
+                    }
+                }
+            }
+
+            let mut dependency = vec![0.0; n];
+            for &w in order.iter().rev() {
+                for &v in &predecessors[w] {
+                    dependency[v] += paths[v] / paths[w] * (1.0 + dependency[w]);
+                }
+                if w != source.index() {
+                    centrality[w] += dependency[w];
+                }
+            }
+        }
+
+        if n > 2 {
+            let pairs = ((n - 1) * (n - 2)) as f64;
+            for value in &mut centrality {
+                *value /= pairs;
+            }
+        }
+
+        centrality
+    }
+
+    /// Component number of every node, treating links as undirected.
+    ///
+    /// Components are numbered from 0 by decreasing size, ties broken by
+    /// their first page path.
+    pub fn components(&self) -> Vec<usize> {
+        let n = self.graph.node_count();
+        let mut sets = UnionFind::new(n);
+        for edge in self.graph.edge_references() {
+            sets.union(edge.source().index(), edge.target().index());
+        }
+
+        let labels: Vec<usize> = (0..n).map(|i| sets.find(i)).collect();
+        renumber_by_size(&labels)
+    }
+
+    /// Community (cluster) number of every node, found by greedily moving
+    /// pages between communities while that raises the modularity of the
+    /// undirected link graph (the local-moving phase of the Louvain method).
+    ///
+    /// Nodes are visited in path order and ties go to the lowest community, so
+    /// the result is deterministic. Clusters are numbered like components.
+    pub fn clusters(&self) -> Vec<usize> {
+        let n = self.graph.node_count();
+        let mut community: Vec<usize> = (0..n).collect();
+        let twice_edges = 2.0 * self.graph.edge_count() as f64;
+        if twice_edges == 0.0 {
+            return renumber_by_size(&community);
+        }
+
+        let degree: Vec<f64> = self.graph.node_indices()
+            .map(|node| self.graph.neighbors_undirected(node).count() as f64)
+            .collect();
+        let mut total = degree.clone();
+
+        for _ in 0..MAX_ITERATIONS {
+            let mut moved = false;
+            for node in self.graph.node_indices() {
+                let i = node.index();
+                let current = community[i];
+                total[current] -= degree[i];
+
+                let mut links: BTreeMap<usize, f64> = BTreeMap::new();
+                for neighbor in self.graph.neighbors_undirected(node) {
+                    *links.entry(community[neighbor.index()]).or_default() += 1.0;
+                }
+
+                let gain = |c: usize, links_in: f64| links_in - total[c] * degree[i] / twice_edges;
+                let mut best = (current, gain(current, links.get(&current).copied().unwrap_or(0.0)));
+                for (&c, &links_in) in &links {
+                    let g = gain(c, links_in);
+                    if g > best.1 + 1e-12 {
+                        best = (c, g);
+                    }
+                }
+
+                community[i] = best.0;
+                total[best.0] += degree[i];
+                moved |= best.0 != current;
+            }
+            if !moved {
+                break;
+            }
+        }
+
+        renumber_by_size(&community)
+    }
+}
+
+/// Relabel groups `0..` by decreasing size, ties broken by first member
+fn renumber_by_size(labels: &[usize]) -> Vec<usize> {
+    let mut groups: HashMap<usize, (usize, usize)> = HashMap::new();
+    for (node, &label) in labels.iter().enumerate() {
+        let entry = groups.entry(label).or_insert((0, node));
+        entry.0 += 1;
+    }
+
+    let mut order: Vec<(usize, usize, usize)> = groups.into_iter()
+        .map(|(label, (size, first))| (label, size, first))
+        .collect();
+    order.sort_by(|a, b| b.1.cmp(&a.1).then(a.2.cmp(&b.2)));
+
+    let numbers: HashMap<usize, usize> = order.iter()
+        .enumerate()
+        .map(|(number, &(label, _, _))| (label, number))
+        .collect();
+    labels.iter().map(|label| numbers[label]).collect()
+}
+
+/// Link metrics for one page
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PageMetrics {
+    pub path: String,
+    pub title: String,
+    pub pagerank: f64,
+    pub in_degree: usize,
+    pub out_degree: usize,
+    pub betweenness: f64,
+    pub component: usize,
+    pub cluster: usize,
+}
+
+/// Link analytics for a whole graph
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraphAnalytics {
+    /// Metrics for every page, by path
+    pub pages: Vec<PageMetrics>,
+    /// Page paths in each connected component, largest first
+    pub components: Vec<Vec<String>>,
+    /// Page paths in each community cluster, largest first
+    pub clusters: Vec<Vec<String>>,
+}
+
+impl GraphAnalytics {
+    pub fn compute(source: &Graph) -> Self {
+        let links = LinkGraph::from_graph(source);
+        let graph = links.graph();
+
+        let pagerank = links.pagerank();
+        let betweenness = links.betweenness();
+        let components = links.components();
+        let clusters = links.clusters();
+
+        let pages: Vec<PageMetrics> = graph.node_indices()
+            .map(|node| {
+                let i = node.index();
+                let path = links.path(node);
+                PageMetrics {
+                    path: path.to_string(),
+                    title: source.get_page(path).map_or_else(String::new, |page| page.title.clone()),
+                    pagerank: pagerank[i],
+                    in_degree: graph.edges_directed(node, Direction::Incoming).count(),
+                    out_degree: graph.edges_directed(node, Direction::Outgoing).count(),
+                    betweenness: betweenness[i],
+                    component: components[i],
+                    cluster: clusters[i],
+                }
+            })
+            .collect();
+
+        Self {
+            components: group_paths(&pages, |page| page.component),
+            clusters: group_paths(&pages, |page| page.cluster),
+            pages,
+        }
+    }
+
+    pub fn get(&self, path: &str) -> Option<&PageMetrics> {
+        self.pages
+            .binary_search_by(|page| page.path.as_str().cmp(path))
+            .ok()
+            .map(|i| &self.pages[i])
+    }
+
+    /// The `limit` pages with the highest PageRank, ties broken by path
+    pub fn most_important(&self, limit: usize) -> Vec<&PageMetrics> {
+        let mut pages: Vec<&PageMetrics> = self.pages.iter().collect();
+        pages.sort_by(|a, b| b.pagerank.total_cmp(&a.pagerank).then_with(|| a.path.cmp(&b.path)));
+        pages.truncate(limit);
+        pages
+    }
+}
+
+fn group_paths(pages: &[PageMetrics], group: impl Fn(&PageMetrics) -> usize) -> Vec<Vec<String>> {
+    let count = pages.iter().map(|page| group(page) + 1).max().unwrap_or(0);
+    let mut groups = vec![Vec::new(); count];
+    for page in pages {
+        groups[group(page)].push(page.path.clone());
+    }
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::test_graph;
+
+    #[test]
+    fn test_links_resolve_by_path_or_title() {
+        let links = LinkGraph::from_graph(&test_graph(&[
+            ("a.md", &["B", "c.md", "missing", "a", "b"]),
+            ("b.md", &[]),
+            ("c.md", &[]),
+        ]));
+
+        assert_eq!(links.graph().node_count(), 3);
+        assert_eq!(links.graph().edge_count(), 2);
+        let a = links.node("a.md").unwrap();
+        let b = links.node("b.md").unwrap();
+        assert!(links.graph().find_edge(a, b).is_some());
+    }
+
+    #[test]
+    fn test_pagerank_favours_linked_pages() {
+        let analytics = GraphAnalytics::compute(&test_graph(&[
+            ("hub.md", &[]),
+            ("a.md", &["hub"]),
+            ("b.md", &["hub"]),
+            ("c.md", &["hub", "a"]),
+        ]));
+
+        let total: f64 = analytics.pages.iter().map(|page| page.pagerank).sum();
+        assert!((total - 1.0).abs() < 1e-9);
+
+        let ranked: Vec<&str> = analytics.most_important(2).iter().map(|page| page.path.as_str()).collect();
+        assert_eq!(ranked, vec!["hub.md", "a.md"]);
+
+        let hub = analytics.get("hub.md").unwrap();
+        assert_eq!((hub.in_degree, hub.out_degree), (3, 0));
+    }
+
+    #[test]
+    fn test_betweenness_of_a_chain() {
+        let links = LinkGraph::from_graph(&test_graph(&[
+            ("a.md", &["b"]),
+            ("b.md", &["c"]),
+            ("c.md", &[]),
+        ]));
+
+        let betweenness = links.betweenness();
+        let b = links.node("b.md").unwrap().index();
+        // b lies on the only a -> c path, one of the 2 ordered pairs not involving it
+        assert_eq!(betweenness[b], 0.5);
+        assert_eq!(betweenness[links.node("a.md").unwrap().index()], 0.0);
+    }
+
+    #[test]
+    fn test_components_and_clusters() {
+        // Two triangles joined by a single link, plus an isolated page
+        let analytics = GraphAnalytics::compute(&test_graph(&[
+            ("a1.md", &["a2", "a3"]),
+            ("a2.md", &["a3"]),
+            ("a3.md", &["b1"]),
+            ("b1.md", &["b2", "b3"]),
+            ("b2.md", &["b3"]),
+            ("b3.md", &[]),
+            ("lonely.md", &[]),
+        ]));
+
+        assert_eq!(analytics.components, vec![
+            vec!["a1.md", "a2.md", "a3.md", "b1.md", "b2.md", "b3.md"],
+            vec!["lonely.md"],
+        ]);
+        assert_eq!(analytics.clusters, vec![
+            vec!["a1.md", "a2.md", "a3.md"],
+            vec!["b1.md", "b2.md", "b3.md"],
+            vec!["lonely.md"],
+        ]);
+    }
+
+    #[test]
+    fn test_empty_graph() {
+        let analytics = GraphAnalytics::compute(&Graph::new());
+        assert!(analytics.pages.is_empty());
+        assert!(analytics.components.is_empty());
+        assert!(analytics.most_important(5).is_empty());
+    }
+}
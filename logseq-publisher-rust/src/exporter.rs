@@ -1,6 +1,18 @@
+use crate::analytics::LinkGraph;
 use crate::graph::Graph;
+use crate::optimizer::{self, AssetManifest};
 use crate::parser::{Block, Page};
-use serde::{Deserialize, Serialize};
+use petgraph::graph::NodeIndex;
+use petgraph::visit::EdgeRef;
+use petgraph::Direction;
+use serde::Deserialize;
+
+/// Number of pages listed under "Most Important Pages" on the index
+const IMPORTANT_PAGE_COUNT: usize = 10;
+
+/// Graph view node radius range, in pixels
+const MIN_NODE_SIZE: f64 = 4.0;
+const MAX_NODE_SIZE: f64 = 16.0;
 
 #[derive(Debug, Deserialize)]
 pub struct ExportConfig {
@@ -8,11 +20,18 @@ pub struct ExportConfig {
     pub include_backlinks: bool,
     pub include_graph_view: bool,
     pub custom_css: Option<String>,
+    /// Manifest from `optimizer::publish_assets`, used to render responsive images
+    /// and to point asset links at their fingerprinted paths
+    #[serde(default)]
+    pub assets: Option<AssetManifest>,
 }
 
 /// Export graph to HTML
 pub fn export_to_html(graph: &Graph, config: &ExportConfig) -> Result<String, String> {
     let stats = graph.stats();
+    // Only PageRank is needed up front; clusters are left to the graph view
+    let links = LinkGraph::from_graph(graph);
+    let pagerank = links.pagerank();
 
     let mut html = String::from("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n");
     html.push_str("<meta charset=\"UTF-8\">\n");
@@ -23,13 +42,14 @@ pub fn export_to_html(graph: &Graph, config: &ExportConfig) -> Result<String, St
 
     // Header
     html.push_str("<header>\n");
-    html.push_str(&format!("<h1>Published Logseq Graph</h1>\n"));
+    html.push_str("<h1>Published Logseq Graph</h1>\n");
     html.push_str(&format!("<p>{} pages • {} blocks • {} links</p>\n",
         stats.page_count, stats.total_blocks, stats.total_links));
     html.push_str("</header>\n");
 
     // Main content
     html.push_str("<main>\n");
+    html.push_str(&render_important_pages(graph, &links, &pagerank));
     html.push_str("<div id=\"content\"></div>\n");
     html.push_str("</main>\n");
 
@@ -38,7 +58,7 @@ pub fn export_to_html(graph: &Graph, config: &ExportConfig) -> Result<String, St
     html.push_str("<p>Generated by Logseq Publisher • Built with Rust + WASM</p>\n");
     html.push_str("</footer>\n");
 
-    html.push_str(&generate_js(config));
+    html.push_str(&generate_js(config, graph, &links, &pagerank));
     html.push_str("</body>\n</html>");
 
     Ok(html)
@@ -72,7 +92,7 @@ pub fn export_page_to_html(page: &Page, backlinks: &[String], config: &ExportCon
     // Blocks
     html.push_str("<div class=\"blocks\">\n");
     for block in &page.blocks {
-        html.push_str(&render_block(block));
+        html.push_str(&render_block(block, config.assets.as_ref()));
     }
     html.push_str("</div>\n");
 
@@ -90,21 +110,82 @@ pub fn export_page_to_html(page: &Page, backlinks: &[String], config: &ExportCon
 
     html.push_str("</article>\n");
 
+    // Point links to assets (PDFs, raw `<img>`/`<a>` markup, ...) at their fingerprinted paths
+    match &config.assets {
+        Some(manifest) => optimizer::rewrite_asset_references(&html, manifest),
+        None => html,
+    }
+}
+
+/// Index section listing the pages with the highest PageRank
+fn render_important_pages(graph: &Graph, links: &LinkGraph, pagerank: &[f64]) -> String {
+    let mut nodes: Vec<NodeIndex> = links.graph().node_indices().collect();
+    if nodes.is_empty() {
+        return String::new();
+    }
+    // Ties broken by path, which is node order
+    nodes.sort_by(|a, b| pagerank[b.index()].total_cmp(&pagerank[a.index()]).then_with(|| a.cmp(b)));
+    nodes.truncate(IMPORTANT_PAGE_COUNT);
+
+    let mut html = String::from("<section class=\"important-pages\">\n");
+    html.push_str("<h2>Most Important Pages</h2>\n");
+    html.push_str("<ol>\n");
+    for node in nodes {
+        let path = links.path(node);
+        let title = graph.get_page(path).map_or("", |page| page.title.as_str());
+        let in_degree = links.graph().edges_directed(node, Direction::Incoming).count();
+        html.push_str(&format!("<li><a href=\"#{}\" class=\"wiki-link\">{}</a> <span class=\"link-count\">{} linked references</span></li>\n",
+            escape_html(path), escape_html(title), in_degree));
+    }
+    html.push_str("</ol>\n");
+    html.push_str("</section>\n");
+
     html
 }
 
-fn render_block(block: &Block) -> String {
+/// Graph view data: nodes sized by PageRank and colored by cluster, and the
+/// links between them
+fn graph_view_data(graph: &Graph, links: &LinkGraph, pagerank: &[f64]) -> serde_json::Value {
+    let max_rank = pagerank.iter().copied().fold(0.0, f64::max);
+    let clusters = links.clusters();
+
+    let nodes: Vec<serde_json::Value> = links.graph().node_indices()
+        .map(|node| {
+            let i = node.index();
+            let path = links.path(node);
+            let weight = if max_rank > 0.0 { pagerank[i] / max_rank } else { 0.0 };
+            let size = MIN_NODE_SIZE + (MAX_NODE_SIZE - MIN_NODE_SIZE) * weight.sqrt();
+            serde_json::json!({
+                "id": path,
+                "title": graph.get_page(path).map_or("", |page| page.title.as_str()),
+                "size": (size * 10.0).round() / 10.0,
+                "cluster": clusters[i],
+            })
+        })
+        .collect();
+
+    let edges: Vec<serde_json::Value> = links.graph().edge_references()
+        .map(|edge| serde_json::json!({
+            "source": links.path(edge.source()),
+            "target": links.path(edge.target()),
+        }))
+        .collect();
+
+    serde_json::json!({ "nodes": nodes, "links": edges })
+}
+
+fn render_block(block: &Block, assets: Option<&AssetManifest>) -> String {
     let mut html = String::new();
 
     html.push_str(&format!("<div class=\"block\" data-id=\"{}\" data-level=\"{}\">\n",
         block.id, block.level));
     html.push_str(&format!("<div class=\"block-content\">{}</div>\n",
-        render_markdown(&block.content)));
+        render_markdown(&block.content, assets)));
 
     if !block.children.is_empty() {
         html.push_str("<div class=\"block-children\">\n");
         for child in &block.children {
-            html.push_str(&render_block(child));
+            html.push_str(&render_block(child, assets));
         }
         html.push_str("</div>\n");
     }
@@ -114,10 +195,25 @@ fn render_block(block: &Block) -> String {
     html
 }
 
-fn render_markdown(content: &str) -> String {
+fn render_markdown(content: &str, assets: Option<&AssetManifest>) -> String {
+    // Convert images ![alt](src){:width 300} to responsive <img>/<picture> elements
+    // and links [text](url) to anchors, both stashed so the passes below leave
+    // their attributes alone
+    let mut stash = Stash::default();
+    let image_regex = regex::Regex::new(r"!\[([^\]]*)\]\(([^)\s]+)\)(\{[^}]*\})?").unwrap();
+    let with_images = image_regex.replace_all(content, |caps: &regex::Captures| {
+        let size = caps.get(3).map_or("", |m| m.as_str());
+        stash.push(render_image(&caps[2], &caps[1], size_attribute(size, "width"), size_attribute(size, "height"), assets))
+    });
+
+    let anchor_regex = regex::Regex::new(r"\[([^\[\]]+)\]\(([^)\s]+)\)").unwrap();
+    let with_anchors = anchor_regex.replace_all(&with_images, |caps: &regex::Captures| {
+        stash.push(format!("<a href=\"{}\">{}</a>", escape_html(&caps[2]), &caps[1]))
+    });
+
     // Convert wiki-links [[page]] to HTML links
     let link_regex = regex::Regex::new(r"\[\[([^\]]+)\]\]").unwrap();
-    let with_links = link_regex.replace_all(content, "<a href=\"#$1\" class=\"wiki-link\">$1</a>");
+    let with_links = link_regex.replace_all(&with_anchors, "<a href=\"#$1\" class=\"wiki-link\">$1</a>");
 
     // Convert tags #tag to styled spans
     let tag_regex = regex::Regex::new(r"#(\w+)").unwrap();
@@ -130,13 +226,133 @@ fn render_markdown(content: &str) -> String {
     let italic_regex = regex::Regex::new(r"\*([^*]+)\*").unwrap();
     let with_italic = italic_regex.replace_all(&with_bold, "<em>$1</em>");
 
-    with_italic.to_string()
+    stash.restore(&with_italic)
 }
 
-fn generate_css(config: &ExportConfig) -> String {
-    let mut css = String::from("<style>\n");
+/// Markup rendered ahead of the inline passes of `render_markdown`, kept out
+/// of their way behind placeholders until they are done
+#[derive(Default)]
+struct Stash(Vec<String>);
 
-    css.push_str(r#"
+impl Stash {
+    /// Stash `html`, returning the placeholder to put in its place
+    fn push(&mut self, html: String) -> String {
+        self.0.push(html);
+        format!("\u{E000}{}\u{E001}", self.0.len() - 1)
+    }
+
+    /// `content` with the placeholders replaced by the stashed markup
+    fn restore(&self, content: &str) -> String {
+        let placeholder_regex = regex::Regex::new("\u{E000}(\\d+)\u{E001}").unwrap();
+        placeholder_regex.replace_all(content, |caps: &regex::Captures| {
+            caps[1].parse::<usize>().ok()
+                .and_then(|index| self.0.get(index))
+                .cloned()
+                .unwrap_or_default()
+        }).into_owned()
+    }
+}
+
+/// Escape text for use in HTML content and attribute values
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Render an image, using the optimized variants from the manifest when available.
+///
+/// A `{:width N}` size renders the image N pixels wide; `width`/`height`
+/// attributes are always emitted when the dimensions are known, so the browser
+/// reserves space before the image loads.
+fn render_image(
+    src: &str,
+    alt: &str,
+    width: Option<u32>,
+    height: Option<u32>,
+    assets: Option<&AssetManifest>,
+) -> String {
+    let found = assets.and_then(|manifest| manifest.find_reference(src));
+    let intrinsic = found.and_then(|(asset, _)| asset.width.zip(asset.height));
+
+    // Display size: explicit sizes win, the other dimension follows the aspect ratio
+    let (display_width, display_height) = match (width, height, intrinsic) {
+        (Some(w), Some(h), _) => (Some(w), Some(h)),
+        (Some(w), None, Some((iw, ih))) => (Some(w), Some(scale(ih, w, iw))),
+        (None, Some(h), Some((iw, ih))) => (Some(scale(iw, h, ih)), Some(h)),
+        (None, None, Some((iw, ih))) => (Some(iw), Some(ih)),
+        (w, h, None) => (w, h),
+    };
+
+    let mut attributes = format!("alt=\"{}\"", escape_html(alt));
+    if let Some(w) = display_width {
+        attributes.push_str(&format!(" width=\"{}\"", w));
+    }
+    if let Some(h) = display_height {
+        attributes.push_str(&format!(" height=\"{}\"", h));
+    }
+    attributes.push_str(" loading=\"lazy\" decoding=\"async\"");
+
+    let Some((asset, prefix)) = found else {
+        return format!("<img src=\"{}\" {}>", escape_html(src), attributes);
+    };
+    let prefix = escape_html(prefix);
+
+    let sizes = match width {
+        Some(w) => format!("{}px", w),
+        None => "(max-width: 900px) 100vw, 900px".to_string(),
+    };
+    let srcset = |mime_type: &str, full: Option<&str>| {
+        let mut candidates: Vec<String> = asset.variants
+            .iter()
+            .filter(|variant| variant.mime_type == mime_type)
+            .map(|variant| format!("{}{} {}w", prefix, variant.path, variant.width))
+            .collect();
+        if let (Some(full), Some((iw, _))) = (full, intrinsic) {
+            candidates.push(format!("{}{} {}w", prefix, full, iw));
+        }
+        candidates.join(", ")
+    };
+
+    let original_mime = asset.variants
+        .iter()
+        .map(|variant| variant.mime_type.as_str())
+        .find(|mime_type| *mime_type != "image/webp");
+    let img_srcset = original_mime
+        .map(|mime_type| srcset(mime_type, Some(&asset.optimized_path)))
+        .unwrap_or_default();
+
+    let mut img = format!("<img src=\"{}{}\"", prefix, asset.optimized_path);
+    if !img_srcset.is_empty() {
+        img.push_str(&format!(" srcset=\"{}\" sizes=\"{}\"", img_srcset, sizes));
+    }
+    img.push_str(&format!(" {}>", attributes));
+
+    let webp_srcset = srcset("image/webp", None);
+    if webp_srcset.is_empty() {
+        img
+    } else {
+        format!("<picture><source type=\"image/webp\" srcset=\"{}\" sizes=\"{}\">{}</picture>",
+            webp_srcset, sizes, img)
+    }
+}
+
+/// Value of `:key N` in a Logseq image size such as `{:width 300, :height 200}`
+fn size_attribute(size: &str, key: &str) -> Option<u32> {
+    let pattern = format!(r":{}\s+(\d+)", key);
+    regex::Regex::new(&pattern).unwrap()
+        .captures(size)
+        .and_then(|caps| caps[1].parse().ok())
+}
+
+/// `value` scaled by `numerator / denominator`, rounded
+fn scale(value: u32, numerator: u32, denominator: u32) -> u32 {
+    (value as f64 * numerator as f64 / denominator.max(1) as f64).round() as u32
+}
+
+fn generate_css(config: &ExportConfig) -> String {
+    let mut css = String::from(r#"
 :root {
     --bg-color: #ffffff;
     --text-color: #2d3748;
@@ -206,6 +422,15 @@ h2 { font-size: 1.5rem; margin: 1.5rem 0 1rem; }
     font-size: 0.875rem;
 }
 
+.important-pages ol {
+    padding-left: 1.5rem;
+}
+
+.link-count {
+    color: #718096;
+    font-size: 0.875rem;
+}
+
 .backlinks {
     margin-top: 3rem;
     padding-top: 2rem;
@@ -226,14 +451,11 @@ footer {
         css.push_str(custom_css);
     }
 
-    css.push_str("</style>\n");
-    css
+    format!("<style>\n{}\n</style>\n", optimizer::minify_css(&css))
 }
 
-fn generate_js(config: &ExportConfig) -> String {
-    let mut js = String::from("<script>\n");
-
-    js.push_str(r#"
+fn generate_js(config: &ExportConfig, graph: &Graph, links: &LinkGraph, pagerank: &[f64]) -> String {
+    let mut js = String::from(r#"
 // Basic navigation
 document.addEventListener('click', (e) => {
     if (e.target.classList.contains('wiki-link')) {
@@ -249,11 +471,13 @@ console.log('Logseq Publisher initialized');
 "#);
 
     if config.include_graph_view {
+        // Escape `</` so page titles can't close the script element
+        let data = graph_view_data(graph, links, pagerank).to_string().replace("</", "<\\/");
+        js.push_str(&format!("const graphData = {};\n", data));
         js.push_str("// Graph view would be initialized here\n");
     }
 
-    js.push_str("</script>\n");
-    js
+    format!("<script>\n{}\n</script>\n", optimizer::minify_js(&js))
 }
 
 #[cfg(test)]
@@ -263,10 +487,142 @@ mod tests {
     #[test]
     fn test_markdown_rendering() {
         let content = "This is **bold** and *italic* with [[link]] and #tag";
-        let html = render_markdown(content);
+        let html = render_markdown(content, None);
         assert!(html.contains("<strong>bold</strong>"));
         assert!(html.contains("<em>italic</em>"));
         assert!(html.contains("wiki-link"));
         assert!(html.contains("class=\"tag\""));
     }
+
+    #[test]
+    fn test_inline_assets_are_minified() {
+        let config = ExportConfig {
+            theme: "default".to_string(),
+            include_backlinks: false,
+            include_graph_view: false,
+            custom_css: Some(".note::after { content: \"a  b\"; }".to_string()),
+            assets: None,
+        };
+
+        let css = generate_css(&config);
+        assert!(css.contains(".note::after{content:\"a  b\"}"));
+        assert!(css.contains("body{font-family:"));
+
+        let graph = Graph::new();
+        let links = LinkGraph::from_graph(&graph);
+        let js = generate_js(&config, &graph, &links, &links.pagerank());
+        assert!(js.contains("document.addEventListener('click',(e)=>{"));
+        assert!(!js.contains("// Basic navigation"));
+    }
+
+    #[test]
+    fn test_index_ranks_pages_and_sizes_graph_nodes() {
+        let mut graph = Graph::new();
+        for (path, links) in [("hub.md", vec![]), ("a.md", vec!["hub"]), ("b.md", vec!["hub", "a"])] {
+            let mut page = crate::parser::parse_logseq_page("", path).unwrap();
+            page.links = links.into_iter().map(String::from).collect();
+            graph.add_page(page);
+        }
+        let config = ExportConfig {
+            theme: "default".to_string(),
+            include_backlinks: false,
+            include_graph_view: true,
+            custom_css: None,
+            assets: None,
+        };
+
+        let html = export_to_html(&graph, &config).unwrap();
+        let section = &html[html.find("<h2>Most Important Pages</h2>").unwrap()..];
+        let hub = section.find("href=\"#hub.md\"").unwrap();
+        let a = section.find("href=\"#a.md\"").unwrap();
+        let b = section.find("href=\"#b.md\"").unwrap();
+        assert!(hub < a && a < b);
+
+        assert!(html.contains("{\"cluster\":0,\"id\":\"hub.md\",\"size\":16.0,\"title\":\"hub\"}"));
+        assert!(html.contains("{\"source\":\"b.md\",\"target\":\"hub.md\"}"));
+    }
+
+    #[test]
+    fn test_asset_links_are_rewritten() {
+        let manifest: AssetManifest = serde_json::from_str(r#"{
+            "files": [{
+                "original_path": "assets/spec.pdf",
+                "optimized_path": "assets/spec.abc.pdf",
+                "hash": "abc",
+                "original_size": 9000,
+                "optimized_size": 9000,
+                "variants": []
+            }],
+            "total_size": 9000,
+            "optimized_size": 9000
+        }"#).unwrap();
+        let config = ExportConfig {
+            theme: "default".to_string(),
+            include_backlinks: false,
+            include_graph_view: false,
+            custom_css: None,
+            assets: Some(manifest),
+        };
+
+        let page = crate::parser::parse_logseq_page(
+            "- See the [spec](../assets/spec.pdf#page=2) and <a href=\"../assets/spec.pdf\">again</a>",
+            "pages/doc.md",
+        ).unwrap();
+        let html = export_page_to_html(&page, &[], &config);
+        assert!(html.contains("<a href=\"../assets/spec.abc.pdf#page=2\">spec</a>"));
+        assert!(html.contains("<a href=\"../assets/spec.abc.pdf\">again</a>"));
+    }
+
+    #[test]
+    fn test_important_pages_are_escaped() {
+        let mut graph = Graph::new();
+        graph.add_page(crate::parser::parse_logseq_page("", "x<y.md").unwrap());
+        let links = LinkGraph::from_graph(&graph);
+
+        let html = render_important_pages(&graph, &links, &links.pagerank());
+        assert!(html.contains("<a href=\"#x&lt;y.md\" class=\"wiki-link\">x&lt;y</a>"));
+    }
+
+    #[test]
+    fn test_image_size_syntax() {
+        let html = render_markdown("![diagram](../assets/d.png){:height 120, :width 300}", None);
+        assert_eq!(html, "<img src=\"../assets/d.png\" alt=\"diagram\" width=\"300\" height=\"120\" loading=\"lazy\" decoding=\"async\">");
+    }
+
+    #[test]
+    fn test_image_attributes_are_escaped() {
+        let html = render_markdown("![a \"b\" #c *d*](../assets/*e*.png) and *f*", None);
+        assert_eq!(html, "<img src=\"../assets/*e*.png\" alt=\"a &quot;b&quot; #c *d*\" loading=\"lazy\" decoding=\"async\"> and <em>f</em>");
+    }
+
+    #[test]
+    fn test_responsive_images() {
+        let manifest: AssetManifest = serde_json::from_str(r#"{
+            "files": [{
+                "original_path": "assets/shot.png",
+                "optimized_path": "assets/shot.abc.png",
+                "hash": "abc",
+                "original_size": 9000,
+                "optimized_size": 8000,
+                "width": 1200,
+                "height": 600,
+                "variants": [
+                    {"path": "assets/shot.abc-480.png", "width": 480, "height": 240, "mime_type": "image/png", "size": 2000},
+                    {"path": "assets/shot.abc-480.webp", "width": 480, "height": 240, "mime_type": "image/webp", "size": 1000},
+                    {"path": "assets/shot.abc.webp", "width": 1200, "height": 600, "mime_type": "image/webp", "size": 5000}
+                ]
+            }],
+            "total_size": 9000,
+            "optimized_size": 8000
+        }"#).unwrap();
+
+        let html = render_markdown("![shot](../assets/shot.png){:width 300}", Some(&manifest));
+        assert!(html.starts_with("<picture><source type=\"image/webp\" srcset=\"../assets/shot.abc-480.webp 480w, ../assets/shot.abc.webp 1200w\" sizes=\"300px\">"));
+        assert!(html.contains("<img src=\"../assets/shot.abc.png\" srcset=\"../assets/shot.abc-480.png 480w, ../assets/shot.abc.png 1200w\" sizes=\"300px\""));
+        assert!(html.contains("width=\"300\" height=\"150\""));
+
+        let html = render_markdown("![shot](assets/shot.png)", Some(&manifest));
+        assert!(html.contains("width=\"1200\" height=\"600\""));
+        assert!(html.contains("sizes=\"(max-width: 900px) 100vw, 900px\""));
+    }
 }
@@ -1,4 +1,6 @@
+use crate::analytics::LinkGraph;
 use crate::parser::Page;
+use crate::query::{self, LinkDirection};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -42,6 +44,11 @@ impl Graph {
         self.pages.get(path)
     }
 
+    /// All pages, in no particular order
+    pub fn pages(&self) -> impl Iterator<Item = &Page> {
+        self.pages.values()
+    }
+
     pub fn get_backlinks(&self, path: &str) -> Vec<String> {
         self.backlinks
             .get(path)
@@ -77,24 +84,18 @@ impl Graph {
         }
     }
 
+    /// `start_path` followed by the pages within `max_depth` outgoing links of
+    /// it, nearest first. See `query` for more general link queries.
     pub fn traverse_from(&self, start_path: &str, max_depth: usize) -> Vec<String> {
-        let mut visited = Vec::new();
-        self.traverse_recursive(start_path, 0, max_depth, &mut visited);
-        visited
-    }
-
-    fn traverse_recursive(&self, path: &str, depth: usize, max_depth: usize, visited: &mut Vec<String>) {
-        if depth > max_depth || visited.contains(&path.to_string()) {
-            return;
+        if !self.pages.contains_key(start_path) {
+            return Vec::new();
         }
 
-        visited.push(path.to_string());
-
-        if let Some(page) = self.pages.get(path) {
-            for link in &page.links {
-                self.traverse_recursive(link, depth + 1, max_depth, visited);
-            }
-        }
+        let links = LinkGraph::from_graph(self);
+        let found = query::neighbourhood(&links, start_path, max_depth, LinkDirection::Outgoing);
+        std::iter::once(start_path.to_string())
+            .chain(found.into_iter().map(|neighbour| neighbour.path))
+            .collect()
     }
 }
 
@@ -102,6 +103,24 @@ fn count_blocks(blocks: &[crate::parser::Block]) -> usize {
     blocks.iter().map(|b| 1 + count_blocks(&b.children)).sum()
 }
 
+/// A graph of content-less pages with the given paths and outgoing links, each titled
+/// after its path without `.md`
+#[cfg(test)]
+pub(crate) fn test_graph(pages: &[(&str, &[&str])]) -> Graph {
+    let mut graph = Graph::new();
+    for (path, links) in pages {
+        graph.add_page(Page {
+            path: path.to_string(),
+            title: path.trim_end_matches(".md").to_string(),
+            properties: HashMap::new(),
+            blocks: Vec::new(),
+            tags: Vec::new(),
+            links: links.iter().map(|link| link.to_string()).collect(),
+        });
+    }
+    graph
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -114,6 +133,26 @@ mod tests {
         assert_eq!(graph.page_count(), 0);
     }
 
+    #[test]
+    fn test_traverse_from_stops_at_max_depth() {
+        let mut graph = Graph::new();
+        for i in 0..5 {
+            graph.add_page(Page {
+                path: format!("page{}.md", i),
+                title: format!("page{}", i),
+                properties: HashMap::new(),
+                blocks: Vec::new(),
+                tags: Vec::new(),
+                // A chain that loops back to the start
+                links: vec![format!("page{}.md", (i + 1) % 5)],
+            });
+        }
+
+        assert_eq!(graph.traverse_from("page0.md", 2), vec!["page0.md", "page1.md", "page2.md"]);
+        assert_eq!(graph.traverse_from("page0.md", 100).len(), 5);
+        assert!(graph.traverse_from("missing.md", 2).is_empty());
+    }
+
     #[test]
     fn test_add_page_and_backlinks() {
         let mut graph = Graph::new();
@@ -6,6 +6,8 @@ pub mod parser;
 pub mod graph;
 pub mod optimizer;
 pub mod exporter;
+pub mod analytics;
+pub mod query;
 
 #[wasm_bindgen]
 extern "C" {
@@ -65,6 +67,41 @@ impl LogseqPublisher {
             .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
     }
 
+    /// Link analytics: PageRank, degree, betweenness, components and clusters
+    #[wasm_bindgen]
+    pub fn analytics(&self) -> Result<String, JsValue> {
+        let analytics = analytics::GraphAnalytics::compute(&self.graph);
+        serde_json::to_string(&analytics)
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
+
+    /// Shortest chain of links between two pages, as page paths, or `null`
+    #[wasm_bindgen]
+    pub fn shortest_path(&self, from: &str, to: &str) -> Result<String, JsValue> {
+        let links = analytics::LinkGraph::from_graph(&self.graph);
+        serde_json::to_string(&query::shortest_path(&links, from, to))
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
+
+    /// Pages within `hops` links of a page; `direction` is "outgoing",
+    /// "incoming" or "both"
+    #[wasm_bindgen]
+    pub fn neighbourhood(&self, path: &str, hops: usize, direction: &str) -> Result<String, JsValue> {
+        let direction: query::LinkDirection = direction.parse()
+            .map_err(|e: String| JsValue::from_str(&e))?;
+        let links = analytics::LinkGraph::from_graph(&self.graph);
+        serde_json::to_string(&query::neighbourhood(&links, path, hops, direction))
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
+
+    /// All pages reachable from a page by following links
+    #[wasm_bindgen]
+    pub fn reachable_from(&self, root: &str) -> Result<String, JsValue> {
+        let links = analytics::LinkGraph::from_graph(&self.graph);
+        serde_json::to_string(&query::reachable_from(&links, root))
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
+
     /// Export to HTML
     #[wasm_bindgen]
     pub fn export_html(&self, config_json: &str) -> Result<String, JsValue> {
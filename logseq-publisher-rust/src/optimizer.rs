@@ -1,6 +1,22 @@
+use image::codecs::gif::GifDecoder;
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::{CompressionType, FilterType as PngFilter, PngEncoder};
+use image::codecs::webp::WebPEncoder;
+use image::imageops::FilterType;
+use image::metadata::Orientation;
+use image::{AnimationDecoder, DynamicImage, GenericImageView, ImageDecoder, ImageFormat, ImageReader};
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 use sha2::{Sha256, Digest};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fs;
+use std::io::Cursor;
+use std::path::Path;
 
+pub use logseq_minify::{minify_css, minify_js};
+
+/// Optimized assets with their fingerprinted paths and true byte sizes
 #[derive(Debug, Serialize, Deserialize)]
 pub struct AssetManifest {
     pub files: Vec<OptimizedAsset>,
@@ -8,82 +24,444 @@ pub struct AssetManifest {
     pub optimized_size: usize,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl AssetManifest {
+    pub fn new(files: Vec<OptimizedAsset>) -> Self {
+        let total_size = files.iter().map(|asset| asset.original_size).sum();
+        let optimized_size = files.iter().map(|asset| asset.optimized_size).sum();
+
+        Self { files, total_size, optimized_size }
+    }
+
+    /// Manifest entry for an original asset path
+    pub fn get(&self, original_path: &str) -> Option<&OptimizedAsset> {
+        self.files.iter().find(|asset| asset.original_path == original_path)
+    }
+
+    /// Manifest entry for a reference such as `../assets/image.png`, with the
+    /// prefix that locates the output root from the referencing page
+    pub fn find_reference<'a>(&self, reference: &'a str) -> Option<(&OptimizedAsset, &'a str)> {
+        self.files
+            .iter()
+            .filter(|asset| !asset.original_path.is_empty())
+            .filter_map(|asset| {
+                let prefix = reference.strip_suffix(asset.original_path.as_str())?;
+                (prefix.is_empty() || prefix.ends_with('/')).then_some((asset, prefix))
+            })
+            .max_by_key(|(asset, _)| asset.original_path.len())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OptimizedAsset {
     pub original_path: String,
     pub optimized_path: String,
     pub hash: String,
     pub original_size: usize,
     pub optimized_size: usize,
+    /// Intrinsic dimensions, for images
+    #[serde(default)]
+    pub width: Option<u32>,
+    #[serde(default)]
+    pub height: Option<u32>,
+    /// Downscaled and alternative-format copies, for images
+    #[serde(default)]
+    pub variants: Vec<ImageVariant>,
+}
+
+/// A resized or re-encoded copy of an image
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageVariant {
+    pub path: String,
+    pub width: u32,
+    pub height: u32,
+    pub mime_type: String,
+    pub size: usize,
+}
+
+/// Files produced by optimizing one asset
+#[derive(Debug)]
+pub struct AssetOutput {
+    pub asset: OptimizedAsset,
+    /// Output paths and contents: the optimized asset followed by its variants
+    pub files: Vec<(String, Vec<u8>)>,
+}
+
+/// Settings for image optimization
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageOptions {
+    /// Widths of the downscaled variants; widths at or above the original are skipped
+    #[serde(default = "default_image_widths")]
+    pub widths: Vec<u32>,
+    /// Also encode WebP copies, kept only where smaller than the original format
+    #[serde(default = "default_webp")]
+    pub webp: bool,
+    #[serde(default = "default_jpeg_quality")]
+    pub jpeg_quality: u8,
 }
 
-/// Optimize assets (images, CSS, JS)
+impl Default for ImageOptions {
+    fn default() -> Self {
+        Self {
+            widths: default_image_widths(),
+            webp: default_webp(),
+            jpeg_quality: default_jpeg_quality(),
+        }
+    }
+}
+
+fn default_image_widths() -> Vec<u32> {
+    vec![480, 960, 1600]
+}
+
+fn default_webp() -> bool {
+    true
+}
+
+fn default_jpeg_quality() -> u8 {
+    80
+}
+
+/// Optimize assets (images, CSS, JS), reading each path from disk
 pub fn optimize_assets(asset_paths: &[String]) -> Result<AssetManifest, String> {
     let mut optimized_assets = Vec::new();
-    let mut total_size = 0;
-    let mut optimized_size = 0;
+
+    let options = ImageOptions::default();
 
     for path in asset_paths {
-        let asset = optimize_single_asset(path)?;
-        total_size += asset.original_size;
-        optimized_size += asset.optimized_size;
-        optimized_assets.push(asset);
+        let bytes = read_asset(Path::new(path), path)?;
+        optimized_assets.push(optimize_asset(path, &bytes, &options).asset);
     }
 
-    Ok(AssetManifest {
-        files: optimized_assets,
-        total_size,
-        optimized_size,
-    })
+    Ok(AssetManifest::new(optimized_assets))
 }
 
-fn optimize_single_asset(path: &str) -> Result<OptimizedAsset, String> {
-    // In a real implementation, this would:
-    // - Compress images (WebP, AVIF)
-    // - Minify CSS/JS
-    // - Generate content hashes
-    // - Create responsive image variants
+/// Optimize the assets at `asset_paths` under `input_dir` and write them to
+/// `output_dir` under their fingerprinted names.
+///
+/// CSS is optimized after the other assets so its `url(...)` references point
+/// at fingerprinted files, and its own hash covers the rewritten content.
+pub fn publish_assets(
+    input_dir: &Path,
+    output_dir: &Path,
+    asset_paths: &[String],
+    options: &ImageOptions,
+) -> Result<AssetManifest, String> {
+    let (stylesheets, others): (Vec<&String>, Vec<&String>) = asset_paths
+        .iter()
+        .partition(|path| extension(path).eq_ignore_ascii_case("css"));
 
-    let hash = generate_hash(path);
-    let extension = path.split('.').last().unwrap_or("");
+    let mut optimized_assets = Vec::new();
+    let mut outputs = Vec::new();
 
-    // Simulate optimization
-    let original_size = path.len() * 100; // Mock size
-    let optimized_size = (original_size as f64 * 0.7) as usize; // 30% reduction
+    for path in others {
+        let bytes = read_asset(&input_dir.join(path), path)?;
+        let output = optimize_asset(path, &bytes, options);
+        outputs.extend(output.files);
+        optimized_assets.push(output.asset);
+    }
+
+    for path in stylesheets {
+        let bytes = read_asset(&input_dir.join(path), path)?;
+        let css = String::from_utf8_lossy(&bytes);
+        let referenced = relative_to_dir(&optimized_assets, path);
+        let rewritten = rewrite_asset_references(&css, &referenced);
+        let mut output = optimize_asset(path, rewritten.as_bytes(), options);
+        output.asset.original_size = bytes.len();
+        outputs.extend(output.files);
+        optimized_assets.push(output.asset);
+    }
 
-    Ok(OptimizedAsset {
+    for (path, bytes) in outputs {
+        let target = output_dir.join(&path);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        fs::write(&target, bytes)
+            .map_err(|e| format!("Failed to write asset {}: {}", path, e))?;
+    }
+
+    Ok(AssetManifest::new(optimized_assets))
+}
+
+/// Optimize one asset's contents, returning its manifest entry and output files.
+///
+/// The fingerprint is taken from the optimized bytes, so it changes exactly when
+/// the served file does.
+pub fn optimize_asset(path: &str, bytes: &[u8], options: &ImageOptions) -> AssetOutput {
+    let extension = extension(path).to_ascii_lowercase();
+    let is_image = matches!(extension.as_str(), "png" | "jpg" | "jpeg" | "gif" | "webp");
+    if is_image {
+        if let Some(output) = optimize_image(path, bytes, options) {
+            return output;
+        }
+    }
+
+    let optimized = match extension.as_str() {
+        "css" => minify_css(&String::from_utf8_lossy(bytes)).into_bytes(),
+        "js" | "mjs" => minify_js(&String::from_utf8_lossy(bytes)).into_bytes(),
+        _ => bytes.to_vec(),
+    };
+    // Never serve a larger file than the original
+    let optimized = if optimized.len() < bytes.len() { optimized } else { bytes.to_vec() };
+
+    // Images that are passed through (e.g. animations) still report their size
+    let dimensions = if is_image { image_dimensions(bytes) } else { None };
+
+    let hash = generate_hash(&optimized);
+    let asset = OptimizedAsset {
         original_path: path.to_string(),
-        optimized_path: format!("{}.{}.{}", path.trim_end_matches(extension), hash, extension),
+        optimized_path: fingerprinted_path(path, &hash),
         hash,
-        original_size,
-        optimized_size,
-    })
+        original_size: bytes.len(),
+        optimized_size: optimized.len(),
+        width: dimensions.map(|(width, _)| width),
+        height: dimensions.map(|(_, height)| height),
+        variants: Vec::new(),
+    };
+
+    AssetOutput {
+        files: vec![(asset.optimized_path.clone(), optimized)],
+        asset,
+    }
 }
 
-fn generate_hash(content: &str) -> String {
-    let mut hasher = Sha256::new();
-    hasher.update(content.as_bytes());
-    format!("{:x}", hasher.finalize())[..8].to_string()
+/// Re-encode a PNG, JPEG, static GIF or WebP image without metadata, plus
+/// downscaled and WebP variants.
+///
+/// Images are always re-encoded, even when that grows the file, so EXIF data
+/// such as GPS coordinates is never published. Returns `None` for animated GIFs
+/// and images that fail to decode, which are passed through unchanged.
+fn optimize_image(path: &str, bytes: &[u8], options: &ImageOptions) -> Option<AssetOutput> {
+    let format = ImageReader::new(Cursor::new(bytes)).with_guessed_format().ok()?.format()?;
+    if !matches!(format, ImageFormat::Png | ImageFormat::Jpeg | ImageFormat::Gif | ImageFormat::WebP) {
+        return None;
+    }
+    if format == ImageFormat::Gif && is_animated_gif(bytes) {
+        return None;
+    }
+
+    let image = decode_image(bytes)?;
+    let (width, height) = image.dimensions();
+    let main = encode_image(&image, format, options)?;
+    let hash = generate_hash(&main);
+    let optimized_path = fingerprinted_path(path, &hash);
+    let stem = optimized_path.trim_end_matches(extension(&optimized_path)).trim_end_matches('.');
+
+    let mut widths: Vec<u32> = options.widths
+        .iter()
+        .copied()
+        .filter(|w| *w > 0 && *w < width)
+        .collect();
+    widths.sort_unstable();
+    widths.dedup();
+    widths.push(width);
+
+    let mut variants = Vec::new();
+    let mut files = Vec::new();
+    for target_width in widths {
+        let resized;
+        let (variant, variant_size) = if target_width == width {
+            (&image, main.len())
+        } else {
+            let target_height = ((height as f64 * target_width as f64 / width as f64).round() as u32).max(1);
+            resized = image.resize_exact(target_width, target_height, FilterType::Lanczos3);
+            let encoded = encode_image(&resized, format, options)?;
+            let variant_path = format!("{}-{}.{}", stem, target_width, extension(path));
+            variants.push(image_variant(&variant_path, &resized, format, encoded.len()));
+            let size = encoded.len();
+            files.push((variant_path, encoded));
+            (&resized, size)
+        };
+
+        if options.webp && format != ImageFormat::WebP {
+            if let Some(webp) = encode_image(variant, ImageFormat::WebP, options) {
+                if webp.len() < variant_size {
+                    let webp_path = if target_width == width {
+                        format!("{}.webp", stem)
+                    } else {
+                        format!("{}-{}.webp", stem, target_width)
+                    };
+                    variants.push(image_variant(&webp_path, variant, ImageFormat::WebP, webp.len()));
+                    files.push((webp_path, webp));
+                }
+            }
+        }
+    }
+
+    let asset = OptimizedAsset {
+        original_path: path.to_string(),
+        optimized_path: optimized_path.clone(),
+        hash,
+        original_size: bytes.len(),
+        optimized_size: main.len(),
+        width: Some(width),
+        height: Some(height),
+        variants,
+    };
+    files.insert(0, (optimized_path, main));
+
+    Some(AssetOutput { asset, files })
 }
 
-/// Minify CSS
-pub fn minify_css(css: &str) -> String {
-    css.lines()
-        .map(|line| line.trim())
-        .filter(|line| !line.is_empty())
-        .collect::<Vec<_>>()
-        .join("")
-        .replace("  ", " ")
+/// Decode an image, applying its EXIF orientation since the metadata is dropped
+fn decode_image(bytes: &[u8]) -> Option<DynamicImage> {
+    let mut decoder = ImageReader::new(Cursor::new(bytes))
+        .with_guessed_format()
+        .ok()?
+        .into_decoder()
+        .ok()?;
+    // An unreadable orientation still gets the image re-encoded, so its EXIF is dropped either way
+    let orientation = decoder.orientation().unwrap_or(Orientation::NoTransforms);
+    let mut image = DynamicImage::from_decoder(decoder).ok()?;
+    image.apply_orientation(orientation);
+    Some(image)
 }
 
-/// Minify JavaScript
-pub fn minify_js(js: &str) -> String {
-    // Basic minification - in production use proper minifier
-    js.lines()
-        .map(|line| line.trim())
-        .filter(|line| !line.is_empty() && !line.starts_with("//"))
+fn encode_image(image: &DynamicImage, format: ImageFormat, options: &ImageOptions) -> Option<Vec<u8>> {
+    let mut bytes = Vec::new();
+    let result = match format {
+        ImageFormat::Jpeg => {
+            let encoder = JpegEncoder::new_with_quality(&mut bytes, options.jpeg_quality);
+            DynamicImage::ImageRgb8(image.to_rgb8()).write_with_encoder(encoder)
+        }
+        ImageFormat::Png => {
+            let encoder = PngEncoder::new_with_quality(&mut bytes, CompressionType::Best, PngFilter::Adaptive);
+            image.write_with_encoder(encoder)
+        }
+        ImageFormat::WebP => {
+            let encoder = WebPEncoder::new_lossless(&mut bytes);
+            if image.color().has_alpha() {
+                DynamicImage::ImageRgba8(image.to_rgba8()).write_with_encoder(encoder)
+            } else {
+                DynamicImage::ImageRgb8(image.to_rgb8()).write_with_encoder(encoder)
+            }
+        }
+        _ => DynamicImage::ImageRgba8(image.to_rgba8()).write_to(&mut Cursor::new(&mut bytes), format),
+    };
+
+    result.ok().map(|_| bytes)
+}
+
+fn image_variant(path: &str, image: &DynamicImage, format: ImageFormat, size: usize) -> ImageVariant {
+    ImageVariant {
+        path: path.to_string(),
+        width: image.width(),
+        height: image.height(),
+        mime_type: format.to_mime_type().to_string(),
+        size,
+    }
+}
+
+fn is_animated_gif(bytes: &[u8]) -> bool {
+    GifDecoder::new(Cursor::new(bytes))
+        .map(|decoder| decoder.into_frames().take(2).count() > 1)
+        .unwrap_or(false)
+}
+
+fn image_dimensions(bytes: &[u8]) -> Option<(u32, u32)> {
+    ImageReader::new(Cursor::new(bytes))
+        .with_guessed_format()
+        .ok()?
+        .into_dimensions()
+        .ok()
+}
+
+/// Replace references to original asset paths with their fingerprinted paths.
+///
+/// A reference matches when it ends with an asset path and is delimited like a
+/// URL in HTML attributes, `srcset` lists or CSS `url(...)`, so relative forms
+/// such as `../assets/image.png` are rewritten as well.
+pub fn rewrite_asset_references(content: &str, manifest: &AssetManifest) -> String {
+    let mut assets: Vec<&OptimizedAsset> = manifest.files
+        .iter()
+        .filter(|asset| !asset.original_path.is_empty())
+        .collect();
+    if assets.is_empty() {
+        return content.to_string();
+    }
+    // Longest paths first so `a/b.png` wins over `b.png`
+    assets.sort_by_key(|asset| Reverse(asset.original_path.len()));
+
+    let pattern = assets
+        .iter()
+        .map(|asset| regex::escape(&asset.original_path))
         .collect::<Vec<_>>()
-        .join("")
+        .join("|");
+    let path_regex = Regex::new(&pattern).unwrap();
+    let by_path: HashMap<&str, &str> = assets
+        .iter()
+        .map(|asset| (asset.original_path.as_str(), asset.optimized_path.as_str()))
+        .collect();
+
+    let is_start = |c: char| matches!(c, '"' | '\'' | '(' | '=' | ',' | '/') || c.is_whitespace();
+    let is_end = |c: char| matches!(c, '"' | '\'' | ')' | '?' | '#' | ',') || c.is_whitespace();
+
+    let mut result = String::with_capacity(content.len());
+    let mut last = 0;
+    for m in path_regex.find_iter(content) {
+        let starts = content[..m.start()].chars().next_back().is_none_or(is_start);
+        let ends = content[m.end()..].chars().next().is_none_or(is_end);
+        if starts && ends {
+            result.push_str(&content[last..m.start()]);
+            result.push_str(by_path[m.as_str()]);
+            last = m.end();
+        }
+    }
+    result.push_str(&content[last..]);
+    result
+}
+
+/// Manifest of `assets` as referenced from the file at `path`: assets in its
+/// directory or below are addressed relative to that directory
+fn relative_to_dir(assets: &[OptimizedAsset], path: &str) -> AssetManifest {
+    let dir = match path.rsplit_once('/') {
+        Some((dir, _)) => format!("{}/", dir),
+        None => String::new(),
+    };
+
+    let relative = assets
+        .iter()
+        .map(|asset| match (asset.original_path.strip_prefix(&dir), asset.optimized_path.strip_prefix(&dir)) {
+            (Some(original), Some(optimized)) => OptimizedAsset {
+                original_path: original.to_string(),
+                optimized_path: optimized.to_string(),
+                ..asset.clone()
+            },
+            _ => asset.clone(),
+        })
+        .collect();
+
+    AssetManifest::new(relative)
+}
+
+fn read_asset(file: &Path, path: &str) -> Result<Vec<u8>, String> {
+    fs::read(file).map_err(|e| format!("Failed to read asset {}: {}", path, e))
+}
+
+fn extension(path: &str) -> &str {
+    let file_name = path.rsplit(['/', '\\']).next().unwrap_or(path);
+    match file_name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => extension,
+        _ => "",
+    }
+}
+
+/// `dir/name.ext` becomes `dir/name.<hash>.ext`
+fn fingerprinted_path(path: &str, hash: &str) -> String {
+    let extension = extension(path);
+    if extension.is_empty() {
+        format!("{}.{}", path, hash)
+    } else {
+        let stem = &path[..path.len() - extension.len() - 1];
+        format!("{}.{}.{}", stem, hash, extension)
+    }
+}
+
+fn generate_hash(content: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(content);
+    format!("{:x}", hasher.finalize())[..8].to_string()
 }
 
 #[cfg(test)]
@@ -92,26 +470,144 @@ mod tests {
 
     #[test]
     fn test_asset_optimization() {
+        let dir = tempfile::tempdir().unwrap();
+        let image = dir.path().join("image.png");
+        let styles = dir.path().join("styles.css");
+        fs::write(&image, [0x89, b'P', b'N', b'G', 0, 1, 2, 3]).unwrap();
+        fs::write(&styles, "body {\n    margin: 0;\n}\n").unwrap();
+
         let assets = vec![
-            "image.png".to_string(),
-            "styles.css".to_string(),
+            image.to_string_lossy().to_string(),
+            styles.to_string_lossy().to_string(),
         ];
 
         let manifest = optimize_assets(&assets).unwrap();
         assert_eq!(manifest.files.len(), 2);
+        assert_eq!(manifest.files[0].original_size, 8);
+        assert_eq!(manifest.files[0].optimized_size, 8);
+        assert!(manifest.files[1].optimized_size < manifest.files[1].original_size);
+        assert_eq!(manifest.total_size, 8 + 24);
         assert!(manifest.optimized_size < manifest.total_size);
+
+        assert!(optimize_assets(&["missing/image.png".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_fingerprinting_is_content_based() {
+        let options = ImageOptions::default();
+        let a = optimize_asset("assets/logo.svg", b"one", &options).asset;
+        let b = optimize_asset("assets/copy.svg", b"one", &options).asset;
+        let c = optimize_asset("assets/logo.svg", b"two", &options).asset;
+
+        assert_eq!(a.hash, b.hash);
+        assert_ne!(a.hash, c.hash);
+        assert_eq!(a.optimized_path, format!("assets/logo.{}.svg", a.hash));
+        assert_eq!(fingerprinted_path("assets/.nojekyll", "abc"), "assets/.nojekyll.abc");
+        assert_eq!(fingerprinted_path("v1.2/LICENSE", "abc"), "v1.2/LICENSE.abc");
+    }
+
+    #[test]
+    fn test_publish_assets_rewrites_references() {
+        let input = tempfile::tempdir().unwrap();
+        let output = tempfile::tempdir().unwrap();
+        fs::create_dir_all(input.path().join("assets")).unwrap();
+        fs::write(input.path().join("assets/bg.svg"), b"<svg/>").unwrap();
+        fs::write(input.path().join("assets/site.css"), "body { background: url(bg.svg); }").unwrap();
+
+        let paths = vec!["assets/bg.svg".to_string(), "assets/site.css".to_string()];
+        let manifest = publish_assets(input.path(), output.path(), &paths, &ImageOptions::default()).unwrap();
+
+        let bg = manifest.get("assets/bg.svg").unwrap();
+        let css = manifest.get("assets/site.css").unwrap();
+        assert_eq!(fs::read(output.path().join(&bg.optimized_path)).unwrap(), b"<svg/>");
+
+        let written = fs::read_to_string(output.path().join(&css.optimized_path)).unwrap();
+        let bg_name = bg.optimized_path.trim_start_matches("assets/");
+        assert!(written.contains(&format!("url({})", bg_name)), "{}", written);
+        assert_eq!(css.optimized_size, written.len());
+    }
+
+    #[test]
+    fn test_rewrite_asset_references() {
+        let asset = optimize_asset("assets/img.png", b"img", &ImageOptions::default()).asset;
+        let manifest = AssetManifest::new(vec![asset.clone()]);
+        let new_path = &asset.optimized_path;
+
+        let html = r#"<img src="../assets/img.png" srcset="/assets/img.png 1x,assets/img.png 2x"><img src="assets/img.png.bak">"#;
+        let rewritten = rewrite_asset_references(html, &manifest);
+        assert!(rewritten.contains(&format!("src=\"../{}\"", new_path)));
+        assert!(rewritten.contains(&format!("/{} 1x,{} 2x", new_path, new_path)));
+        assert!(rewritten.contains("assets/img.png.bak"));
+        assert!(!rewritten.contains("myassets/img"));
+    }
+
+    fn test_png(width: u32, height: u32) -> Vec<u8> {
+        let image = image::RgbImage::from_fn(width, height, |x, y| image::Rgb([(x % 256) as u8, (y % 256) as u8, 128]));
+        let mut bytes = Vec::new();
+        DynamicImage::ImageRgb8(image)
+            .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+            .unwrap();
+        bytes
     }
 
     #[test]
-    fn test_css_minification() {
-        let css = r#"
-        body {
-            margin: 0;
-            padding: 0;
+    fn test_image_variants() {
+        let options = ImageOptions {
+            widths: vec![100, 50, 400],
+            webp: true,
+            jpeg_quality: 80,
+        };
+        let output = optimize_asset("assets/shot.png", &test_png(200, 100), &options);
+        let asset = &output.asset;
+
+        assert_eq!((asset.width, asset.height), (Some(200), Some(100)));
+        assert_eq!(output.files[0].0, asset.optimized_path);
+        assert_eq!(output.files.len(), 1 + asset.variants.len());
+
+        let png_widths: Vec<u32> = asset.variants
+            .iter()
+            .filter(|variant| variant.mime_type == "image/png")
+            .map(|variant| variant.width)
+            .collect();
+        assert_eq!(png_widths, vec![50, 100]);
+
+        let half = asset.variants.iter().find(|variant| variant.width == 100).unwrap();
+        assert_eq!(half.height, 50);
+        assert_eq!(half.path, format!("assets/shot.{}-100.png", asset.hash));
+
+        for (path, bytes) in &output.files {
+            let decoded = image::load_from_memory(bytes).unwrap();
+            let expected = asset.variants.iter().find(|variant| &variant.path == path)
+                .map_or(200, |variant| variant.width);
+            assert_eq!(decoded.width(), expected, "{}", path);
         }
-        "#;
+    }
+
+    #[test]
+    fn test_jpeg_metadata_is_stripped() {
+        let image = image::RgbImage::from_pixel(16, 8, image::Rgb([200, 10, 10]));
+        let mut jpeg = Vec::new();
+        DynamicImage::ImageRgb8(image)
+            .write_with_encoder(JpegEncoder::new_with_quality(&mut jpeg, 90))
+            .unwrap();
+        // Splice an APP1 (EXIF) segment in after the SOI marker
+        let exif = b"Exif\0\0secret-gps-data";
+        let mut with_exif = jpeg[..2].to_vec();
+        with_exif.extend_from_slice(&[0xFF, 0xE1, 0, (exif.len() + 2) as u8]);
+        with_exif.extend_from_slice(exif);
+        with_exif.extend_from_slice(&jpeg[2..]);
 
-        let minified = minify_css(css);
-        assert!(!minified.contains('\n'));
+        let output = optimize_asset("photo.jpg", &with_exif, &ImageOptions::default());
+        let (_, main) = &output.files[0];
+        assert!(!main.windows(6).any(|w| w == b"secret"));
+        assert_eq!(output.asset.width, Some(16));
+    }
+
+    #[test]
+    fn test_undecodable_image_is_passed_through() {
+        let output = optimize_asset("broken.png", b"not a png", &ImageOptions::default());
+        assert_eq!(output.files.len(), 1);
+        assert_eq!(output.files[0].1, b"not a png");
+        assert_eq!(output.asset.width, None);
     }
 }
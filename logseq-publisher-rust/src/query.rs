@@ -0,0 +1,187 @@
+use crate::analytics::LinkGraph;
+use petgraph::graph::NodeIndex;
+use petgraph::Direction;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::str::FromStr;
+
+/// Which links to follow from a page
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkDirection {
+    /// Links on the page to other pages
+    Outgoing,
+    /// Links to the page from other pages
+    Incoming,
+    /// Both, ignoring direction
+    Both,
+}
+
+impl FromStr for LinkDirection {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "outgoing" => Ok(Self::Outgoing),
+            "incoming" => Ok(Self::Incoming),
+            "both" => Ok(Self::Both),
+            _ => Err(format!("Unknown link direction '{}', expected outgoing, incoming or both", s)),
+        }
+    }
+}
+
+/// A page found by a query, with its distance in links from the start page
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Neighbour {
+    pub path: String,
+    pub distance: usize,
+}
+
+/// Shortest chain of links from `from` to `to`, as page paths including both
+/// ends, or `None` when `to` can't be reached.
+///
+/// When several chains are equally short, the one through the earliest page
+/// paths is returned.
+pub fn shortest_path(links: &LinkGraph, from: &str, to: &str) -> Option<Vec<String>> {
+    let start = links.node(from)?;
+    let goal = links.node(to)?;
+    let parents = breadth_first(links, start, LinkDirection::Outgoing, None);
+
+    parents[goal.index()]?;
+    let mut path = vec![to.to_string()];
+    let mut node = goal;
+    while node != start {
+        node = parents[node.index()].map(|(parent, _)| parent)?;
+        path.push(links.path(node).to_string());
+    }
+    path.reverse();
+    Some(path)
+}
+
+/// Pages within `hops` links of `path` in the given direction, nearest first
+/// and then by path. The start page itself is not included.
+pub fn neighbourhood(links: &LinkGraph, path: &str, hops: usize, direction: LinkDirection) -> Vec<Neighbour> {
+    links.node(path)
+        .map(|start| visited(links, start, breadth_first(links, start, direction, Some(hops))))
+        .unwrap_or_default()
+}
+
+/// All pages reachable from `root` by following links, nearest first and then
+/// by path. The root itself is not included.
+pub fn reachable_from(links: &LinkGraph, root: &str) -> Vec<Neighbour> {
+    links.node(root)
+        .map(|start| visited(links, start, breadth_first(links, start, LinkDirection::Outgoing, None)))
+        .unwrap_or_default()
+}
+
+/// Breadth-first search from `start`, returning each reached node's parent and
+/// distance (the start is its own parent). Neighbours are visited in path
+/// order, so parents are deterministic.
+fn breadth_first(
+    links: &LinkGraph,
+    start: NodeIndex,
+    direction: LinkDirection,
+    max_depth: Option<usize>,
+) -> Vec<Option<(NodeIndex, usize)>> {
+    let graph = links.graph();
+    let mut parents = vec![None; graph.node_count()];
+    parents[start.index()] = Some((start, 0));
+
+    let mut queue = VecDeque::from([start]);
+    while let Some(node) = queue.pop_front() {
+        let distance = parents[node.index()].map_or(0, |(_, d)| d);
+        if max_depth.is_some_and(|max| distance >= max) {
+            continue;
+        }
+
+        let mut next: Vec<NodeIndex> = match direction {
+            LinkDirection::Outgoing => graph.neighbors_directed(node, Direction::Outgoing).collect(),
+            LinkDirection::Incoming => graph.neighbors_directed(node, Direction::Incoming).collect(),
+            LinkDirection::Both => graph.neighbors_undirected(node).collect(),
+        };
+        next.sort();
+        for neighbour in next {
+            if parents[neighbour.index()].is_none() {
+                parents[neighbour.index()] = Some((node, distance + 1));
+                queue.push_back(neighbour);
+            }
+        }
+    }
+
+    parents
+}
+
+fn visited(links: &LinkGraph, start: NodeIndex, parents: Vec<Option<(NodeIndex, usize)>>) -> Vec<Neighbour> {
+    let mut found: Vec<Neighbour> = parents.into_iter()
+        .enumerate()
+        .filter(|&(i, _)| i != start.index())
+        .filter_map(|(i, parent)| parent.map(|(_, distance)| Neighbour {
+            path: links.path(NodeIndex::new(i)).to_string(),
+            distance,
+        }))
+        .collect();
+    found.sort_by(|a, b| a.distance.cmp(&b.distance).then_with(|| a.path.cmp(&b.path)));
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::test_graph;
+
+    /// a -> b -> c -> d, a -> e -> d, f -> a
+    fn links() -> LinkGraph {
+        LinkGraph::from_graph(&test_graph(&[
+            ("a.md", &["b", "e"]),
+            ("b.md", &["c"]),
+            ("c.md", &["d"]),
+            ("d.md", &[]),
+            ("e.md", &["d"]),
+            ("f.md", &["a"]),
+        ]))
+    }
+
+    fn paths(found: &[Neighbour]) -> Vec<&str> {
+        found.iter().map(|n| n.path.as_str()).collect()
+    }
+
+    #[test]
+    fn test_shortest_path() {
+        let links = links();
+        assert_eq!(shortest_path(&links, "a.md", "d.md").unwrap(), vec!["a.md", "e.md", "d.md"]);
+        assert_eq!(shortest_path(&links, "f.md", "c.md").unwrap(), vec!["f.md", "a.md", "b.md", "c.md"]);
+        assert_eq!(shortest_path(&links, "b.md", "b.md").unwrap(), vec!["b.md"]);
+        assert_eq!(shortest_path(&links, "d.md", "a.md"), None);
+        assert_eq!(shortest_path(&links, "a.md", "missing.md"), None);
+    }
+
+    #[test]
+    fn test_neighbourhood_directions() {
+        let links = links();
+
+        let outgoing = neighbourhood(&links, "a.md", 1, LinkDirection::Outgoing);
+        assert_eq!(paths(&outgoing), vec!["b.md", "e.md"]);
+
+        let incoming = neighbourhood(&links, "d.md", 2, LinkDirection::Incoming);
+        assert_eq!(paths(&incoming), vec!["c.md", "e.md", "a.md", "b.md"]);
+        assert_eq!(incoming[2], Neighbour { path: "a.md".to_string(), distance: 2 });
+
+        let both = neighbourhood(&links, "a.md", 1, LinkDirection::Both);
+        assert_eq!(paths(&both), vec!["b.md", "e.md", "f.md"]);
+
+        assert!(neighbourhood(&links, "a.md", 0, LinkDirection::Both).is_empty());
+    }
+
+    #[test]
+    fn test_reachable_from() {
+        let links = links();
+        assert_eq!(paths(&reachable_from(&links, "f.md")), vec!["a.md", "b.md", "e.md", "c.md", "d.md"]);
+        assert!(reachable_from(&links, "d.md").is_empty());
+    }
+
+    #[test]
+    fn test_direction_from_str() {
+        assert_eq!("both".parse::<LinkDirection>(), Ok(LinkDirection::Both));
+        assert!("sideways".parse::<LinkDirection>().is_err());
+    }
+}
@@ -125,6 +125,7 @@ fn test_exporter_with_empty_graph() {
         include_backlinks: true,
         include_graph_view: false,
         custom_css: None,
+        assets: None,
     };
 
     let result = export_to_html(&graph, &config);
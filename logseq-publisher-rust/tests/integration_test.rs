@@ -40,6 +40,7 @@ tags: test, example
         include_backlinks: true,
         include_graph_view: false,
         custom_css: None,
+        assets: None,
     };
 
     let html = export_to_html(&graph, &config).unwrap();
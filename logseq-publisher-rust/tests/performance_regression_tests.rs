@@ -155,6 +155,7 @@ fn test_export_performance() {
         include_backlinks: true,
         include_graph_view: false,
         custom_css: None,
+        assets: None,
     };
 
     let (result, duration) = measure_time(|| {
@@ -172,8 +173,13 @@ fn test_export_performance() {
 
 #[test]
 fn test_asset_optimization_performance() {
+    let dir = tempfile::tempdir().unwrap();
     let assets: Vec<String> = (0..500)
-        .map(|i| format!("assets/image{}.png", i))
+        .map(|i| {
+            let path = dir.path().join(format!("script{}.js", i));
+            std::fs::write(&path, format!("// Asset {}\nconst value{} = {};\n", i, i, i)).unwrap();
+            path.to_string_lossy().to_string()
+        })
         .collect();
 
     let (result, duration) = measure_time(|| {
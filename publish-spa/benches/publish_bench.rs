@@ -0,0 +1,97 @@
+//! Benchmarks of the publish pipeline on generated graphs, single-threaded and on all cores
+//!
+//! `graph_bench.rs` and `parser_bench.rs` belong to `logseq-publisher-rust`, whose parser
+//! and graph stay sequential, so the parallel parsing and rendering of `publish_to` is
+//! measured here instead. `parse_and_render` covers the steps those benches would.
+//!
+//! The release profile in `.cargo/config.toml` aborts on panic, which the benchmark
+//! harness can't use, so run with:
+//!
+//! ```sh
+//! cargo bench --target x86_64-unknown-linux-gnu --config 'profile.release.panic="unwind"'
+//! ```
+
+#[cfg(not(target_arch = "wasm32"))]
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+#[cfg(not(target_arch = "wasm32"))]
+use publish_spa_wasm::{native::block_on, publish_to, MemoryFs, PublishConfig};
+
+/// A graph of `count` linked pages with nested blocks, tags, tasks and code
+#[cfg(not(target_arch = "wasm32"))]
+fn generate_graph(count: usize) -> MemoryFs {
+    (0..count)
+        .map(|i| {
+            let content = format!(
+                "- This is page number {i}\n  - With some nested content\n  - And links to [[page_{}]]\n  \
+                 - And [[page_{}]]\n- Some more content with **bold** and *italic*\n- Code block:\n  \
+                 ```rust\n  fn page_{i}() {{\n      println!(\"Page {i}\")\n  }}\n  ```\n\
+                 - Tags: #tag{} #benchmark\n- TODO Some task\n",
+                (i + 1) % count,
+                (i + 2) % count,
+                i % 10
+            );
+            (format!("pages/page_{}.md", i), content)
+        })
+        .collect()
+}
+
+/// Publish `graph` to memory using `threads` worker threads
+#[cfg(not(target_arch = "wasm32"))]
+fn publish(graph: &MemoryFs, config: &PublishConfig, threads: usize) -> MemoryFs {
+    let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap();
+    pool.install(|| {
+        let mut site = MemoryFs::new();
+        block_on(publish_to(config, graph, &mut site)).unwrap();
+        site
+    })
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn bench_publish(c: &mut Criterion, name: &str, config: PublishConfig) {
+    let cores = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let mut thread_counts = vec![1];
+    if cores > 1 {
+        thread_counts.push(cores);
+    }
+    let mut group = c.benchmark_group(name);
+    group.sample_size(10);
+
+    for size in [100, 1000] {
+        let graph = generate_graph(size);
+        group.throughput(Throughput::Elements(size as u64));
+        for &threads in &thread_counts {
+            group.bench_with_input(
+                BenchmarkId::new(format!("{}_threads", threads), size),
+                &graph,
+                |b, graph| b.iter(|| publish(graph, &config, threads)),
+            );
+        }
+    }
+
+    group.finish();
+}
+
+/// Parsing, graph building and page rendering
+#[cfg(not(target_arch = "wasm32"))]
+fn bench_parse_and_render(c: &mut Criterion) {
+    let mut config = PublishConfig::new(String::new(), String::new());
+    config.minify = false;
+    bench_publish(c, "parse_and_render", config);
+}
+
+/// The full pipeline including minification and precompression
+#[cfg(not(target_arch = "wasm32"))]
+fn bench_full_publish(c: &mut Criterion) {
+    let mut config = PublishConfig::new(String::new(), String::new());
+    config.precompress = true;
+    bench_publish(c, "full_publish", config);
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+criterion_group!(benches, bench_parse_and_render, bench_full_publish);
+#[cfg(not(target_arch = "wasm32"))]
+criterion_main!(benches);
+
+/// Benchmarks need threads and a native clock
+#[cfg(target_arch = "wasm32")]
+fn main() {}
@@ -0,0 +1,76 @@
+/**
+ * Browser helper functions for WASM interop
+ * These functions are called from Rust via wasm-bindgen
+ */
+
+const MARKDOWN = /\.(md|markdown)$/;
+const SKIPPED_DIRS = new Set(['node_modules', '.git']);
+
+/**
+ * Read the markdown files of a graph folder picked or dropped in the browser
+ * @param {FileList|File[]|FileSystemDirectoryHandle} input - Files of the folder
+ *   (e.g. from `<input type="file" webkitdirectory>`) or a handle to it
+ * @returns {Promise<Array<{path: string, content: string, modified: number}|{path: string, error: string}>>}
+ *   Files that can't be read carry the reason instead of their content
+ */
+export async function read_dropped_files(input) {
+    const entries = input && input.kind === 'directory'
+        ? await directoryEntries(input, '')
+        : fileEntries(Array.from(input || []));
+
+    const files = [];
+    for (const { path, file } of entries) {
+        if (!MARKDOWN.test(path) || path.split('/').some(part => SKIPPED_DIRS.has(part))) {
+            continue;
+        }
+        try {
+            files.push({
+                path,
+                content: await file.text(),
+                modified: file.lastModified
+            });
+        } catch (err) {
+            files.push({ path, error: err.message });
+        }
+    }
+
+    return files;
+}
+
+/**
+ * Pair files with their paths relative to the picked folder
+ * @param {File[]} files - Files with `webkitRelativePath` like `graph/pages/a.md`, or bare files
+ */
+function fileEntries(files) {
+    const paths = files.map(file => file.webkitRelativePath || file.name);
+    // Folder pickers prefix every path with the folder's own name
+    const prefix = paths.length > 0 && paths.every(path => path.includes('/'))
+        ? paths[0].slice(0, paths[0].indexOf('/') + 1)
+        : '';
+    const shared = prefix && paths.every(path => path.startsWith(prefix));
+
+    return files.map((file, i) => ({
+        path: shared ? paths[i].slice(prefix.length) : paths[i],
+        file
+    }));
+}
+
+/**
+ * Recursively list the files below a directory handle
+ * @param {FileSystemDirectoryHandle} handle - Directory handle
+ * @param {string} prefix - Path of the directory relative to the graph root
+ */
+async function directoryEntries(handle, prefix) {
+    const entries = [];
+    for await (const child of handle.values()) {
+        const path = prefix + child.name;
+        if (child.kind === 'directory') {
+            if (!SKIPPED_DIRS.has(child.name)) {
+                entries.push(...await directoryEntries(child, path + '/'));
+            }
+        } else {
+            entries.push({ path, file: await child.getFile() });
+        }
+    }
+    return entries;
+}
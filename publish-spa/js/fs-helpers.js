@@ -3,16 +3,27 @@
  * These functions are called from Rust via wasm-bindgen
  */
 
-import { promises as fs } from 'fs';
-import { join, dirname, relative } from 'path';
-import { glob } from 'glob';
+let nodeModules;
+
+/**
+ * Load the Node.js modules on first use, so that this module also loads in
+ * browsers, where only the in-browser publishing functions are called
+ */
+function node() {
+    nodeModules ??= Promise.all([import('fs'), import('path')])
+        .then(([fs, path]) => ({ fs: fs.promises, ...path }));
+    return nodeModules;
+}
 
 /**
  * Recursively read all files in a directory
  * @param {string} dirPath - Directory path to read
- * @returns {Promise<Array<{path: string, content: string}>>}
+ * @returns {Promise<Array<{path: string, content: string, modified: number}|{path: string, error: string}>>}
+ *   Files that can't be read carry the reason instead of their content
  */
 export async function read_dir_recursive(dirPath) {
+    const { fs, join, relative } = await node();
+    const { glob } = await import('glob');
     const files = [];
 
     try {
@@ -25,16 +36,18 @@ export async function read_dir_recursive(dirPath) {
 
         // Read each file
         for (const filePath of filePaths) {
+            const relativePath = relative(dirPath, filePath);
             try {
                 const content = await fs.readFile(filePath, 'utf-8');
-                const relativePath = relative(dirPath, filePath);
+                const stats = await fs.stat(filePath);
 
                 files.push({
                     path: relativePath,
-                    content: content
+                    content: content,
+                    modified: stats.mtimeMs
                 });
             } catch (err) {
-                console.error(`Warning: Failed to read ${filePath}:`, err.message);
+                files.push({ path: relativePath, error: err.message });
             }
         }
     } catch (err) {
@@ -50,6 +63,7 @@ export async function read_dir_recursive(dirPath) {
  * @param {string} content - File content
  */
 export async function write_file(filePath, content) {
+    const { fs, dirname } = await node();
     try {
         // Ensure directory exists
         await fs.mkdir(dirname(filePath), { recursive: true });
@@ -61,14 +75,111 @@ export async function write_file(filePath, content) {
     }
 }
 
+/**
+ * Write a binary file, creating directories as needed
+ * @param {string} filePath - File path to write
+ * @param {Uint8Array} content - File bytes
+ */
+export async function write_binary_file(filePath, content) {
+    const { fs, dirname } = await node();
+    try {
+        await fs.mkdir(dirname(filePath), { recursive: true });
+        await fs.writeFile(filePath, content);
+    } catch (err) {
+        throw new Error(`Failed to write file ${filePath}: ${err.message}`);
+    }
+}
+
+/**
+ * Read a binary file
+ * @param {string} filePath - File path to read
+ * @returns {Promise<Uint8Array|undefined>} File bytes, or undefined if the file does not exist
+ */
+export async function read_binary_file(filePath) {
+    const { fs } = await node();
+    try {
+        return new Uint8Array(await fs.readFile(filePath));
+    } catch (err) {
+        if (err.code === 'ENOENT') {
+            return undefined;
+        }
+        throw new Error(`Failed to read file ${filePath}: ${err.message}`);
+    }
+}
+
+/**
+ * Remove a file if it exists
+ * @param {string} filePath - File path to remove
+ */
+export async function remove_file(filePath) {
+    const { fs } = await node();
+    try {
+        await fs.rm(filePath, { force: true });
+    } catch (err) {
+        throw new Error(`Failed to remove file ${filePath}: ${err.message}`);
+    }
+}
+
 /**
  * Ensure a directory exists
  * @param {string} dirPath - Directory path
  */
 export async function ensure_dir(dirPath) {
+    const { fs } = await node();
     try {
         await fs.mkdir(dirPath, { recursive: true });
     } catch (err) {
         throw new Error(`Failed to create directory ${dirPath}: ${err.message}`);
     }
 }
+
+/**
+ * Move a file over another, which is replaced atomically, creating its directory as needed
+ * @param {string} fromPath - File to move
+ * @param {string} toPath - Destination path
+ */
+export async function rename_file(fromPath, toPath) {
+    const { fs, dirname } = await node();
+    try {
+        await fs.mkdir(dirname(toPath), { recursive: true });
+        await fs.rename(fromPath, toPath);
+    } catch (err) {
+        throw new Error(`Failed to move ${fromPath} to ${toPath}: ${err.message}`);
+    }
+}
+
+/**
+ * Keep a copy of a file, as a hard link where the file system allows it
+ * @param {string} filePath - File to back up
+ * @param {string} backupPath - Where to keep the copy
+ * @returns {Promise<boolean>} Whether there was a file to back up
+ */
+export async function backup_file(filePath, backupPath) {
+    const { fs, dirname } = await node();
+    try {
+        if (!(await fs.stat(filePath)).isFile()) {
+            return false;
+        }
+        await fs.mkdir(dirname(backupPath), { recursive: true });
+        await fs.link(filePath, backupPath).catch(() => fs.copyFile(filePath, backupPath));
+        return true;
+    } catch (err) {
+        if (err.code === 'ENOENT') {
+            return false;
+        }
+        throw new Error(`Failed to back up ${filePath}: ${err.message}`);
+    }
+}
+
+/**
+ * Remove a directory and everything in it, if it exists
+ * @param {string} dirPath - Directory path
+ */
+export async function remove_dir(dirPath) {
+    const { fs } = await node();
+    try {
+        await fs.rm(dirPath, { recursive: true, force: true });
+    } catch (err) {
+        throw new Error(`Failed to remove directory ${dirPath}: ${err.message}`);
+    }
+}
@@ -0,0 +1,10 @@
+//! Native command-line entry point: `publish-spa OUT-DIR [OPTIONS]` or `publish-spa serve [OPTIONS]`
+
+#[cfg(not(target_arch = "wasm32"))]
+fn main() -> std::process::ExitCode {
+    publish_spa_wasm::cli::run(std::env::args().skip(1))
+}
+
+/// The binary reads the graph with `std::fs`; WASM builds go through the JavaScript API instead
+#[cfg(target_arch = "wasm32")]
+fn main() {}
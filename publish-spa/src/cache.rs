@@ -0,0 +1,138 @@
+//! Build cache for incremental publishing
+//!
+//! The cache lives in the output directory as `.publish-cache.json`. It records each
+//! page's source hash, parsed page and render key, and the hash of every generated
+//! output. A rebuild with the same config only re-parses changed sources, re-renders
+//! pages whose render inputs changed, rewrites outputs whose content changed and
+//! removes outputs that are no longer generated.
+
+use crate::errors::PublishError;
+use crate::exporter::ExportConfig;
+use crate::parser::Page;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Path of the cache manifest, relative to the output directory
+pub const CACHE_PATH: &str = ".publish-cache.json";
+
+/// Bumped whenever the manifest layout changes; older manifests are ignored
+const CACHE_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BuildCache {
+    version: u32,
+    /// Hash of the publisher version and export config; pages and outputs are only
+    /// reused by builds with the same hash
+    pub config_hash: String,
+    /// Cached pages by source path
+    pub pages: BTreeMap<String, CachedPage>,
+    /// Content hashes of the generated text outputs, before post-processing
+    pub outputs: BTreeMap<String, String>,
+    /// Precompressed `.gz` and `.br` siblings of the outputs
+    pub compressed: BTreeSet<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedPage {
+    pub source_hash: String,
+    /// Hash of everything the page's HTML depends on, see `exporter::export_graph_incremental`
+    pub render_key: String,
+    /// Output path of the rendered page
+    pub output: String,
+    pub page: Page,
+}
+
+impl BuildCache {
+    pub fn new(config_hash: String) -> Self {
+        Self {
+            version: CACHE_VERSION,
+            config_hash,
+            ..Self::default()
+        }
+    }
+
+    /// Parse a manifest, returning `None` when it is corrupt or from another cache version
+    pub fn from_json(data: &[u8]) -> Option<Self> {
+        serde_json::from_slice::<BuildCache>(data)
+            .ok()
+            .filter(|cache| cache.version == CACHE_VERSION)
+    }
+
+    pub fn to_json(&self) -> Result<Vec<u8>, PublishError> {
+        serde_json::to_vec(self)
+            .map_err(|e| PublishError::export(format!("Failed to serialize build cache: {}", e)))
+    }
+
+    /// The cached source page, if its content hash is still `source_hash`
+    pub fn page(&self, path: &str, source_hash: &str) -> Option<&Page> {
+        self.pages
+            .get(path)
+            .filter(|cached| cached.source_hash == source_hash)
+            .map(|cached| &cached.page)
+    }
+
+    /// Render keys of the cached pages whose output is still on record
+    pub fn render_keys(&self) -> impl Iterator<Item = (&String, &String)> {
+        self.pages
+            .iter()
+            .filter(|(_, cached)| self.outputs.contains_key(&cached.output))
+            .map(|(path, cached)| (path, &cached.render_key))
+    }
+
+    /// Every file the build wrote, including precompressed siblings
+    pub fn written_paths(&self) -> impl Iterator<Item = &String> {
+        self.outputs.keys().chain(&self.compressed)
+    }
+}
+
+/// Hex-encoded SHA-256 of `data`
+pub fn content_hash(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Hash of the settings that affect every output: the publisher version and export config
+pub fn config_hash(config: &ExportConfig) -> String {
+    let config = serde_json::to_string(config).unwrap_or_default();
+    content_hash(format!("{}\n{}", env!("CARGO_PKG_VERSION"), config).as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_logseq_page;
+
+    #[test]
+    fn test_content_hash() {
+        assert_eq!(
+            content_hash(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut cache = BuildCache::new("config".to_string());
+        cache.pages.insert("pages/a.md".to_string(), CachedPage {
+            source_hash: content_hash(b"- A"),
+            render_key: "key".to_string(),
+            output: "pages/a.html".to_string(),
+            page: parse_logseq_page("- A", "pages/a.md").unwrap(),
+        });
+        cache.outputs.insert("pages/a.html".to_string(), "hash".to_string());
+        cache.compressed.insert("pages/a.html.gz".to_string());
+
+        let cache = BuildCache::from_json(&cache.to_json().unwrap()).unwrap();
+        assert_eq!(cache.config_hash, "config");
+        assert!(cache.page("pages/a.md", &content_hash(b"- A")).is_some());
+        assert!(cache.page("pages/a.md", &content_hash(b"- B")).is_none());
+        assert_eq!(cache.render_keys().count(), 1);
+        assert_eq!(cache.written_paths().count(), 2);
+
+        assert!(BuildCache::from_json(b"{\"version\": 0}").is_none());
+        assert!(BuildCache::from_json(b"not json").is_none());
+    }
+}
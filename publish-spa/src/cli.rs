@@ -0,0 +1,355 @@
+//! Command-line interface of the native `publish-spa` binary
+
+use crate::diagnostics::ErrorPolicy;
+use crate::health::HealthRule;
+use crate::{native, serve, PublishConfig};
+use serde::de::DeserializeOwned;
+use std::fs;
+use std::process::ExitCode;
+
+const USAGE: &str = "Usage: publish-spa OUT-DIR [OPTIONS]
+       publish-spa serve [OPTIONS]
+
+The serve command publishes to memory, serves the site on localhost, and republishes
+and reloads open pages whenever the graph changes.
+
+Options:
+  -d, --directory DIR              Graph directory to export (default: .)
+      --theme NAME                 Theme name (default: default)
+      --theme-mode MODE            light or dark (default: light)
+      --accent-color COLOR         tomato, red, crimson, pink, plum, purple, violet, indigo,
+                                   blue, cyan, teal, green, grass or orange (default: blue)
+      --custom-css FILE            Append the CSS in FILE to style.css
+      --no-backlinks               Leave out linked references
+      --graph-view                 Include the graph view
+      --unlinked-references        Include unlinked references
+      --max-unlinked-references N  Unlinked references shown per page (default: 50)
+      --related-pages              Include related pages that aren't directly linked
+      --min-related-score N        Score a page needs to be listed as related; one point
+                                   per co-citation, shared link and shared tag (default: 2)
+      --max-related-pages N        Related pages shown per page (default: 5)
+      --no-stub-pages              Render links to missing pages as plain text
+      --base-url URL               Public URL of the site; enables sitemap.xml, canonical
+                                   links and, at a host root, robots.txt
+      --base-path PATH             Sub-path the site is served under, e.g. /project/
+      --link-style STYLE           relative or absolute (default: relative)
+      --slug-strategy STRATEGY     preserve, lowercase-kebab or pretty (default: preserve)
+      --redirect-from STRATEGY     Redirect page paths of a previous slug strategy (repeatable)
+      --feed                       Write an Atom feed of the latest journal pages
+      --feed-entries N             Entries per feed (default: 20)
+      --tag-feeds                  Also write one feed per tag
+      --no-minify                  Do not minify HTML, CSS and JavaScript
+      --precompress                Write .gz and .br siblings of text outputs
+      --incremental                Keep a build cache in OUT-DIR and only regenerate
+                                   what changed since the last build
+      --health-report              Write health-report.json listing graph problems
+      --health-report-html         Also write the report as health-report.html
+      --fail-on RULES              Exit with 1 when the graph breaks any of these
+                                   comma-separated rules: broken-link, orphan-page,
+                                   dead-end, duplicate-title, empty-page,
+                                   stale-block-ref, or all
+      --strict                     Exit with 1 without writing anything when a page
+                                   fails to parse or there are other diagnostics
+      --max-warnings N             Like --strict, but allow up to N diagnostics
+      --host HOST                  Address serve listens on (default: 127.0.0.1)
+      --port N                     Port serve listens on (default: 8080)
+  -h, --help                       Print this help
+  -V, --version                    Print the version
+
+Exit codes: 0 on success, 1 when publishing fails, 2 on invalid arguments";
+
+/// Exit code for invalid command-line arguments
+const USAGE_ERROR: u8 = 2;
+
+const DEFAULT_HOST: &str = "127.0.0.1";
+const DEFAULT_PORT: u16 = 8080;
+
+/// What the command line asks for
+#[derive(Debug)]
+pub enum Command {
+    Publish {
+        config: Box<PublishConfig>,
+        /// File whose contents become `config.custom_css`
+        custom_css_file: Option<String>,
+    },
+    /// Serve the site locally and republish on changes
+    Serve {
+        config: Box<PublishConfig>,
+        custom_css_file: Option<String>,
+        /// `host:port` to listen on
+        address: String,
+    },
+    Help,
+    Version,
+}
+
+/// Run the binary with the process arguments (excluding the program name)
+pub fn run<I: IntoIterator<Item = String>>(args: I) -> ExitCode {
+    let (mut config, custom_css_file, address) = match parse_args(args) {
+        Ok(Command::Publish { config, custom_css_file }) => (config, custom_css_file, None),
+        Ok(Command::Serve { config, custom_css_file, address }) => (config, custom_css_file, Some(address)),
+        Ok(Command::Help) => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Ok(Command::Version) => {
+            println!("publish-spa {}", env!("CARGO_PKG_VERSION"));
+            return ExitCode::SUCCESS;
+        }
+        Err(message) => {
+            eprintln!("Error: {}\n\n{}", message, USAGE);
+            return ExitCode::from(USAGE_ERROR);
+        }
+    };
+
+    if let Some(path) = custom_css_file {
+        match fs::read_to_string(&path) {
+            Ok(css) => config.custom_css = Some(css),
+            Err(e) => {
+                eprintln!("Error: Failed to read custom CSS '{}': {}", path, e);
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    if let Some(address) = address {
+        return match serve::serve(&config, &address) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                ExitCode::FAILURE
+            }
+        };
+    }
+
+    match native::publish(&config) {
+        Ok(stats) => {
+            if !stats.diagnostics.is_empty() {
+                eprintln!("{}", native::render_diagnostics(&config.input_dir, &stats.diagnostics));
+            }
+            println!(
+                "Published {} pages ({} blocks, {} links) to {}",
+                stats.page_count, stats.total_blocks, stats.total_links, config.output_dir
+            );
+            if stats.reused_pages > 0 {
+                println!("Reused {} unchanged pages from the previous build", stats.reused_pages);
+            }
+            if stats.health_issues > 0 {
+                println!("Found {} graph health issues", stats.health_issues);
+            }
+            if stats.compressed_files > 0 {
+                println!(
+                    "Precompressed {} files ({} bytes gzip, {} bytes brotli)",
+                    stats.compressed_files, stats.gzip_bytes, stats.brotli_bytes
+                );
+            }
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Parse command-line arguments; options take their value as the next argument or after `=`
+pub fn parse_args<I: IntoIterator<Item = String>>(args: I) -> Result<Command, String> {
+    let mut config = PublishConfig::new(".".to_string(), String::new());
+    let mut output_dir = None;
+    let mut custom_css_file = None;
+    let mut host = None;
+    let mut port = None;
+    let mut args = args.into_iter().peekable();
+    let serve = args.next_if(|arg| arg == "serve").is_some();
+
+    while let Some(arg) = args.next() {
+        let (flag, inline_value) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => (flag.to_string(), Some(value.to_string())),
+            _ => (arg.clone(), None),
+        };
+        let mut value = || {
+            inline_value
+                .clone()
+                .or_else(|| args.next())
+                .ok_or_else(|| format!("{} requires a value", flag))
+        };
+
+        match flag.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "-V" | "--version" => return Ok(Command::Version),
+            "-d" | "--directory" => config.input_dir = value()?,
+            "--theme" => config.theme = value()?,
+            "--theme-mode" => config.theme_mode = parse_name(&flag, &value()?)?,
+            "--accent-color" => config.accent_color = parse_name(&flag, &value()?)?,
+            "--custom-css" => custom_css_file = Some(value()?),
+            "--no-backlinks" => config.include_backlinks = false,
+            "--graph-view" => config.include_graph_view = true,
+            "--unlinked-references" => config.include_unlinked_references = true,
+            "--max-unlinked-references" => config.max_unlinked_references = parse_number(&flag, &value()?)?,
+            "--related-pages" => config.include_related_pages = true,
+            "--min-related-score" => config.min_related_score = parse_number(&flag, &value()?)?,
+            "--max-related-pages" => config.max_related_pages = parse_number(&flag, &value()?)?,
+            "--no-stub-pages" => config.generate_stub_pages = false,
+            "--base-url" => config.base_url = Some(value()?),
+            "--base-path" => config.base_path = Some(value()?),
+            "--link-style" => config.link_style = parse_name(&flag, &value()?)?,
+            "--slug-strategy" => config.slug_strategy = parse_name(&flag, &value()?)?,
+            "--redirect-from" => config.redirect_from.push(parse_name(&flag, &value()?)?),
+            "--feed" => config.generate_feed = true,
+            "--feed-entries" => config.feed_entries = parse_number(&flag, &value()?)?,
+            "--tag-feeds" => config.tag_feeds = true,
+            "--no-minify" => config.minify = false,
+            "--precompress" => config.precompress = true,
+            "--incremental" => config.incremental = true,
+            "--health-report" => config.health_report = true,
+            "--health-report-html" => config.health_report_html = true,
+            "--fail-on" => config.fail_on.extend(parse_rules(&flag, &value()?)?),
+            "--strict" => config.error_policy = ErrorPolicy::Strict,
+            "--max-warnings" => config.error_policy = ErrorPolicy::Threshold(parse_number(&flag, &value()?)?),
+            "--host" => host = Some(value()?),
+            "--port" => port = Some(value()?.parse::<u16>().map_err(|_| format!("Invalid value for {}: expected a port number", flag))?),
+            _ if arg.starts_with('-') && arg.len() > 1 => return Err(format!("Unknown option '{}'", arg)),
+            _ if output_dir.is_none() && !serve => output_dir = Some(arg),
+            _ => return Err(format!("Unexpected argument '{}'", arg)),
+        }
+    }
+
+    if serve {
+        let address = format!("{}:{}", host.as_deref().unwrap_or(DEFAULT_HOST), port.unwrap_or(DEFAULT_PORT));
+        return Ok(Command::Serve { config: Box::new(config), custom_css_file, address });
+    }
+    if host.is_some() || port.is_some() {
+        return Err("--host and --port only apply to serve".to_string());
+    }
+
+    config.output_dir = output_dir.ok_or("Missing OUT-DIR")?;
+    Ok(Command::Publish { config: Box::new(config), custom_css_file })
+}
+
+/// Parse an option value by the same name the JavaScript config uses
+fn parse_name<T: DeserializeOwned>(flag: &str, value: &str) -> Result<T, String> {
+    serde_json::from_value(serde_json::Value::String(value.to_string()))
+        .map_err(|e| format!("Invalid value '{}' for {}: {}", value, flag, e))
+}
+
+/// Parse a comma-separated list of health rules, where `all` stands for every rule
+fn parse_rules(flag: &str, value: &str) -> Result<Vec<HealthRule>, String> {
+    let mut rules = Vec::new();
+    for name in value.split(',').map(str::trim) {
+        if name == "all" {
+            rules.extend(HealthRule::ALL);
+        } else {
+            rules.push(parse_name(flag, name)?);
+        }
+    }
+    Ok(rules)
+}
+
+fn parse_number(flag: &str, value: &str) -> Result<usize, String> {
+    value
+        .parse()
+        .map_err(|_| format!("Invalid value '{}' for {}: expected a number", value, flag))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::slug::SlugStrategy;
+    use crate::theme::{AccentColor, ThemeMode};
+    use crate::urls::LinkStyle;
+
+    fn parse(args: &[&str]) -> Result<Command, String> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    fn parse_config(args: &[&str]) -> (PublishConfig, Option<String>) {
+        match parse(args).unwrap() {
+            Command::Publish { config, custom_css_file } => (*config, custom_css_file),
+            command => panic!("expected a publish command, got {:?}", command),
+        }
+    }
+
+    #[test]
+    fn test_defaults() {
+        let (config, custom_css_file) = parse_config(&["www"]);
+        assert_eq!(config.output_dir, "www");
+        assert_eq!(config.input_dir, ".");
+        assert_eq!(config.theme_mode, ThemeMode::Light);
+        assert_eq!(config.accent_color, AccentColor::Blue);
+        assert!(config.include_backlinks);
+        assert!(config.minify);
+        assert!(!config.precompress);
+        assert!(custom_css_file.is_none());
+    }
+
+    #[test]
+    fn test_options() {
+        let (config, custom_css_file) = parse_config(&[
+            "--directory", "graph", "www", "--theme-mode=dark", "--accent-color", "grass",
+            "--custom-css", "brand.css", "--no-backlinks", "--max-unlinked-references", "10",
+            "--base-url", "https://example.com/notes/", "--link-style", "absolute",
+            "--slug-strategy", "pretty", "--redirect-from", "preserve",
+            "--redirect-from=lowercase-kebab", "--feed", "--tag-feeds", "--no-minify", "--precompress",
+            "--incremental", "--related-pages", "--min-related-score", "3",
+            "--health-report-html", "--fail-on", "broken-link,stale-block-ref", "--max-warnings", "3",
+        ]);
+
+        assert_eq!(config.input_dir, "graph");
+        assert_eq!(config.output_dir, "www");
+        assert_eq!(config.theme_mode, ThemeMode::Dark);
+        assert_eq!(config.accent_color, AccentColor::Grass);
+        assert_eq!(custom_css_file.as_deref(), Some("brand.css"));
+        assert!(!config.include_backlinks);
+        assert_eq!(config.max_unlinked_references, 10);
+        assert!(config.include_related_pages);
+        assert_eq!(config.min_related_score, 3);
+        assert_eq!(config.base_url.as_deref(), Some("https://example.com/notes/"));
+        assert_eq!(config.link_style, LinkStyle::Absolute);
+        assert_eq!(config.slug_strategy, SlugStrategy::Pretty);
+        assert_eq!(config.redirect_from, [SlugStrategy::Preserve, SlugStrategy::LowercaseKebab]);
+        assert!(config.generate_feed && config.tag_feeds);
+        assert!(!config.minify);
+        assert!(config.precompress);
+        assert!(config.incremental);
+        assert!(config.health_report_html);
+        assert_eq!(config.fail_on, [HealthRule::BrokenLink, HealthRule::StaleBlockRef]);
+        assert_eq!(config.error_policy, ErrorPolicy::Threshold(3));
+        assert_eq!(parse_config(&["www", "--strict"]).0.error_policy, ErrorPolicy::Strict);
+    }
+
+    #[test]
+    fn test_invalid_arguments() {
+        assert!(matches!(parse(&["www", "--help"]), Ok(Command::Help)));
+        assert!(matches!(parse(&["-V"]), Ok(Command::Version)));
+        assert_eq!(parse(&[]).unwrap_err(), "Missing OUT-DIR");
+        assert_eq!(parse(&["www", "--graph"]).unwrap_err(), "Unknown option '--graph'");
+        assert_eq!(parse(&["www", "extra"]).unwrap_err(), "Unexpected argument 'extra'");
+        assert_eq!(parse(&["www", "--directory"]).unwrap_err(), "--directory requires a value");
+        assert!(parse(&["www", "--theme-mode", "sepia"]).unwrap_err().starts_with("Invalid value 'sepia' for --theme-mode"));
+        assert!(parse(&["www", "--feed-entries", "many"]).is_err());
+        assert!(parse(&["www", "--fail-on", "broken-link,typo"]).unwrap_err().starts_with("Invalid value 'typo' for --fail-on"));
+        assert_eq!(parse(&["www", "--port", "80"]).unwrap_err(), "--host and --port only apply to serve");
+        assert_eq!(parse(&["serve", "www"]).unwrap_err(), "Unexpected argument 'www'");
+        assert!(parse(&["serve", "--port", "http"]).is_err());
+    }
+
+    #[test]
+    fn test_serve() {
+        match parse(&["serve", "-d", "graph", "--theme-mode", "dark"]).unwrap() {
+            Command::Serve { config, address, .. } => {
+                assert_eq!(config.input_dir, "graph");
+                assert_eq!(config.theme_mode, ThemeMode::Dark);
+                assert_eq!(address, "127.0.0.1:8080");
+            }
+            command => panic!("expected a serve command, got {:?}", command),
+        }
+
+        match parse(&["serve", "--host=0.0.0.0", "--port", "4000"]).unwrap() {
+            Command::Serve { address, .. } => assert_eq!(address, "0.0.0.0:4000"),
+            command => panic!("expected a serve command, got {:?}", command),
+        }
+
+        // A site can still be published to a directory named serve
+        assert!(matches!(parse(&["-d", ".", "serve"]), Ok(Command::Publish { .. })));
+    }
+}
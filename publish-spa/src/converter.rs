@@ -1,21 +1,34 @@
-use std::collections::HashMap;
 use wasm_bindgen::prelude::*;
-use js_sys::{Array, Reflect};
+use js_sys::{Array, Reflect, Uint8Array};
 use crate::errors::PublishError;
+use crate::fs::{GraphFiles, GraphSource, OutputSink, Staged, Staging, BACKUP_DIR, STAGING_DIR};
+
+/// A markdown source file read from the graph directory
+#[derive(Debug, Clone)]
+pub struct SourceFile {
+    pub content: String,
+    /// Last modification time in milliseconds since the Unix epoch, when known
+    pub modified: Option<u64>,
+}
 
 /// Read all markdown files from a graph directory
 /// This uses Node.js fs module via JavaScript interop
-pub async fn read_graph_files(input_dir: &str) -> Result<HashMap<String, String>, PublishError> {
+pub async fn read_graph_files(input_dir: &str) -> Result<GraphFiles, PublishError> {
     // Validate input directory path
     validate_input_path(input_dir)?;
 
-    let mut files = HashMap::new();
-
     // Call JavaScript helper to read files
     let files_array = read_dir_recursive(input_dir)
         .await
         .map_err(|e| PublishError::io(format!("Failed to read directory '{}': {:?}", input_dir, e)))?;
 
+    source_files(files_array)
+}
+
+/// Convert the `{path, content, modified}` objects returned by the JavaScript helpers,
+/// and the `{path, error}` objects of files they couldn't read
+fn source_files(files_array: Array) -> Result<GraphFiles, PublishError> {
+    let mut files = GraphFiles::default();
     let length = files_array.length();
     for i in 0..length {
         let file_obj = files_array.get(i);
@@ -28,14 +41,24 @@ pub async fn read_graph_files(input_dir: &str) -> Result<HashMap<String, String>
         // Validate each file path
         validate_file_path(&path)?;
 
+        if let Some(error) = Reflect::get(&file_obj, &JsValue::from_str("error")).ok().and_then(|value| value.as_string()) {
+            files.push_unreadable(path, error);
+            continue;
+        }
+
         let content = Reflect::get(&file_obj, &JsValue::from_str("content"))
-            .map_err(|_| PublishError::js_interop(&format!("Missing content property for file '{}'", path)))?
+            .map_err(|_| PublishError::js_interop(format!("Missing content property for file '{}'", path)))?
             .as_string()
-            .ok_or_else(|| PublishError::js_interop(&format!("Content is not a string for file '{}'", path)))?;
+            .ok_or_else(|| PublishError::js_interop(format!("Content is not a string for file '{}'", path)))?;
+
+        let modified = Reflect::get(&file_obj, &JsValue::from_str("modified"))
+            .ok()
+            .and_then(|value| value.as_f64())
+            .map(|ms| ms as u64);
 
         // Only include markdown files
         if path.ends_with(".md") || path.ends_with(".markdown") {
-            files.insert(path, content);
+            files.files.insert(path, SourceFile { content, modified });
         }
     }
 
@@ -43,7 +66,7 @@ pub async fn read_graph_files(input_dir: &str) -> Result<HashMap<String, String>
 }
 
 /// Validate input directory path for security
-fn validate_input_path(path: &str) -> Result<(), PublishError> {
+pub(crate) fn validate_input_path(path: &str) -> Result<(), PublishError> {
     if path.is_empty() {
         return Err(PublishError::invalid_input("Input directory path cannot be empty"));
     }
@@ -63,44 +86,210 @@ fn validate_input_path(path: &str) -> Result<(), PublishError> {
 }
 
 /// Validate individual file path for security
-fn validate_file_path(path: &str) -> Result<(), PublishError> {
+pub(crate) fn validate_file_path(path: &str) -> Result<(), PublishError> {
     if path.is_empty() {
         return Err(PublishError::invalid_input("File path cannot be empty"));
     }
 
     // Check for path traversal
     if path.contains("..") {
-        return Err(PublishError::invalid_input(&format!("Path traversal not allowed in file path: '{}'", path)));
+        return Err(PublishError::invalid_input(format!("Path traversal not allowed in file path: '{}'", path)));
     }
 
     // Check for null bytes
     if path.contains('\0') {
-        return Err(PublishError::invalid_input(&format!("Null bytes not allowed in file path: '{}'", path)));
+        return Err(PublishError::invalid_input(format!("Null bytes not allowed in file path: '{}'", path)));
     }
 
     // Check for suspicious patterns
     if path.starts_with('/') || path.starts_with('\\') {
-        return Err(PublishError::invalid_input(&format!("Absolute file paths not allowed: '{}'", path)));
+        return Err(PublishError::invalid_input(format!("Absolute file paths not allowed: '{}'", path)));
     }
 
     Ok(())
 }
 
-/// Write output HTML files
-pub async fn write_output_files(output_dir: &str, files: HashMap<String, String>) -> Result<(), PublishError> {
-    // Validate output directory
-    validate_input_path(output_dir)?;
+/// A directory accessed through the Node.js `fs` helpers
+///
+/// As an output sink, the files a build writes go to a `.publish-staging` directory
+/// inside it and are renamed over their counterparts on commit. Like `NativeDir`, a
+/// commit that fails partway puts the previous files back from `.publish-backup`.
+#[derive(Debug, Clone)]
+pub struct NodeDir {
+    path: String,
+    /// Changes of the build in progress, between `begin` and `commit`
+    staging: Option<Staging>,
+}
+
+impl NodeDir {
+    pub fn new(path: impl Into<String>) -> Self {
+        Self { path: path.into(), staging: None }
+    }
 
-    for (path, content) in files {
-        // Validate output file path
-        validate_file_path(&path)?;
+    fn staging_dir(&self) -> String {
+        format!("{}/{}", self.path, STAGING_DIR)
+    }
 
-        let output_path = format!("{}/{}", output_dir, path);
-        write_file(&output_path, &content)
+    /// Apply one staged change, returning whether the file it replaces or removes was
+    /// backed up
+    async fn apply(&self, path: &str, change: Staged) -> Result<bool, PublishError> {
+        let target = format!("{}/{}", self.path, path);
+        let backed_up = backup_file(&target, &format!("{}/{}/{}", self.path, BACKUP_DIR, path))
             .await
-            .map_err(|e| PublishError::io(format!("Failed to write file '{}': {:?}", output_path, e)))?;
+            .map_err(|e| PublishError::io(format!("Failed to back up '{}': {:?}", target, e)))?;
+        match change {
+            Staged::Written => rename_file(&format!("{}/{}", self.staging_dir(), path), &target)
+                .await
+                .map_err(|e| PublishError::io(format!("Failed to move '{}' into place: {:?}", target, e)))?,
+            Staged::Removed => remove_file(&target)
+                .await
+                .map_err(|e| PublishError::io(format!("Failed to remove file '{}': {:?}", target, e)))?,
+        }
+        Ok(backed_up.is_truthy())
+    }
+
+    /// Undo a change made by [`apply`](Self::apply)
+    async fn undo(&self, path: &str, backed_up: bool) -> Result<(), JsValue> {
+        let target = format!("{}/{}", self.path, path);
+        if backed_up {
+            rename_file(&format!("{}/{}/{}", self.path, BACKUP_DIR, path), &target).await
+        } else {
+            remove_file(&target).await
+        }
+    }
+
+    /// Remove the staging and backup directories
+    async fn clean_up(&self) -> Result<(), PublishError> {
+        for dir in [self.staging_dir(), format!("{}/{}", self.path, BACKUP_DIR)] {
+            remove_dir(&dir)
+                .await
+                .map_err(|e| PublishError::io(format!("Failed to remove '{}': {:?}", dir, e)))?;
+        }
+        Ok(())
+    }
+}
+
+impl GraphSource for NodeDir {
+    async fn read_graph_files(&self) -> Result<GraphFiles, PublishError> {
+        read_graph_files(&self.path).await
+    }
+}
+
+/// Files picked or dropped in the browser: a `FileList`, an array of `File`s or a
+/// `FileSystemDirectoryHandle` for the graph folder
+#[derive(Debug, Clone)]
+pub struct BrowserFiles {
+    input: JsValue,
+}
+
+impl BrowserFiles {
+    pub fn new(input: JsValue) -> Self {
+        Self { input }
+    }
+}
+
+impl GraphSource for BrowserFiles {
+    async fn read_graph_files(&self) -> Result<GraphFiles, PublishError> {
+        let files_array = read_dropped_files(&self.input)
+            .await
+            .map_err(|e| PublishError::io(format!("Failed to read dropped files: {:?}", e)))?;
+
+        source_files(files_array)
+    }
+}
+
+impl OutputSink for NodeDir {
+    async fn write_file(&mut self, path: &str, content: &[u8]) -> Result<(), PublishError> {
+        // Validate output directory and file path
+        validate_input_path(&self.path)?;
+        validate_file_path(path)?;
+
+        let output_path = match &mut self.staging {
+            Some(staging) => {
+                staging.record(path, Staged::Written);
+                format!("{}/{}/{}", self.path, STAGING_DIR, path)
+            }
+            None => format!("{}/{}", self.path, path),
+        };
+        write_binary_file(&output_path, content)
+            .await
+            .map_err(|e| PublishError::io(format!("Failed to write file '{}': {:?}", output_path, e)))
+    }
+
+    async fn read_file(&self, path: &str) -> Result<Option<Vec<u8>>, PublishError> {
+        validate_input_path(&self.path)?;
+        validate_file_path(path)?;
+
+        let file_path = match self.staging.as_ref().and_then(|staging| staging.get(path)) {
+            Some(Staged::Removed) => return Ok(None),
+            Some(Staged::Written) => format!("{}/{}", self.staging_dir(), path),
+            None => format!("{}/{}", self.path, path),
+        };
+        let content = read_binary_file(&file_path)
+            .await
+            .map_err(|e| PublishError::io(format!("Failed to read file '{}': {:?}", file_path, e)))?;
+        // The helper resolves to undefined for missing files
+        Ok((!content.is_undefined()).then(|| Uint8Array::new(&content).to_vec()))
+    }
+
+    async fn remove_file(&mut self, path: &str) -> Result<(), PublishError> {
+        validate_input_path(&self.path)?;
+        validate_file_path(path)?;
+
+        let file_path = match &mut self.staging {
+            // Only a staged copy needs removing now, the file itself goes on commit
+            Some(staging) => match staging.record(path, Staged::Removed) {
+                Some(Staged::Written) => format!("{}/{}/{}", self.path, STAGING_DIR, path),
+                _ => return Ok(()),
+            },
+            None => format!("{}/{}", self.path, path),
+        };
+        remove_file(&file_path)
+            .await
+            .map_err(|e| PublishError::io(format!("Failed to remove file '{}': {:?}", file_path, e)))
+    }
+
+    async fn begin(&mut self) -> Result<(), PublishError> {
+        validate_input_path(&self.path)?;
+
+        // Left over by a build that was killed before it could clean up
+        self.clean_up().await?;
+        self.staging = Some(Staging::default());
+        Ok(())
+    }
+
+    async fn commit(&mut self) -> Result<(), PublishError> {
+        let Some(staging) = self.staging.take() else {
+            return Ok(());
+        };
+        let mut applied = Vec::new();
+        let mut result = Ok(());
+        for (path, change) in staging.changes() {
+            match self.apply(path, change).await {
+                Ok(backed_up) => applied.push((path, backed_up)),
+                Err(e) => {
+                    result = Err(e);
+                    break;
+                }
+            }
+        }
+        if result.is_err() {
+            // Put the previous files back, newest change first; the commit error is the
+            // one worth reporting
+            for (path, backed_up) in applied.into_iter().rev() {
+                let _ = self.undo(path, backed_up).await;
+            }
+        }
+        let cleaned = self.clean_up().await;
+        result.and(cleaned)
+    }
+
+    async fn rollback(&mut self) -> Result<(), PublishError> {
+        if self.staging.take().is_none() {
+            return Ok(());
+        }
+        self.clean_up().await
     }
-    Ok(())
 }
 
 /// JavaScript interop: Read directory recursively
@@ -110,10 +299,32 @@ extern "C" {
     async fn read_dir_recursive(path: &str) -> Result<Array, JsValue>;
 
     #[wasm_bindgen(catch)]
-    async fn write_file(path: &str, content: &str) -> Result<(), JsValue>;
+    async fn write_binary_file(path: &str, content: &[u8]) -> Result<(), JsValue>;
+
+    #[wasm_bindgen(catch)]
+    async fn read_binary_file(path: &str) -> Result<JsValue, JsValue>;
+
+    #[wasm_bindgen(catch)]
+    async fn remove_file(path: &str) -> Result<(), JsValue>;
 
     #[wasm_bindgen(catch)]
     async fn ensure_dir(path: &str) -> Result<(), JsValue>;
+
+    #[wasm_bindgen(catch)]
+    async fn rename_file(from: &str, to: &str) -> Result<(), JsValue>;
+
+    #[wasm_bindgen(catch)]
+    async fn backup_file(path: &str, backup: &str) -> Result<JsValue, JsValue>;
+
+    #[wasm_bindgen(catch)]
+    async fn remove_dir(path: &str) -> Result<(), JsValue>;
+}
+
+/// JavaScript interop: Read files picked or dropped in the browser
+#[wasm_bindgen(module = "/js/browser-helpers.js")]
+extern "C" {
+    #[wasm_bindgen(catch)]
+    async fn read_dropped_files(input: &JsValue) -> Result<Array, JsValue>;
 }
 
 #[cfg(test)]
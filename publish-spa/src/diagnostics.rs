@@ -0,0 +1,223 @@
+//! Problems found while publishing, with source locations and a terminal renderer
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        })
+    }
+}
+
+/// A source range; lines and columns are 1-based, columns count characters and
+/// the end column is exclusive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Span {
+    pub start_line: usize,
+    pub start_column: usize,
+    pub end_line: usize,
+    pub end_column: usize,
+}
+
+impl Span {
+    /// The whole of line `line` of `source`
+    pub fn line(source: &str, line: usize) -> Self {
+        let length = source.lines().nth(line.saturating_sub(1)).map_or(0, |text| text.chars().count());
+        Self {
+            start_line: line,
+            start_column: 1,
+            end_line: line,
+            end_column: length + 1,
+        }
+    }
+}
+
+/// A problem with one source file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// Stable identifier of the kind of problem, e.g. `unclosed-frontmatter`
+    pub code: String,
+    /// Graph-relative path of the file
+    pub file: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub span: Option<Span>,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn error(code: &str, file: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Error,
+            code: code.to_string(),
+            file: file.into(),
+            span: None,
+            message: message.into(),
+        }
+    }
+
+    pub fn warning(code: &str, file: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            ..Self::error(code, file, message)
+        }
+    }
+
+    pub fn with_span(mut self, span: Span) -> Self {
+        self.span = Some(span);
+        self
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.file)?;
+        if let Some(span) = &self.span {
+            write!(f, ":{}:{}", span.start_line, span.start_column)?;
+        }
+        write!(f, ": {}[{}]: {}", self.severity, self.code, self.message)
+    }
+}
+
+/// Whether diagnostics fail a publish
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ErrorPolicy {
+    /// Report diagnostics and publish the rest of the graph
+    #[default]
+    Warn,
+    /// Fail on any diagnostic
+    Strict,
+    /// Fail when there are more than this many diagnostics
+    Threshold(usize),
+}
+
+impl ErrorPolicy {
+    /// Whether a build with these diagnostics may be published
+    pub fn allows(&self, diagnostics: &[Diagnostic]) -> bool {
+        match self {
+            ErrorPolicy::Warn => true,
+            ErrorPolicy::Strict => diagnostics.is_empty(),
+            ErrorPolicy::Threshold(max) => diagnostics.len() <= *max,
+        }
+    }
+}
+
+impl fmt::Display for ErrorPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ErrorPolicy::Warn => f.write_str("warn"),
+            ErrorPolicy::Strict => f.write_str("strict"),
+            ErrorPolicy::Threshold(max) => write!(f, "threshold of {}", max),
+        }
+    }
+}
+
+/// Render a diagnostic for a terminal, with a code frame of `source` under
+/// the spanned lines when both are available:
+///
+/// ```text
+/// error[unclosed-frontmatter]: Frontmatter opened here is never closed
+///   --> pages/a.md:1:1
+///    |
+///  1 | ---
+///    | ^^^
+/// ```
+pub fn render(diagnostic: &Diagnostic, source: Option<&str>) -> String {
+    let mut out = format!("{}[{}]: {}\n", diagnostic.severity, diagnostic.code, diagnostic.message);
+
+    let Some(span) = diagnostic.span else {
+        out.push_str(&format!("  --> {}\n", diagnostic.file));
+        return out;
+    };
+    out.push_str(&format!("  --> {}:{}:{}\n", diagnostic.file, span.start_line, span.start_column));
+
+    let Some(source) = source else {
+        return out;
+    };
+    let lines: Vec<&str> = source.lines().collect();
+    let last = span.end_line.min(lines.len());
+    if span.start_line == 0 || span.start_line > last {
+        return out;
+    }
+
+    let width = last.to_string().len();
+    let gutter = " ".repeat(width);
+    out.push_str(&format!(" {} |\n", gutter));
+    for number in span.start_line..=last {
+        let text = lines[number - 1];
+        out.push_str(&format!(" {:>width$} | {}\n", number, text, width = width));
+
+        let length = text.chars().count();
+        let start = if number == span.start_line { span.start_column.max(1) } else { 1 };
+        let end = if number == span.end_line { span.end_column } else { length + 1 };
+        let carets = end.saturating_sub(start).max(1);
+        out.push_str(&format!(" {} | {}{}\n", gutter, " ".repeat(start - 1), "^".repeat(carets)));
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_code_frame() {
+        let source = "---\ntitle:: x\n- block";
+        let diagnostic = Diagnostic::error("unclosed-frontmatter", "pages/a.md", "Frontmatter is never closed")
+            .with_span(Span::line(source, 1));
+
+        assert_eq!(render(&diagnostic, Some(source)), "\
+error[unclosed-frontmatter]: Frontmatter is never closed
+  --> pages/a.md:1:1
+   |
+ 1 | ---
+   | ^^^
+");
+        assert_eq!(diagnostic.to_string(), "pages/a.md:1:1: error[unclosed-frontmatter]: Frontmatter is never closed");
+    }
+
+    #[test]
+    fn test_render_without_source() {
+        let diagnostic = Diagnostic::warning("slug-collision", "b.md", "Renamed");
+        assert_eq!(render(&diagnostic, None), "warning[slug-collision]: Renamed\n  --> b.md\n");
+    }
+
+    #[test]
+    fn test_error_policy() {
+        let diagnostics = vec![
+            Diagnostic::warning("slug-collision", "a.md", "Renamed"),
+            Diagnostic::warning("slug-collision", "b.md", "Renamed"),
+        ];
+        assert!(ErrorPolicy::Warn.allows(&diagnostics));
+        assert!(!ErrorPolicy::Strict.allows(&diagnostics));
+        assert!(ErrorPolicy::Strict.allows(&[]));
+        assert!(ErrorPolicy::Threshold(2).allows(&diagnostics));
+        assert!(!ErrorPolicy::Threshold(1).allows(&diagnostics));
+
+        let policy: ErrorPolicy = serde_json::from_str(r#"{"threshold": 5}"#).unwrap();
+        assert_eq!(policy, ErrorPolicy::Threshold(5));
+        assert_eq!(serde_json::from_str::<ErrorPolicy>(r#""strict""#).unwrap(), ErrorPolicy::Strict);
+    }
+
+    #[test]
+    fn test_render_multiline_span() {
+        let source = "one\ntwo words\nthree";
+        let span = Span { start_line: 2, start_column: 5, end_line: 3, end_column: 3 };
+        let diagnostic = Diagnostic::error("code", "f.md", "Message").with_span(span);
+
+        let frame = render(&diagnostic, Some(source));
+        assert!(frame.ends_with(" 2 | two words\n   |     ^^^^^\n 3 | three\n   | ^^\n"), "{}", frame);
+    }
+}
@@ -6,13 +6,6 @@ use serde::{Deserialize, Serialize};
 #[derive(Debug, Error, Serialize, Deserialize)]
 #[serde(tag = "type", content = "details")]
 pub enum PublishError {
-    /// Parse error in a specific file
-    #[error("Parse error in {file}: {message}")]
-    Parse {
-        file: String,
-        message: String,
-    },
-
     /// IO error during file operations
     #[error("IO error: {0}")]
     Io(String),
@@ -41,6 +34,10 @@ pub enum PublishError {
     #[error("Serialization error: {0}")]
     Serialization(String),
 
+    /// The publish was cancelled before it finished
+    #[error("Publishing was cancelled")]
+    Cancelled,
+
     /// Generic error with context
     #[error("{context}: {message}")]
     WithContext {
@@ -50,14 +47,6 @@ pub enum PublishError {
 }
 
 impl PublishError {
-    /// Create a parse error with file and message
-    pub fn parse(file: impl Into<String>, message: impl Into<String>) -> Self {
-        Self::Parse {
-            file: file.into(),
-            message: message.into(),
-        }
-    }
-
     /// Create an IO error
     pub fn io(message: impl Into<String>) -> Self {
         Self::Io(message.into())
@@ -133,13 +122,6 @@ impl From<JsValue> for PublishError {
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_parse_error() {
-        let err = PublishError::parse("test.md", "invalid syntax");
-        assert!(err.to_string().contains("test.md"));
-        assert!(err.to_string().contains("invalid syntax"));
-    }
-
     #[test]
     fn test_io_error() {
         let err = PublishError::io("file not found");
@@ -170,6 +152,12 @@ mod tests {
         assert!(err.to_string().contains("Invalid path"));
     }
 
+    #[test]
+    fn test_cancelled_error() {
+        assert_eq!(PublishError::Cancelled.to_string(), "Publishing was cancelled");
+        assert_eq!(serde_json::to_string(&PublishError::Cancelled).unwrap(), r#"{"type":"Cancelled"}"#);
+    }
+
     #[test]
     fn test_with_context() {
         let err = PublishError::io("read failed").with_context("Loading configuration");
@@ -178,16 +166,15 @@ mod tests {
 
     #[test]
     fn test_error_to_jsvalue() {
-        let err = PublishError::parse("test.md", "syntax error");
+        let err = PublishError::io("file not found");
         let js_val: JsValue = err.into();
         assert!(!js_val.is_undefined());
     }
 
     #[test]
     fn test_serialization() {
-        let err = PublishError::parse("test.md", "syntax error");
+        let err = PublishError::io("file not found");
         let json = serde_json::to_string(&err).unwrap();
-        assert!(json.contains("Parse"));
-        assert!(json.contains("test.md"));
+        assert_eq!(json, r#"{"type":"Io","details":"file not found"}"#);
     }
 }
@@ -1,36 +1,361 @@
-use crate::graph::Graph;
+use crate::cache;
+use crate::diagnostics::Diagnostic;
+use crate::feed::{self, Feed, FeedEntry};
+use crate::graph::{Graph, MissingPage, RelatedPage, UnlinkedReference};
+use crate::health::{HealthReport, HealthRule};
+use crate::metadata::{self, PageMetadata};
+use crate::parallel;
 use crate::parser::{Block, Page};
+use crate::sitemap::{self, SitemapEntry};
+use crate::slug::{self, SlugStrategy};
+use crate::theme::{self, AccentColor, ThemeMode};
+use crate::urls::{LinkStyle, UrlBuilder};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use regex::Regex;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::LazyLock;
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct ExportConfig {
     pub theme: String,
+    #[serde(default)]
+    pub theme_mode: ThemeMode,
+    #[serde(default)]
+    pub accent_color: AccentColor,
     pub include_backlinks: bool,
     pub include_graph_view: bool,
     pub custom_css: Option<String>,
+    #[serde(default)]
+    pub include_unlinked_references: bool,
+    #[serde(default = "default_max_unlinked_references")]
+    pub max_unlinked_references: usize,
+    /// Show pages related by co-citation, shared links and shared tags
+    #[serde(default)]
+    pub include_related_pages: bool,
+    #[serde(default = "default_min_related_score")]
+    pub min_related_score: usize,
+    #[serde(default = "default_max_related_pages")]
+    pub max_related_pages: usize,
+    #[serde(default = "default_generate_stub_pages")]
+    pub generate_stub_pages: bool,
+    /// Public URL the site is deployed at, enabling sitemap.xml, robots.txt and canonical links
+    #[serde(default)]
+    pub base_url: Option<String>,
+    /// Write an Atom feed of the latest journal pages to feed.xml
+    #[serde(default)]
+    pub generate_feed: bool,
+    #[serde(default = "default_feed_entries")]
+    pub feed_entries: usize,
+    /// Also write one feed per tag under feeds/tags/
+    #[serde(default)]
+    pub tag_feeds: bool,
+    #[serde(default)]
+    pub link_style: LinkStyle,
+    /// Sub-path the site is served under, e.g. `/project/` for GitHub Pages project sites
+    #[serde(default)]
+    pub base_path: Option<String>,
+    #[serde(default)]
+    pub slug_strategy: SlugStrategy,
+    /// Strategies the site was previously published with; their page paths redirect to the current ones
+    #[serde(default)]
+    pub redirect_from: Vec<SlugStrategy>,
+    /// Minify HTML pages, style.css (including custom CSS) and app.js
+    #[serde(default = "default_minify")]
+    pub minify: bool,
+    /// Write `.gz` and `.br` siblings of every text output for static hosts to serve directly
+    #[serde(default)]
+    pub precompress: bool,
+}
+
+/// Title of the published site, used for the index page and feeds
+const SITE_TITLE: &str = "Logseq Published Graph";
+
+/// Default number of entries per feed
+pub fn default_feed_entries() -> usize {
+    20
+}
+
+/// Default cap on unlinked references rendered per page
+pub fn default_max_unlinked_references() -> usize {
+    50
+}
+
+/// Default relatedness score a page needs to be listed as related
+pub fn default_min_related_score() -> usize {
+    2
+}
+
+/// Default cap on related pages rendered per page
+pub fn default_max_related_pages() -> usize {
+    5
+}
+
+/// Stub pages are generated for missing link targets unless disabled
+pub fn default_generate_stub_pages() -> bool {
+    true
+}
+
+pub fn default_minify() -> bool {
+    true
+}
+
+/// Resolves wiki-link names to the output paths of pages or stub pages
+pub struct LinkResolver {
+    /// Page source paths to output paths
+    outputs: HashMap<String, String>,
+    /// Lowercased page titles and aliases to output paths
+    pages: HashMap<String, String>,
+    /// Lowercased missing page names to stub output paths
+    stubs: HashMap<String, String>,
+    urls: UrlBuilder,
+}
+
+impl LinkResolver {
+    pub fn new(graph: &Graph, config: &ExportConfig) -> Self {
+        let outputs = slug::assign_output_paths(graph.pages().map(|page| page.path.as_str()), config.slug_strategy)
+            .paths;
+
+        let pages = graph.name_index()
+            .into_iter()
+            .filter_map(|(name, mut paths)| {
+                paths.sort();
+                paths.first()
+                    .and_then(|path| outputs.get(path))
+                    .map(|output| (name, output.clone()))
+            })
+            .collect();
+
+        let stubs = if config.generate_stub_pages {
+            graph.missing_pages()
+                .iter()
+                .map(|missing| (missing.name.to_lowercase(), slug::stub_output_path(&missing.name, config.slug_strategy)))
+                .collect()
+        } else {
+            HashMap::new()
+        };
+
+        let urls = UrlBuilder::new(
+            config.link_style,
+            config.base_path.as_deref(),
+            config.base_url.as_deref(),
+            config.slug_strategy == SlugStrategy::Pretty,
+        );
+
+        Self { outputs, pages, stubs, urls }
+    }
+
+    /// Output path of the page with the given source path
+    pub fn page_path(&self, source: &str) -> String {
+        self.outputs.get(source)
+            .cloned()
+            .unwrap_or_else(|| slug::page_output_path(source, SlugStrategy::default()))
+    }
+
+    /// Output path a link name points to, or `None` when it should render as plain text
+    pub fn resolve(&self, name: &str) -> Option<&str> {
+        let key = name.to_lowercase();
+        self.pages.get(&key)
+            .or_else(|| self.stubs.get(&key))
+            .map(String::as_str)
+    }
+
+    /// Href for the link name, as linked from the page at output path `from`
+    pub fn href(&self, from: &str, name: &str) -> Option<String> {
+        self.resolve(name).map(|target| self.url(from, target))
+    }
+
+    /// Href to the output file `to`, as linked from the page at output path `from`
+    pub fn url(&self, from: &str, to: &str) -> String {
+        self.urls.href(from, to)
+    }
+}
+
+/// Result of an incremental export
+#[derive(Debug, Default)]
+pub struct IncrementalExport {
+    /// Generated files; pages that did not need re-rendering are left out
+    pub files: HashMap<String, String>,
+    /// Render key of every page by source path
+    pub render_keys: HashMap<String, String>,
+    /// Output path of every page by source path
+    pub page_outputs: HashMap<String, String>,
+    /// Output paths of pages whose previous output is still current
+    pub reused: HashSet<String>,
+    /// Stub pages and redirects left out because their output path was already taken
+    pub collisions: Vec<Diagnostic>,
 }
 
 /// Export entire graph to HTML files
+#[allow(dead_code)]
 pub fn export_graph_to_html(graph: &Graph, config: &ExportConfig) -> Result<HashMap<String, String>, String> {
+    export_graph_incremental(graph, config, &HashMap::new(), &HashMap::new(), |_, _| true).map(|export| export.files)
+}
+
+/// Export the graph, skipping pages whose render key matches `previous_keys`
+///
+/// A page's render key covers its source hash (from `source_hashes`) and everything
+/// else its HTML depends on: output path, backlinks, unlinked references, related
+/// pages and the targets of its links. The caller is responsible for invalidating keys when the
+/// export config changes.
+///
+/// `on_rendered` is called with the number of pages done and in total after each page;
+/// the export stops with an error once it returns false.
+pub fn export_graph_incremental(
+    graph: &Graph,
+    config: &ExportConfig,
+    source_hashes: &HashMap<String, String>,
+    previous_keys: &HashMap<String, String>,
+    mut on_rendered: impl FnMut(usize, usize) -> bool,
+) -> Result<IncrementalExport, String> {
     let mut html_files = HashMap::new();
+    let mut render_keys = HashMap::new();
+    let mut page_outputs = HashMap::new();
+    let mut reused = HashSet::new();
+
+    let links = LinkResolver::new(graph, config);
 
     // Generate index page
-    let index_html = generate_index_page(graph, config);
+    let index_html = generate_index_page(graph, &links, config);
     html_files.insert("index.html".to_string(), index_html);
 
-    // Generate individual page HTML
-    for page in graph.pages() {
+    let unlinked = if config.include_unlinked_references {
+        graph.unlinked_references(config.max_unlinked_references)
+    } else {
+        HashMap::new()
+    };
+
+    let related = if config.include_related_pages {
+        graph.related_pages(config.min_related_score, config.max_related_pages)
+    } else {
+        HashMap::new()
+    };
+
+    // Generate individual page HTML, rendering pages in parallel
+    let pages: Vec<&Page> = graph.pages().collect();
+    let total = pages.len();
+    let rendered = parallel::map_with_progress(pages, |page: &Page| {
         let backlinks = graph.get_backlinks(&page.path);
-        let page_html = export_page_to_html(page, &backlinks, config);
+        let unlinked_refs = unlinked.get(&page.path).map(Vec::as_slice).unwrap_or_default();
+        let related_pages = related.get(&page.path).map(Vec::as_slice).unwrap_or_default();
+        let output_path = links.page_path(&page.path);
+
+        let source_hash = source_hashes.get(&page.path).map(String::as_str).unwrap_or_default();
+        let key = render_key(page, source_hash, &backlinks, unlinked_refs, related_pages, &links);
+        let page_html = (previous_keys.get(&page.path) != Some(&key))
+            .then(|| export_page_to_html(page, &backlinks, unlinked_refs, related_pages, &links, config));
+        (page, output_path, key, page_html)
+    }, |done| on_rendered(done, total))
+    .ok_or_else(|| "Export cancelled".to_string())?;
+
+    for (page, output_path, key, page_html) in rendered {
+        if let Some(page_html) = page_html {
+            html_files.insert(output_path.clone(), page_html);
+        } else {
+            reused.insert(output_path.clone());
+        }
+        render_keys.insert(page.path.clone(), key);
+        page_outputs.insert(page.path.clone(), output_path);
+    }
+
+    // Generate stub pages for links to pages without a source file
+    let mut stub_paths = Vec::new();
+    let mut collisions = Vec::new();
+    if config.generate_stub_pages {
+        for missing in graph.missing_pages() {
+            let html_path = slug::stub_output_path(&missing.name, config.slug_strategy);
+            match html_files.entry(html_path) {
+                Entry::Vacant(entry) => {
+                    let stub_html = export_stub_page_to_html(&missing, entry.key(), &links, config);
+                    stub_paths.push(entry.key().clone());
+                    entry.insert(stub_html);
+                }
+                Entry::Occupied(entry) => collisions.push(Diagnostic::warning(
+                    "slug-collision",
+                    missing.referenced_by.first().cloned().unwrap_or_default(),
+                    format!("No stub page for [[{}]]: output path {} is already taken", missing.name, entry.key()),
+                )),
+            }
+        }
+    }
 
-        // Convert path to HTML file path
-        let html_path = page.path
-            .trim_end_matches(".md")
-            .trim_end_matches(".markdown")
-            .to_string() + ".html";
+    // Redirect page paths from previous slug strategies to the current ones
+    if !config.redirect_from.is_empty() {
+        let sources: HashMap<&String, &String> = links.outputs.iter()
+            .map(|(source, output)| (output, source))
+            .collect();
+        let mut redirects = BTreeMap::new();
+        for (old_path, new_path) in slug::legacy_redirects(&links.outputs, &config.redirect_from) {
+            match html_files.entry(old_path) {
+                Entry::Vacant(entry) => {
+                    let redirect_html = generate_redirect_page(entry.key(), &new_path, &links, config);
+                    redirects.insert(entry.key().clone(), new_path);
+                    entry.insert(redirect_html);
+                }
+                Entry::Occupied(entry) => collisions.push(Diagnostic::warning(
+                    "slug-collision",
+                    sources.get(&new_path).map_or_else(String::new, |source| source.to_string()),
+                    format!("No redirect from {}: the path is already taken", entry.key()),
+                )),
+            }
+        }
 
-        html_files.insert(html_path, page_html);
+        let redirects_json = serde_json::to_string_pretty(&redirects)
+            .map_err(|e| format!("Failed to serialize redirects: {}", e))?;
+        html_files.insert("redirects.json".to_string(), redirects_json);
+    }
+
+    // Generate Atom feeds of the latest journals and, optionally, of each tag
+    if config.generate_feed {
+        let journals: Vec<&Page> = graph.pages().filter(|page| feed::is_journal(page)).collect();
+        html_files.insert("feed.xml".to_string(),
+            generate_feed("feed.xml", SITE_TITLE, &journals, &links, config));
+
+        if config.tag_feeds {
+            let mut tags: Vec<String> = graph.pages()
+                .flat_map(|page| page.tags.iter().map(|tag| tag.to_lowercase()))
+                .collect();
+            tags.sort();
+            tags.dedup();
+
+            for tag in tags {
+                let tagged: Vec<&Page> = graph.pages()
+                    .filter(|page| page.tags.iter().any(|t| t.to_lowercase() == tag))
+                    .collect();
+                let feed_path = format!("feeds/tags/{}.xml", tag);
+                let title = format!("{} · #{}", SITE_TITLE, tag);
+                let feed_xml = generate_feed(&feed_path, &title, &tagged, &links, config);
+                html_files.insert(feed_path, feed_xml);
+            }
+        }
+    }
+
+    // Generate search engine files when the public URL is known
+    if let Some(base_url) = &config.base_url {
+        let mut pages: Vec<&Page> = graph.pages()
+            .filter(|page| !sitemap::excluded_from_sitemap(page))
+            .collect();
+        pages.sort_by(|a, b| a.path.cmp(&b.path));
+
+        let mut entries = vec![SitemapEntry {
+            loc: sitemap::canonical_url(base_url, "index.html"),
+            lastmod: None,
+        }];
+        entries.extend(pages.into_iter().map(|page| SitemapEntry {
+            loc: sitemap::canonical_url(base_url, &links.page_path(&page.path)),
+            lastmod: sitemap::last_modified(page),
+        }));
+        stub_paths.sort();
+        entries.extend(stub_paths.iter().map(|path| SitemapEntry {
+            loc: sitemap::canonical_url(base_url, path),
+            lastmod: None,
+        }));
+
+        html_files.insert("sitemap.xml".to_string(), sitemap::generate_sitemap(&entries));
+        // Crawlers only look for robots.txt at the root of a host
+        if sitemap::is_host_root(base_url) {
+            html_files.insert("robots.txt".to_string(), sitemap::generate_robots_txt(base_url));
+        }
     }
 
     // Generate CSS file
@@ -39,22 +364,78 @@ pub fn export_graph_to_html(graph: &Graph, config: &ExportConfig) -> Result<Hash
     // Generate JavaScript file
     html_files.insert("app.js".to_string(), generate_js(config));
 
-    Ok(html_files)
+    Ok(IncrementalExport { files: html_files, render_keys, page_outputs, reused, collisions })
+}
+
+/// Hash of the inputs of `export_page_to_html` besides the export config
+fn render_key(
+    page: &Page,
+    source_hash: &str,
+    backlinks: &[String],
+    unlinked: &[UnlinkedReference],
+    related: &[RelatedPage],
+    links: &LinkResolver,
+) -> String {
+    let mut inputs = vec![
+        source_hash.to_string(),
+        links.page_path(&page.path),
+        format!("{:?}", page.modified),
+    ];
+
+    for backlink in backlinks {
+        inputs.push(format!("backlink {} {}", backlink, links.page_path(backlink)));
+    }
+
+    let mut names: Vec<&str> = page.links.iter().map(String::as_str).collect();
+    for reference in unlinked {
+        inputs.push(format!("unlinked {} {} {}", reference.source, links.page_path(&reference.source), reference.content));
+        names.extend(wiki_link_names(&reference.content));
+    }
+
+    for page in related {
+        inputs.push(format!("related {} {:?}", links.page_path(&page.path), page));
+    }
+
+    // Links render as anchors or plain text depending on what they resolve to
+    names.sort_unstable();
+    names.dedup();
+    for name in names {
+        inputs.push(format!("link {} {:?}", name, links.resolve(name)));
+    }
+
+    cache::content_hash(inputs.join("\n").as_bytes())
 }
 
-fn generate_index_page(graph: &Graph, _config: &ExportConfig) -> String {
+/// Targets of the `[[wiki links]]` in block content
+fn wiki_link_names(content: &str) -> Vec<&str> {
+    WIKI_LINK_REGEX
+        .captures_iter(content)
+        .filter_map(|cap| cap.get(1).map(|name| name.as_str()))
+        .collect()
+}
+
+fn generate_index_page(graph: &Graph, links: &LinkResolver, config: &ExportConfig) -> String {
+    let from = "index.html";
     let stats = graph.stats();
 
     let mut html = String::from("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n");
     html.push_str("<meta charset=\"UTF-8\">\n");
     html.push_str("<meta name=\"viewport\" content=\"width=device-width, initial-scale=1.0\">\n");
-    html.push_str("<title>Logseq Published Graph</title>\n");
-    html.push_str("<link rel=\"stylesheet\" href=\"style.css\">\n");
+    html.push_str(&format!("<title>{}</title>\n", SITE_TITLE));
+    if let Some(base_url) = &config.base_url {
+        let canonical = sitemap::canonical_url(base_url, "index.html");
+        html.push_str(&format!("<link rel=\"canonical\" href=\"{}\">\n", escape_html_string(&canonical)));
+    }
+    if config.generate_feed {
+        html.push_str(&format!("<link rel=\"alternate\" type=\"application/atom+xml\" title=\"{}\" href=\"{}\">\n",
+            SITE_TITLE, links.url(from, "feed.xml")));
+    }
+    html.push_str(&format!("<link rel=\"stylesheet\" href=\"{}\">\n", links.url(from, "style.css")));
     html.push_str("</head>\n<body>\n");
 
     html.push_str("<div class=\"container\">\n");
     html.push_str("<header>\n");
-    html.push_str("<h1>📚 Logseq Published Graph</h1>\n");
+    html.push_str(&format!("<h1>📚 {}</h1>\n", SITE_TITLE));
     html.push_str(&format!("<p class=\"stats\">{} pages • {} blocks • {} links</p>\n",
         stats.page_count, stats.total_blocks, stats.total_links));
     html.push_str("</header>\n");
@@ -64,12 +445,12 @@ fn generate_index_page(graph: &Graph, _config: &ExportConfig) -> String {
     html.push_str("<ul class=\"page-list\">\n");
 
     let mut pages: Vec<_> = graph.pages().collect();
-    pages.sort_by(|a, b| a.title.cmp(&b.title));
+    pages.sort_by(|a, b| a.title.cmp(&b.title).then_with(|| a.path.cmp(&b.path)));
 
     for page in pages {
-        let html_path = sanitize_html_path(&page.path);
+        let href = links.url(from, &links.page_path(&page.path));
         let escaped_title = escape_html_string(&page.title);
-        html.push_str(&format!("<li><a href=\"{}\">{}</a></li>\n", html_path, escaped_title));
+        html.push_str(&format!("<li><a href=\"{}\">{}</a></li>\n", escape_html_string(&href), escaped_title));
     }
 
     html.push_str("</ul>\n");
@@ -80,24 +461,41 @@ fn generate_index_page(graph: &Graph, _config: &ExportConfig) -> String {
     html.push_str("</footer>\n");
     html.push_str("</div>\n");
 
-    html.push_str("<script src=\"app.js\"></script>\n");
+    html.push_str(&format!("<script src=\"{}\"></script>\n", links.url(from, "app.js")));
     html.push_str("</body>\n</html>");
 
     html
 }
 
 /// Export single page to standalone HTML
-pub fn export_page_to_html(page: &Page, backlinks: &[String], config: &ExportConfig) -> String {
+pub fn export_page_to_html(
+    page: &Page,
+    backlinks: &[String],
+    unlinked: &[UnlinkedReference],
+    related: &[RelatedPage],
+    links: &LinkResolver,
+    config: &ExportConfig,
+) -> String {
+    let from = links.page_path(&page.path);
     let mut html = String::from("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n");
     html.push_str("<meta charset=\"UTF-8\">\n");
     html.push_str("<meta name=\"viewport\" content=\"width=device-width, initial-scale=1.0\">\n");
     let escaped_title = escape_html_string(&page.title);
     html.push_str(&format!("<title>{}</title>\n", escaped_title));
-    html.push_str("<link rel=\"stylesheet\" href=\"../style.css\">\n");
+    if let Some(base_url) = &config.base_url {
+        let canonical = sitemap::canonical_url(base_url, &from);
+        html.push_str(&format!("<link rel=\"canonical\" href=\"{}\">\n", escape_html_string(&canonical)));
+    }
+    if sitemap::is_noindex(page) {
+        html.push_str("<meta name=\"robots\" content=\"noindex\">\n");
+    }
+    let meta = metadata::page_metadata(page, &from, config.base_url.as_deref());
+    html.push_str(&render_metadata(&meta));
+    html.push_str(&format!("<link rel=\"stylesheet\" href=\"{}\">\n", links.url(&from, "style.css")));
     html.push_str("</head>\n<body>\n");
 
     html.push_str("<div class=\"container\">\n");
-    html.push_str("<nav><a href=\"../index.html\">← Back to Index</a></nav>\n");
+    html.push_str(&format!("<nav><a href=\"{}\">← Back to Index</a></nav>\n", links.url(&from, "index.html")));
 
     let escaped_path = escape_html_string(&page.path);
     html.push_str(&format!("<article data-path=\"{}\">\n", escaped_path));
@@ -127,7 +525,7 @@ pub fn export_page_to_html(page: &Page, backlinks: &[String], config: &ExportCon
     // Blocks
     html.push_str("<div class=\"blocks\">\n");
     for block in &page.blocks {
-        html.push_str(&render_block(block));
+        html.push_str(&render_block(block, links, &from));
     }
     html.push_str("</div>\n");
 
@@ -137,9 +535,38 @@ pub fn export_page_to_html(page: &Page, backlinks: &[String], config: &ExportCon
         html.push_str("<h2>📎 Linked References</h2>\n");
         html.push_str("<ul>\n");
         for backlink in backlinks {
-            let html_path = sanitize_html_path(backlink);
+            let href = links.url(&from, &links.page_path(backlink));
             let escaped_backlink = escape_html_string(backlink);
-            html.push_str(&format!("<li><a href=\"{}\">{}</a></li>\n", html_path, escaped_backlink));
+            html.push_str(&format!("<li><a href=\"{}\">{}</a></li>\n", escape_html_string(&href), escaped_backlink));
+        }
+        html.push_str("</ul>\n");
+        html.push_str("</div>\n");
+    }
+
+    // Unlinked references
+    if config.include_unlinked_references && !unlinked.is_empty() {
+        html.push_str("<div class=\"unlinked-references\">\n");
+        html.push_str("<h2>🔗 Unlinked References</h2>\n");
+        html.push_str("<ul>\n");
+        for reference in unlinked {
+            let href = links.url(&from, &links.page_path(&reference.source));
+            let escaped_source = escape_html_string(&reference.source);
+            html.push_str(&format!("<li><a href=\"{}\">{}</a><div class=\"reference-content\">{}</div></li>\n",
+                escape_html_string(&href), escaped_source, render_markdown(&reference.content, links, &from)));
+        }
+        html.push_str("</ul>\n");
+        html.push_str("</div>\n");
+    }
+
+    // Related pages
+    if config.include_related_pages && !related.is_empty() {
+        html.push_str("<div class=\"related-pages\">\n");
+        html.push_str("<h2>🧭 Related</h2>\n");
+        html.push_str("<ul>\n");
+        for page in related {
+            let href = links.url(&from, &links.page_path(&page.path));
+            html.push_str(&format!("<li><a href=\"{}\">{}</a><span class=\"related-reasons\">{}</span></li>\n",
+                escape_html_string(&href), escape_html_string(&page.title), escape_html_string(&related_reasons(page))));
         }
         html.push_str("</ul>\n");
         html.push_str("</div>\n");
@@ -148,24 +575,273 @@ pub fn export_page_to_html(page: &Page, backlinks: &[String], config: &ExportCon
     html.push_str("</article>\n");
     html.push_str("</div>\n");
 
-    html.push_str("<script src=\"../app.js\"></script>\n");
+    html.push_str(&format!("<script src=\"{}\"></script>\n", links.url(&from, "app.js")));
     html.push_str("</body>\n</html>");
 
     html
 }
 
-fn render_block(block: &Block) -> String {
+/// Why a page is related, e.g. "2 shared links · #rust"
+fn related_reasons(page: &RelatedPage) -> String {
+    let plural = |count: usize, noun: &str| format!("{} {}{}", count, noun, if count == 1 { "" } else { "s" });
+
+    let mut reasons = Vec::new();
+    if page.co_citations > 0 {
+        reasons.push(plural(page.co_citations, "co-citation"));
+    }
+    if page.shared_links > 0 {
+        reasons.push(plural(page.shared_links, "shared link"));
+    }
+    reasons.extend(page.shared_tags.iter().map(|tag| format!("#{}", tag)));
+    reasons.join(" · ")
+}
+
+/// Render description, OpenGraph, Twitter card and JSON-LD tags for a page head
+fn render_metadata(meta: &PageMetadata) -> String {
+    let mut tags: Vec<(&str, &str, &str)> = Vec::new();
+
+    if let Some(description) = &meta.description {
+        tags.push(("name", "description", description));
+    }
+    if let Some(author) = &meta.author {
+        tags.push(("name", "author", author));
+    }
+
+    tags.push(("property", "og:title", &meta.title));
+    tags.push(("property", "og:type", "article"));
+    if let Some(description) = &meta.description {
+        tags.push(("property", "og:description", description));
+    }
+    if let Some(url) = &meta.url {
+        tags.push(("property", "og:url", url));
+    }
+    if let Some(image) = &meta.image {
+        tags.push(("property", "og:image", image));
+    }
+    if let Some(date) = &meta.date_published {
+        tags.push(("property", "article:published_time", date));
+    }
+
+    let card = if meta.image.is_some() { "summary_large_image" } else { "summary" };
+    tags.push(("name", "twitter:card", card));
+    tags.push(("name", "twitter:title", &meta.title));
+    if let Some(description) = &meta.description {
+        tags.push(("name", "twitter:description", description));
+    }
+    if let Some(image) = &meta.image {
+        tags.push(("name", "twitter:image", image));
+    }
+
+    let mut html = String::new();
+    for (attribute, key, content) in tags {
+        html.push_str(&format!("<meta {}=\"{}\" content=\"{}\">\n",
+            attribute, key, escape_html_string(content)));
+    }
+    html.push_str(&format!("<script type=\"application/ld+json\">{}</script>\n", metadata::json_ld(meta)));
+
+    html
+}
+
+/// Export a stub page for a link target that has no source file
+pub fn export_stub_page_to_html(
+    missing: &MissingPage,
+    html_path: &str,
+    links: &LinkResolver,
+    config: &ExportConfig,
+) -> String {
+    let escaped_title = escape_html_string(&missing.name);
+
+    let mut html = String::from("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n");
+    html.push_str("<meta charset=\"UTF-8\">\n");
+    html.push_str("<meta name=\"viewport\" content=\"width=device-width, initial-scale=1.0\">\n");
+    html.push_str(&format!("<title>{}</title>\n", escaped_title));
+    if let Some(base_url) = &config.base_url {
+        let canonical = sitemap::canonical_url(base_url, html_path);
+        html.push_str(&format!("<link rel=\"canonical\" href=\"{}\">\n", escape_html_string(&canonical)));
+    }
+    let meta = metadata::stub_metadata(&missing.name, html_path, config.base_url.as_deref());
+    html.push_str(&render_metadata(&meta));
+    html.push_str(&format!("<link rel=\"stylesheet\" href=\"{}\">\n", links.url(html_path, "style.css")));
+    html.push_str("</head>\n<body>\n");
+
+    html.push_str("<div class=\"container\">\n");
+    html.push_str(&format!("<nav><a href=\"{}\">← Back to Index</a></nav>\n", links.url(html_path, "index.html")));
+
+    html.push_str("<article class=\"stub-page\">\n");
+    html.push_str(&format!("<h1>{}</h1>\n", escaped_title));
+    html.push_str("<p class=\"stub-notice\">This page has no content yet.</p>\n");
+
+    if config.include_backlinks && !missing.referenced_by.is_empty() {
+        html.push_str("<div class=\"backlinks\">\n");
+        html.push_str("<h2>📎 Linked References</h2>\n");
+        html.push_str("<ul>\n");
+        for source in &missing.referenced_by {
+            let href = links.url(html_path, &links.page_path(source));
+            html.push_str(&format!("<li><a href=\"{}\">{}</a></li>\n",
+                escape_html_string(&href), escape_html_string(source)));
+        }
+        html.push_str("</ul>\n");
+        html.push_str("</div>\n");
+    }
+
+    html.push_str("</article>\n");
+    html.push_str("</div>\n");
+
+    html.push_str(&format!("<script src=\"{}\"></script>\n", links.url(html_path, "app.js")));
+    html.push_str("</body>\n</html>");
+
+    html
+}
+
+/// Standalone page listing the issues of a health report, grouped by rule
+pub fn export_health_report_to_html(report: &HealthReport, links: &LinkResolver) -> String {
+    let from = crate::health::REPORT_HTML_PATH;
+    let total: usize = report.counts.values().sum();
+
+    let mut html = String::from("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n");
+    html.push_str("<meta charset=\"UTF-8\">\n");
+    html.push_str("<meta name=\"viewport\" content=\"width=device-width, initial-scale=1.0\">\n");
+    html.push_str("<title>Graph Health Report</title>\n");
+    html.push_str("<meta name=\"robots\" content=\"noindex\">\n");
+    html.push_str(&format!("<link rel=\"stylesheet\" href=\"{}\">\n", links.url(from, "style.css")));
+    html.push_str("</head>\n<body>\n");
+
+    html.push_str("<div class=\"container\">\n");
+    html.push_str(&format!("<nav><a href=\"{}\">← Back to Index</a></nav>\n", links.url(from, "index.html")));
+    html.push_str("<article class=\"health-report\">\n");
+    html.push_str("<h1>🩺 Graph Health Report</h1>\n");
+    html.push_str(&format!("<p class=\"stats\">{} pages • {} issues</p>\n", report.page_count, total));
+
+    html.push_str("<table class=\"health-summary\">\n");
+    for (rule, count) in &report.counts {
+        html.push_str(&format!("<tr><td>{}</td><td>{}</td></tr>\n", health_rule_title(*rule), count));
+    }
+    html.push_str("</table>\n");
+
+    for rule in HealthRule::ALL {
+        let issues: Vec<_> = report.issues.iter().filter(|issue| issue.rule == rule).collect();
+        if issues.is_empty() {
+            continue;
+        }
+
+        html.push_str(&format!("<h2>{}</h2>\n", health_rule_title(rule)));
+        html.push_str("<ul>\n");
+        for issue in issues {
+            let href = links.url(from, &links.page_path(&issue.page));
+            html.push_str(&format!("<li><a href=\"{}\">{}</a> <span class=\"health-message\">{}</span></li>\n",
+                escape_html_string(&href), escape_html_string(&issue.page), escape_html_string(&issue.message)));
+        }
+        html.push_str("</ul>\n");
+    }
+
+    html.push_str("</article>\n");
+    html.push_str("</div>\n");
+    html.push_str("</body>\n</html>");
+
+    html
+}
+
+fn health_rule_title(rule: HealthRule) -> &'static str {
+    match rule {
+        HealthRule::BrokenLink => "Broken links",
+        HealthRule::OrphanPage => "Orphan pages",
+        HealthRule::DeadEnd => "Dead ends",
+        HealthRule::DuplicateTitle => "Duplicate titles",
+        HealthRule::EmptyPage => "Empty pages",
+        HealthRule::StaleBlockRef => "Stale block references",
+    }
+}
+
+/// Generate a page at `html_path` that forwards visitors to `target_path`
+fn generate_redirect_page(
+    html_path: &str,
+    target_path: &str,
+    links: &LinkResolver,
+    config: &ExportConfig,
+) -> String {
+    let href = escape_html_string(&links.url(html_path, target_path));
+
+    let mut html = String::from("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n");
+    html.push_str("<meta charset=\"UTF-8\">\n");
+    html.push_str("<title>Redirecting…</title>\n");
+    if let Some(base_url) = &config.base_url {
+        let canonical = sitemap::canonical_url(base_url, target_path);
+        html.push_str(&format!("<link rel=\"canonical\" href=\"{}\">\n", escape_html_string(&canonical)));
+    }
+    html.push_str("<meta name=\"robots\" content=\"noindex\">\n");
+    html.push_str(&format!("<meta http-equiv=\"refresh\" content=\"0; url={}\">\n", href));
+    html.push_str("</head>\n<body>\n");
+    html.push_str(&format!("<p>This page has moved to <a href=\"{}\">{}</a>.</p>\n", href, href));
+    html.push_str("</body>\n</html>");
+
+    html
+}
+
+/// Generate an Atom feed at `feed_path` of the most recent dated `pages`
+fn generate_feed(
+    feed_path: &str,
+    title: &str,
+    pages: &[&Page],
+    links: &LinkResolver,
+    config: &ExportConfig,
+) -> String {
+    let base_url = config.base_url.as_deref();
+
+    let mut dated: Vec<(String, &Page)> = pages
+        .iter()
+        .filter_map(|page| {
+            feed::page_date(page)
+                .or_else(|| sitemap::last_modified(page))
+                .map(|date| (date, *page))
+        })
+        .collect();
+    dated.sort_by(|(date_a, a), (date_b, b)| date_b.cmp(date_a).then_with(|| a.path.cmp(&b.path)));
+    dated.truncate(config.feed_entries);
+
+    let entries = dated
+        .into_iter()
+        .map(|(date, page)| {
+            let html_path = links.page_path(&page.path);
+            let link = match base_url {
+                Some(base_url) => sitemap::canonical_url(base_url, &html_path),
+                None => links.url(feed_path, &html_path),
+            };
+            let content = page.blocks
+                .iter()
+                .map(|block| render_block(block, links, &html_path))
+                .collect();
+
+            FeedEntry {
+                id: feed::stable_id(base_url, &html_path),
+                title: page.title.clone(),
+                link,
+                date,
+                content,
+            }
+        })
+        .collect();
+
+    feed::generate_atom_feed(&Feed {
+        id: feed::stable_id(base_url, feed_path),
+        title: title.to_string(),
+        path: feed_path.to_string(),
+        base_url: config.base_url.clone(),
+        entries,
+    })
+}
+
+fn render_block(block: &Block, links: &LinkResolver, from: &str) -> String {
     let mut html = String::new();
 
     html.push_str(&format!("<div class=\"block\" data-id=\"{}\" data-level=\"{}\">\n",
         block.id, block.level));
     html.push_str(&format!("<div class=\"block-content\">{}</div>\n",
-        render_markdown(&block.content)));
+        render_markdown(&block.content, links, from)));
 
     if !block.children.is_empty() {
         html.push_str("<div class=\"block-children\">\n");
         for child in &block.children {
-            html.push_str(&render_block(child));
+            html.push_str(&render_block(child, links, from));
         }
         html.push_str("</div>\n");
     }
@@ -175,33 +851,39 @@ fn render_block(block: &Block) -> String {
     html
 }
 
-fn render_markdown(content: &str) -> String {
+// Inline markdown syntax, compiled once and shared by every page and thread
+static WIKI_LINK_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\[\[([^\]]+)\]\]").unwrap());
+static TAG_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"#(\w+)").unwrap());
+static BOLD_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\*\*([^*]+)\*\*").unwrap());
+static ITALIC_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\*([^*]+)\*").unwrap());
+static CODE_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"`([^`]+)`").unwrap());
+
+fn render_markdown(content: &str, links: &LinkResolver, from: &str) -> String {
     // First escape HTML to prevent XSS
     let mut result = escape_html_string(content);
 
-    // Convert wiki-links [[page]] to HTML links (safe, escaped above)
-    let link_regex = regex::Regex::new(r"\[\[([^\]]+)\]\]").unwrap();
-    result = link_regex.replace_all(&result, |caps: &regex::Captures| {
+    // Convert wiki-links [[page]] to HTML links (safe, escaped above),
+    // or to plain text when the target has neither a page nor a stub
+    result = WIKI_LINK_REGEX.replace_all(&result, |caps: &regex::Captures| {
         let link_text = &caps[1]; // Already escaped
-        let html_path = sanitize_html_path(link_text);
-        format!("<a href=\"{}\" class=\"wiki-link\">{}</a>", html_path, link_text)
+        match links.href(from, &unescape_html_string(link_text)) {
+            Some(href) => format!("<a href=\"{}\" class=\"wiki-link\">{}</a>",
+                escape_html_string(&href), link_text),
+            None => link_text.to_string(),
+        }
     }).to_string();
 
     // Convert tags #tag to styled spans
-    let tag_regex = regex::Regex::new(r"#(\w+)").unwrap();
-    result = tag_regex.replace_all(&result, "<span class=\"tag\">#$1</span>").to_string();
+    result = TAG_REGEX.replace_all(&result, "<span class=\"tag\">#$1</span>").to_string();
 
     // Bold **text**
-    let bold_regex = regex::Regex::new(r"\*\*([^*]+)\*\*").unwrap();
-    result = bold_regex.replace_all(&result, "<strong>$1</strong>").to_string();
+    result = BOLD_REGEX.replace_all(&result, "<strong>$1</strong>").to_string();
 
     // Italic *text*
-    let italic_regex = regex::Regex::new(r"\*([^*]+)\*").unwrap();
-    result = italic_regex.replace_all(&result, "<em>$1</em>").to_string();
+    result = ITALIC_REGEX.replace_all(&result, "<em>$1</em>").to_string();
 
     // Code `code`
-    let code_regex = regex::Regex::new(r"`([^`]+)`").unwrap();
-    result = code_regex.replace_all(&result, "<code>$1</code>").to_string();
+    result = CODE_REGEX.replace_all(&result, "<code>$1</code>").to_string();
 
     result
 }
@@ -220,25 +902,19 @@ fn escape_html_string(s: &str) -> String {
         .collect()
 }
 
-/// Sanitize file path for HTML output
-fn sanitize_html_path(path: &str) -> String {
-    path.trim_end_matches(".md")
-        .trim_end_matches(".markdown")
-        .to_string() + ".html"
+/// Reverse `escape_html_string`
+fn unescape_html_string(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#x27;", "'")
+        .replace("&amp;", "&")
 }
 
 fn generate_css(config: &ExportConfig) -> String {
-    let mut css = String::from(r#"/* Logseq Publisher Styles */
-
-:root {
-    --bg-color: #ffffff;
-    --text-color: #2d3748;
-    --link-color: #3182ce;
-    --border-color: #e2e8f0;
-    --tag-bg: #edf2f7;
-    --block-border: #cbd5e0;
-}
-
+    let mut css = String::from("/* Logseq Publisher Styles */\n\n");
+    css.push_str(&theme::css_variables(config.theme_mode, config.accent_color));
+    css.push_str(r#"
 * {
     box-sizing: border-box;
     margin: 0;
@@ -275,7 +951,7 @@ h2 {
 }
 
 .stats {
-    color: #718096;
+    color: var(--muted-color);
     font-size: 0.95rem;
 }
 
@@ -298,7 +974,7 @@ h2 {
 }
 
 .page-list a:hover {
-    background: #ebf8ff;
+    background: var(--hover-bg);
 }
 
 nav {
@@ -332,7 +1008,7 @@ nav a {
 }
 
 .wiki-link:hover {
-    background: #ebf8ff;
+    background: var(--hover-bg);
     border-bottom-style: solid;
 }
 
@@ -346,7 +1022,7 @@ nav a {
 }
 
 .properties {
-    background: #f7fafc;
+    background: var(--surface-bg);
     padding: 1rem;
     border-radius: 0.5rem;
     margin: 1rem 0;
@@ -368,8 +1044,60 @@ nav a {
     margin: 0.5rem 0;
 }
 
+.unlinked-references {
+    margin-top: 2rem;
+}
+
+.unlinked-references ul {
+    list-style: none;
+    padding: 0;
+}
+
+.unlinked-references li {
+    margin: 0.75rem 0;
+}
+
+.related-pages {
+    margin-top: 2rem;
+}
+
+.related-pages ul {
+    list-style: none;
+    padding: 0;
+}
+
+.related-pages li {
+    margin: 0.5rem 0;
+}
+
+.related-reasons {
+    margin-left: 0.75rem;
+    color: var(--muted-color);
+    font-size: 0.875rem;
+}
+
+.health-summary td {
+    padding: 0.25rem 1rem 0.25rem 0;
+}
+
+.health-message {
+    color: var(--muted-color);
+    font-size: 0.875rem;
+}
+
+.stub-notice {
+    color: var(--muted-color);
+    font-style: italic;
+}
+
+.reference-content {
+    color: var(--muted-color);
+    font-size: 0.875rem;
+    padding-left: 1rem;
+}
+
 code {
-    background: #f7fafc;
+    background: var(--surface-bg);
     padding: 0.125rem 0.25rem;
     border-radius: 0.25rem;
     font-family: 'Monaco', 'Courier New', monospace;
@@ -381,7 +1109,7 @@ footer {
     padding-top: 2rem;
     border-top: 1px solid var(--border-color);
     text-align: center;
-    color: #718096;
+    color: var(--muted-color);
     font-size: 0.875rem;
 }
 "#);
@@ -414,15 +1142,320 @@ document.addEventListener('DOMContentLoaded', () => {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::parser::parse_logseq_page;
+    use crate::graph::test_graph;
+    use crate::postprocess;
+
+    fn test_config() -> ExportConfig {
+        ExportConfig {
+            theme: "default".to_string(),
+            theme_mode: ThemeMode::default(),
+            accent_color: AccentColor::default(),
+            include_backlinks: true,
+            include_graph_view: false,
+            custom_css: None,
+            include_unlinked_references: false,
+            max_unlinked_references: default_max_unlinked_references(),
+            include_related_pages: false,
+            min_related_score: default_min_related_score(),
+            max_related_pages: default_max_related_pages(),
+            generate_stub_pages: default_generate_stub_pages(),
+            base_url: None,
+            generate_feed: false,
+            feed_entries: default_feed_entries(),
+            tag_feeds: false,
+            link_style: LinkStyle::default(),
+            base_path: None,
+            slug_strategy: SlugStrategy::default(),
+            redirect_from: Vec::new(),
+            minify: default_minify(),
+            precompress: false,
+        }
+    }
 
     #[test]
     fn test_markdown_rendering() {
         let content = "This is **bold** and *italic* with [[link]] and #tag and `code`";
-        let html = render_markdown(content);
+        let graph = test_graph(&[("page.md", content)]);
+        let links = LinkResolver::new(&graph, &test_config());
+        let html = render_markdown(content, &links, "page.html");
         assert!(html.contains("<strong>bold</strong>"));
         assert!(html.contains("<em>italic</em>"));
         assert!(html.contains("wiki-link"));
         assert!(html.contains("class=\"tag\""));
         assert!(html.contains("<code>code</code>"));
     }
+
+    #[test]
+    fn test_unlinked_references_rendering() {
+        let page = parse_logseq_page("- Target", "target.md").unwrap();
+        let graph = test_graph(&[]);
+        let unlinked = vec![UnlinkedReference {
+            source: "notes.md".to_string(),
+            block_id: "block-0-0".to_string(),
+            content: "mentions target".to_string(),
+        }];
+        let mut config = test_config();
+        config.include_unlinked_references = true;
+        let links = LinkResolver::new(&graph, &config);
+
+        let html = export_page_to_html(&page, &[], &unlinked, &[], &links, &config);
+        assert!(html.contains("Unlinked References"));
+        assert!(html.contains("<a href=\"notes.html\">notes.md</a>"));
+
+        config.include_unlinked_references = false;
+        let html = export_page_to_html(&page, &[], &unlinked, &[], &links, &config);
+        assert!(!html.contains("Unlinked References"));
+    }
+
+    #[test]
+    fn test_related_pages_section() {
+        let graph = test_graph(&[
+            ("pages/rust.md", "- [[Ownership]] #lang"),
+            ("pages/go.md", "- [[Ownership]] #lang"),
+            ("pages/ownership.md", "- Borrowing"),
+        ]);
+        let mut config = test_config();
+
+        let files = export_graph_to_html(&graph, &config).unwrap();
+        assert!(!files["pages/rust.html"].contains("Related"));
+
+        config.include_related_pages = true;
+        let files = export_graph_to_html(&graph, &config).unwrap();
+        let rust = &files["pages/rust.html"];
+        assert!(rust.contains("<h2>🧭 Related</h2>"));
+        assert!(rust.contains("<li><a href=\"go.html\">go</a><span class=\"related-reasons\">1 shared link · #lang</span></li>"));
+        assert!(!files["pages/ownership.html"].contains("Related"));
+
+        config.min_related_score = 3;
+        let files = export_graph_to_html(&graph, &config).unwrap();
+        assert!(!files["pages/rust.html"].contains("Related"));
+    }
+
+    #[test]
+    fn test_stub_pages_for_missing_links() {
+        let graph = test_graph(&[
+            ("pages/notes.md", "- See [[Some Concept]] and [[Project/Plan]]"),
+            ("index.md", "- Read [[notes]]"),
+        ]);
+
+        let files = export_graph_to_html(&graph, &test_config()).unwrap();
+        let stub = &files["Some Concept.html"];
+        assert!(stub.contains("<h1>Some Concept</h1>"));
+        assert!(stub.contains("<a href=\"pages/notes.html\">pages/notes.md</a>"));
+        assert!(files["Project/Plan.html"].contains("href=\"../style.css\""));
+
+        let notes = &files["pages/notes.html"];
+        assert!(notes.contains("<a href=\"../Some%20Concept.html\" class=\"wiki-link\">Some Concept</a>"));
+        assert!(files["index.html"].contains("pages/notes.html"));
+        assert!(files["index.html"].contains("notes</a>"));
+    }
+
+    #[test]
+    fn test_sitemap_and_canonical_links() {
+        let graph = test_graph(&[
+            ("pages/notes.md", "lastmod:: 2024-02-01\n- Notes [[Idea]]"),
+            ("pages/private.md", "sitemap:: false\n- Hidden"),
+        ]);
+        let mut config = test_config();
+
+        let files = export_graph_to_html(&graph, &config).unwrap();
+        assert!(!files.contains_key("sitemap.xml"));
+        assert!(!files["pages/notes.html"].contains("rel=\"canonical\""));
+
+        config.base_url = Some("https://example.com/wiki/".to_string());
+        let files = export_graph_to_html(&graph, &config).unwrap();
+        let sitemap = &files["sitemap.xml"];
+        assert!(sitemap.contains("<loc>https://example.com/wiki/</loc>"));
+        assert!(sitemap.contains("<loc>https://example.com/wiki/pages/notes.html</loc>\n<lastmod>2024-02-01</lastmod>"));
+        assert!(!sitemap.contains("private"));
+        assert!(sitemap.contains("<loc>https://example.com/wiki/Idea.html</loc>"));
+        // Stub pages in the sitemap carry the same canonical link and metadata as pages
+        let stub = &files["Idea.html"];
+        assert!(stub.contains("<link rel=\"canonical\" href=\"https://example.com/wiki/Idea.html\">"));
+        assert!(stub.contains("<meta property=\"og:url\" content=\"https://example.com/wiki/Idea.html\">"));
+        // Crawlers ignore robots.txt below the host root
+        assert!(!files.contains_key("robots.txt"));
+        assert!(files["pages/notes.html"]
+            .contains("<link rel=\"canonical\" href=\"https://example.com/wiki/pages/notes.html\">"));
+
+        config.base_url = Some("https://example.com".to_string());
+        let files = export_graph_to_html(&graph, &config).unwrap();
+        assert!(files["robots.txt"].contains("Sitemap: https://example.com/sitemap.xml"));
+    }
+
+    #[test]
+    fn test_journal_and_tag_feeds() {
+        let graph = test_graph(&[
+            ("journals/2023_04_05.md", "- Older release #release"),
+            ("journals/2023_04_11.md", "- Newer release #release"),
+            ("journals/2023_04_12.md", "- Unrelated"),
+            ("pages/notes.md", "- Not a journal"),
+        ]);
+        let mut config = test_config();
+        config.generate_feed = true;
+        config.feed_entries = 2;
+        config.tag_feeds = true;
+        config.base_url = Some("https://example.com".to_string());
+
+        let files = export_graph_to_html(&graph, &config).unwrap();
+        let feed = &files["feed.xml"];
+        assert!(feed.contains("<id>https://example.com/journals/2023_04_12.html</id>"));
+        assert!(feed.contains("<id>https://example.com/journals/2023_04_11.html</id>"));
+        assert!(!feed.contains("2023_04_05"));
+        assert!(!feed.contains("notes"));
+        assert!(feed.find("2023_04_12").unwrap() < feed.find("2023_04_11").unwrap());
+        assert!(feed.contains("Newer release"));
+        assert!(files["index.html"].contains("type=\"application/atom+xml\""));
+
+        let tag_feed = &files["feeds/tags/release.xml"];
+        assert!(tag_feed.contains("2023_04_05"));
+        assert!(tag_feed.contains("2023_04_11"));
+        assert!(!tag_feed.contains("2023_04_12"));
+
+        let rebuilt = export_graph_to_html(&graph, &config).unwrap();
+        assert_eq!(feed, &rebuilt["feed.xml"]);
+    }
+
+    #[test]
+    fn test_page_head_metadata() {
+        let graph = test_graph(&[(
+            "pages/guide.md",
+            "description:: A \"quoted\" guide\nimage:: ../assets/cover.png\n- Body text",
+        )]);
+        let mut config = test_config();
+        config.base_url = Some("https://example.com".to_string());
+
+        let files = export_graph_to_html(&graph, &config).unwrap();
+        let html = &files["pages/guide.html"];
+        assert!(html.contains("<meta name=\"description\" content=\"A &quot;quoted&quot; guide\">"));
+        assert!(html.contains("<meta property=\"og:image\" content=\"https://example.com/assets/cover.png\">"));
+        assert!(html.contains("<meta name=\"twitter:card\" content=\"summary_large_image\">"));
+        assert!(html.contains("<script type=\"application/ld+json\">{\"@context\":\"https://schema.org\",\"@type\":\"Article\""));
+    }
+
+    #[test]
+    fn test_asset_and_link_paths_by_depth() {
+        let graph = test_graph(&[
+            ("orphan.md", "- Links to [[b]]"),
+            ("pages/a/b.md", "- Links to [[orphan]]"),
+        ]);
+        let mut config = test_config();
+
+        let files = export_graph_to_html(&graph, &config).unwrap();
+        let root = &files["orphan.html"];
+        assert!(root.contains("<link rel=\"stylesheet\" href=\"style.css\">"));
+        assert!(root.contains("<a href=\"index.html\">"));
+        assert!(root.contains("<a href=\"pages/a/b.html\" class=\"wiki-link\">b</a>"));
+
+        let nested = &files["pages/a/b.html"];
+        assert!(nested.contains("<link rel=\"stylesheet\" href=\"../../style.css\">"));
+        assert!(nested.contains("<script src=\"../../app.js\">"));
+        assert!(nested.contains("<a href=\"../../orphan.html\" class=\"wiki-link\">orphan</a>"));
+        assert!(nested.contains("<li><a href=\"../../orphan.html\">orphan.md</a></li>"));
+
+        config.link_style = LinkStyle::Absolute;
+        config.base_path = Some("/project/".to_string());
+        let files = export_graph_to_html(&graph, &config).unwrap();
+        let nested = &files["pages/a/b.html"];
+        assert!(nested.contains("<link rel=\"stylesheet\" href=\"/project/style.css\">"));
+        assert!(nested.contains("<a href=\"/project/orphan.html\" class=\"wiki-link\">orphan</a>"));
+        assert!(files["index.html"].contains("<a href=\"/project/pages/a/b.html\">b</a>"));
+    }
+
+    #[test]
+    fn test_slug_strategies_and_redirects() {
+        let graph = test_graph(&[
+            ("pages/Café Notes.md", "- See [[Roadmap]] and [[Later Idea]]"),
+            ("pages/Roadmap.md", "- Plan"),
+        ]);
+        let mut config = test_config();
+        config.slug_strategy = SlugStrategy::LowercaseKebab;
+
+        let files = export_graph_to_html(&graph, &config).unwrap();
+        let notes = &files["pages/cafe-notes.html"];
+        assert!(notes.contains("<a href=\"roadmap.html\" class=\"wiki-link\">Roadmap</a>"));
+        assert!(notes.contains("<a href=\"../later-idea.html\" class=\"wiki-link\">Later Idea</a>"));
+        assert!(!files.contains_key("redirects.json"));
+
+        config.slug_strategy = SlugStrategy::Pretty;
+        config.redirect_from = vec![SlugStrategy::Preserve, SlugStrategy::LowercaseKebab];
+        config.base_url = Some("https://example.com".to_string());
+        let files = export_graph_to_html(&graph, &config).unwrap();
+        let notes = &files["pages/cafe-notes/index.html"];
+        assert!(notes.contains("<a href=\"../roadmap/\" class=\"wiki-link\">Roadmap</a>"));
+        assert!(notes.contains("<link rel=\"canonical\" href=\"https://example.com/pages/cafe-notes/\">"));
+        assert!(notes.contains("<a href=\"../../\">← Back to Index</a>"));
+        assert!(files["sitemap.xml"].contains("<loc>https://example.com/pages/roadmap/</loc>"));
+
+        let redirect = &files["pages/Café Notes.html"];
+        assert!(redirect.contains("<meta http-equiv=\"refresh\" content=\"0; url=cafe-notes/\">"));
+        assert!(files["pages/cafe-notes.html"].contains("url=cafe-notes/"));
+        let redirects: BTreeMap<String, String> = serde_json::from_str(&files["redirects.json"]).unwrap();
+        assert_eq!(redirects["pages/Roadmap.html"], "pages/roadmap/index.html");
+        assert_eq!(redirects.len(), 4);
+    }
+
+    #[test]
+    fn test_stub_and_redirect_collisions() {
+        let graph = test_graph(&[
+            ("foo.md", "- Links to [[foo/index]], [[A?]] and [[A_]]"),
+        ]);
+        let mut config = test_config();
+        config.slug_strategy = SlugStrategy::LowercaseKebab;
+        config.redirect_from = vec![SlugStrategy::Pretty];
+
+        let export = export_graph_incremental(&graph, &config, &HashMap::new(), &HashMap::new(), |_, _| true).unwrap();
+        // The stub for [[foo/index]] takes the legacy path foo/index.html, and [[A?]] and [[A_]] share a stub
+        assert!(export.files["foo/index.html"].contains("stub-page"));
+        let messages: Vec<(&str, &str)> = export.collisions.iter()
+            .map(|d| (d.file.as_str(), d.message.as_str()))
+            .collect();
+        assert_eq!(messages, vec![
+            ("foo.md", "No stub page for [[A_]]: output path a.html is already taken"),
+            ("foo.md", "No redirect from foo/index.html: the path is already taken"),
+        ]);
+        assert!(export.collisions.iter().all(|d| d.code == "slug-collision"));
+    }
+
+    #[test]
+    fn test_minified_assets() {
+        let graph = test_graph(&[("a.md", "- Block")]);
+        let mut config = test_config();
+        config.custom_css = Some("/* Brand */\n.brand::before { content: \"a  b\"; }".to_string());
+
+        let mut files = export_graph_to_html(&graph, &config).unwrap();
+        assert!(files["style.css"].contains("/* Brand */"));
+
+        postprocess::post_process(&mut files, config.minify, config.precompress).unwrap();
+        assert!(files["style.css"].contains(".brand::before{content:\"a  b\"}"));
+        assert!(!files["style.css"].contains("/* Brand */"));
+        assert!(files["app.js"].contains("document.addEventListener('DOMContentLoaded',()=>{"));
+        assert!(!files["app.js"].contains("// Logseq Publisher JavaScript"));
+        assert!(files["a.html"].contains("<nav><a href=\"index.html\">← Back to Index</a></nav><article"));
+    }
+
+    #[test]
+    fn test_theme_mode_and_accent_color() {
+        let graph = test_graph(&[("a.md", "- Block")]);
+        let mut config = test_config();
+        config.theme_mode = ThemeMode::Dark;
+        config.accent_color = AccentColor::Orange;
+
+        let files = export_graph_to_html(&graph, &config).unwrap();
+        assert!(files["style.css"].contains("color-scheme: dark;"));
+        assert!(files["style.css"].contains("--link-color: #f6ad55;"));
+        assert!(!files["style.css"].contains("#ffffff"));
+    }
+
+    #[test]
+    fn test_missing_links_as_plain_text() {
+        let graph = test_graph(&[("notes.md", "- See [[Some Concept]]")]);
+        let mut config = test_config();
+        config.generate_stub_pages = false;
+
+        let files = export_graph_to_html(&graph, &config).unwrap();
+        assert!(!files.contains_key("Some Concept.html"));
+        assert!(files["notes.html"].contains("See Some Concept"));
+    }
 }
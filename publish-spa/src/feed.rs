@@ -0,0 +1,165 @@
+//! Atom feeds for journal pages and tags
+
+use crate::parser::Page;
+use crate::sitemap::{self, escape_xml};
+
+/// A single `<entry>` in an Atom feed
+#[derive(Debug, Clone)]
+pub struct FeedEntry {
+    /// Stable identifier, unchanged across rebuilds
+    pub id: String,
+    pub title: String,
+    /// Link to the published page, absolute when a base URL is configured
+    pub link: String,
+    /// Entry date as `YYYY-MM-DD`
+    pub date: String,
+    /// Rendered page HTML
+    pub content: String,
+}
+
+/// Feed-level metadata
+#[derive(Debug, Clone)]
+pub struct Feed {
+    pub id: String,
+    pub title: String,
+    /// Output path of the feed file, e.g. `feed.xml`
+    pub path: String,
+    pub base_url: Option<String>,
+    pub entries: Vec<FeedEntry>,
+}
+
+/// Whether the page is a journal, i.e. lives under `journals/`
+pub fn is_journal(page: &Page) -> bool {
+    page.path.starts_with("journals/")
+}
+
+/// Date of a page for feed purposes: the journal file name (`YYYY_MM_DD.md`)
+/// or a `date::` property, as `YYYY-MM-DD`
+pub fn page_date(page: &Page) -> Option<String> {
+    journal_date(page).or_else(|| {
+        page.properties
+            .get("date")
+            .and_then(|value| sitemap::parse_date_property(value))
+    })
+}
+
+/// Stable entry or feed ID: the canonical URL when the site URL is known, else a URN of the path
+pub fn stable_id(base_url: Option<&str>, html_path: &str) -> String {
+    match base_url {
+        Some(base_url) => sitemap::canonical_url(base_url, html_path),
+        None => format!("urn:logseq-publish:{}", sitemap::encode_url_path(html_path)),
+    }
+}
+
+/// Generate an Atom feed document
+pub fn generate_atom_feed(feed: &Feed) -> String {
+    let updated = feed.entries
+        .iter()
+        .map(|entry| entry.date.as_str())
+        .max()
+        .unwrap_or("1970-01-01");
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+    xml.push_str("<feed xmlns=\"http://www.w3.org/2005/Atom\">\n");
+    xml.push_str(&format!("<id>{}</id>\n", escape_xml(&feed.id)));
+    xml.push_str(&format!("<title>{}</title>\n", escape_xml(&feed.title)));
+    xml.push_str(&format!("<updated>{}</updated>\n", atom_timestamp(updated)));
+    xml.push_str(&format!("<author><name>{}</name></author>\n", escape_xml(&feed.title)));
+    if let Some(base_url) = &feed.base_url {
+        let self_url = sitemap::canonical_url(base_url, &feed.path);
+        let site_url = sitemap::canonical_url(base_url, "index.html");
+        xml.push_str(&format!("<link rel=\"self\" href=\"{}\"/>\n", escape_xml(&self_url)));
+        xml.push_str(&format!("<link href=\"{}\"/>\n", escape_xml(&site_url)));
+    }
+
+    for entry in &feed.entries {
+        xml.push_str("<entry>\n");
+        xml.push_str(&format!("<id>{}</id>\n", escape_xml(&entry.id)));
+        xml.push_str(&format!("<title>{}</title>\n", escape_xml(&entry.title)));
+        xml.push_str(&format!("<updated>{}</updated>\n", atom_timestamp(&entry.date)));
+        xml.push_str(&format!("<link href=\"{}\"/>\n", escape_xml(&entry.link)));
+        xml.push_str(&format!(
+            "<content type=\"html\" xml:base=\"{}\">{}</content>\n",
+            escape_xml(&entry.link),
+            escape_xml(&entry.content)
+        ));
+        xml.push_str("</entry>\n");
+    }
+
+    xml.push_str("</feed>\n");
+    xml
+}
+
+/// Date from a journal file name such as `journals/2023_04_11.md`
+fn journal_date(page: &Page) -> Option<String> {
+    if !is_journal(page) {
+        return None;
+    }
+
+    let stem = page.path.rsplit('/').next()?.split('.').next()?;
+    let parts: Vec<&str> = stem.split(['_', '-']).collect();
+    match parts.as_slice() {
+        [year, month, day]
+            if year.len() == 4 && month.len() == 2 && day.len() == 2
+                && parts.iter().all(|p| p.chars().all(|c| c.is_ascii_digit())) =>
+        {
+            Some(format!("{}-{}-{}", year, month, day))
+        }
+        _ => None,
+    }
+}
+
+/// RFC 3339 timestamp at midnight UTC for a `YYYY-MM-DD` date
+fn atom_timestamp(date: &str) -> String {
+    format!("{}T00:00:00Z", date)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_logseq_page;
+
+    #[test]
+    fn test_page_date() {
+        let journal = parse_logseq_page("- Entry", "journals/2023_04_11.md").unwrap();
+        assert!(is_journal(&journal));
+        assert_eq!(page_date(&journal), Some("2023-04-11".to_string()));
+
+        let dated = parse_logseq_page("date:: 2024-05-06\n- Post", "pages/post.md").unwrap();
+        assert_eq!(page_date(&dated), Some("2024-05-06".to_string()));
+
+        let undated = parse_logseq_page("- Note", "journals/notes.md").unwrap();
+        assert_eq!(page_date(&undated), None);
+    }
+
+    #[test]
+    fn test_stable_id() {
+        assert_eq!(
+            stable_id(Some("https://example.com"), "journals/2023_04_11.html"),
+            "https://example.com/journals/2023_04_11.html"
+        );
+        assert_eq!(stable_id(None, "journals/a b.html"), "urn:logseq-publish:journals/a%20b.html");
+    }
+
+    #[test]
+    fn test_generate_atom_feed() {
+        let feed = Feed {
+            id: "https://example.com/feed.xml".to_string(),
+            title: "Journal".to_string(),
+            path: "feed.xml".to_string(),
+            base_url: Some("https://example.com".to_string()),
+            entries: vec![FeedEntry {
+                id: "https://example.com/journals/2023_04_11.html".to_string(),
+                title: "2023_04_11".to_string(),
+                link: "https://example.com/journals/2023_04_11.html".to_string(),
+                date: "2023-04-11".to_string(),
+                content: "<p>Shipped & done</p>".to_string(),
+            }],
+        };
+
+        let xml = generate_atom_feed(&feed);
+        assert!(xml.contains("<updated>2023-04-11T00:00:00Z</updated>"));
+        assert!(xml.contains("<link rel=\"self\" href=\"https://example.com/feed.xml\"/>"));
+        assert!(xml.contains("&lt;p&gt;Shipped &amp; done&lt;/p&gt;"));
+    }
+}
@@ -0,0 +1,284 @@
+//! Where graphs are read from and where published sites are written to
+//!
+//! The publish pipeline only talks to these traits, so the same code runs against
+//! Node.js (`converter::NodeDir`), the native file system (`native::NativeDir`) and
+//! memory ([`MemoryFs`]), which is what tests and in-browser publishing use.
+
+use crate::converter::{self, SourceFile};
+use crate::diagnostics::Diagnostic;
+use crate::errors::PublishError;
+use indexmap::IndexMap;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+/// Directory inside the output directory that holds the files of a build in progress
+pub(crate) const STAGING_DIR: &str = ".publish-staging";
+
+/// Directory inside the output directory that keeps the files a commit replaces or
+/// removes, so a failed commit can put them back
+pub(crate) const BACKUP_DIR: &str = ".publish-backup";
+
+/// A Logseq graph to publish
+#[allow(async_fn_in_trait)]
+pub trait GraphSource {
+    /// Read every markdown file; files that can't be read are reported rather than failing the read
+    async fn read_graph_files(&self) -> Result<GraphFiles, PublishError>;
+}
+
+/// The markdown files of a graph
+#[derive(Debug, Clone, Default)]
+pub struct GraphFiles {
+    /// Files keyed by their `/`-separated path relative to the graph root
+    pub files: HashMap<String, SourceFile>,
+    /// An `unreadable-file` diagnostic for each file that couldn't be read
+    pub unreadable: Vec<Diagnostic>,
+}
+
+impl GraphFiles {
+    /// Record that the file at `path` couldn't be read
+    pub(crate) fn push_unreadable(&mut self, path: impl Into<String>, reason: impl fmt::Display) {
+        self.unreadable.push(Diagnostic::error("unreadable-file", path, format!("Failed to read file: {}", reason)));
+    }
+}
+
+/// Destination of the published site
+#[allow(async_fn_in_trait)]
+pub trait OutputSink {
+    /// Write one output file at `path`, relative to the site root, creating directories as needed
+    async fn write_file(&mut self, path: &str, content: &[u8]) -> Result<(), PublishError>;
+
+    /// Read back a file written by an earlier build, or `None` when there is none
+    async fn read_file(&self, path: &str) -> Result<Option<Vec<u8>>, PublishError>;
+
+    /// Remove a file written by an earlier build; missing files are not an error
+    async fn remove_file(&mut self, path: &str) -> Result<(), PublishError>;
+
+    /// Start a build: until [`commit`](OutputSink::commit), writes and removals go to a
+    /// staging area and the destination keeps the previous site
+    async fn begin(&mut self) -> Result<(), PublishError> {
+        Ok(())
+    }
+
+    /// Apply the staged writes and removals to the destination, in the order they were made
+    async fn commit(&mut self) -> Result<(), PublishError> {
+        Ok(())
+    }
+
+    /// Discard the staging area, leaving the destination as it was before `begin`
+    async fn rollback(&mut self) -> Result<(), PublishError> {
+        Ok(())
+    }
+}
+
+/// A change to an output file made during a build
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Staged {
+    Written,
+    Removed,
+}
+
+/// The files a build in progress wrote and removed, for sinks that stage them on disk
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Staging {
+    /// Latest change of each path, in the order the changes were made
+    changes: IndexMap<String, Staged>,
+}
+
+impl Staging {
+    pub(crate) fn get(&self, path: &str) -> Option<Staged> {
+        self.changes.get(path).copied()
+    }
+
+    /// Record a change to `path`, returning the one it replaces
+    pub(crate) fn record(&mut self, path: &str, change: Staged) -> Option<Staged> {
+        // Moved to the end, so the build cache written last is also committed last
+        let previous = self.changes.shift_remove(path);
+        self.changes.insert(path.to_string(), change);
+        previous
+    }
+
+    pub(crate) fn changes(&self) -> impl Iterator<Item = (&str, Staged)> {
+        self.changes.iter().map(|(path, change)| (path.as_str(), *change))
+    }
+}
+
+/// Files held in memory, usable both as a graph source and as an output sink
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MemoryFs {
+    files: BTreeMap<String, Vec<u8>>,
+    /// Files of the build in progress, between `begin` and `commit`
+    staged: Option<BTreeMap<String, Vec<u8>>>,
+}
+
+impl MemoryFs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add or replace the file at `path`
+    pub fn insert(&mut self, path: impl Into<String>, content: impl Into<Vec<u8>>) {
+        self.files.insert(path.into(), content.into());
+    }
+
+    /// Files of the build in progress, or the committed files outside of one
+    fn current(&mut self) -> &mut BTreeMap<String, Vec<u8>> {
+        self.staged.as_mut().unwrap_or(&mut self.files)
+    }
+
+    pub fn get(&self, path: &str) -> Option<&[u8]> {
+        self.files.get(path).map(Vec::as_slice)
+    }
+
+    /// The file at `path`, if it exists and is valid UTF-8
+    pub fn get_str(&self, path: &str) -> Option<&str> {
+        self.get(path).and_then(|content| std::str::from_utf8(content).ok())
+    }
+
+    /// All files in path order
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &[u8])> {
+        self.files.iter().map(|(path, content)| (path.as_str(), content.as_slice()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+}
+
+impl<P: Into<String>, C: Into<Vec<u8>>> FromIterator<(P, C)> for MemoryFs {
+    fn from_iter<I: IntoIterator<Item = (P, C)>>(iter: I) -> Self {
+        let mut fs = MemoryFs::new();
+        for (path, content) in iter {
+            fs.insert(path, content);
+        }
+        fs
+    }
+}
+
+impl GraphSource for MemoryFs {
+    async fn read_graph_files(&self) -> Result<GraphFiles, PublishError> {
+        let mut files = GraphFiles::default();
+        for (path, content) in self.iter() {
+            if !(path.ends_with(".md") || path.ends_with(".markdown")) {
+                continue;
+            }
+            converter::validate_file_path(path)?;
+
+            match String::from_utf8(content.to_vec()) {
+                Ok(content) => {
+                    files.files.insert(path.to_string(), SourceFile { content, modified: None });
+                }
+                Err(e) => files.push_unreadable(path, e),
+            }
+        }
+        Ok(files)
+    }
+}
+
+impl OutputSink for MemoryFs {
+    async fn write_file(&mut self, path: &str, content: &[u8]) -> Result<(), PublishError> {
+        self.current().insert(path.to_string(), content.to_vec());
+        Ok(())
+    }
+
+    async fn read_file(&self, path: &str) -> Result<Option<Vec<u8>>, PublishError> {
+        let files = self.staged.as_ref().unwrap_or(&self.files);
+        Ok(files.get(path).cloned())
+    }
+
+    async fn remove_file(&mut self, path: &str) -> Result<(), PublishError> {
+        self.current().remove(path);
+        Ok(())
+    }
+
+    async fn begin(&mut self) -> Result<(), PublishError> {
+        self.staged = Some(self.files.clone());
+        Ok(())
+    }
+
+    async fn commit(&mut self) -> Result<(), PublishError> {
+        if let Some(staged) = self.staged.take() {
+            self.files = staged;
+        }
+        Ok(())
+    }
+
+    async fn rollback(&mut self) -> Result<(), PublishError> {
+        self.staged = None;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::native::block_on;
+
+    #[test]
+    fn test_memory_graph_source() {
+        let fs: MemoryFs = [
+            ("pages/Rust.md", "- Links to [[Cargo]]"),
+            ("journals/2024_01_01.markdown", "- Entry"),
+            ("logseq/config.edn", "{}"),
+        ]
+        .into_iter()
+        .collect();
+
+        let files = block_on(fs.read_graph_files()).unwrap();
+        assert_eq!(files.files.len(), 2);
+        assert_eq!(files.files["pages/Rust.md"].content, "- Links to [[Cargo]]");
+        assert_eq!(files.files["pages/Rust.md"].modified, None);
+        assert!(files.unreadable.is_empty());
+
+        let invalid: MemoryFs = [("pages/a.md", vec![0xff, 0xfe])].into_iter().collect();
+        let files = block_on(invalid.read_graph_files()).unwrap();
+        assert!(files.files.is_empty());
+        assert_eq!(files.unreadable[0].code, "unreadable-file");
+        assert_eq!(files.unreadable[0].file, "pages/a.md");
+
+        let escaping: MemoryFs = [("../secret.md", "- Secret")].into_iter().collect();
+        assert!(block_on(escaping.read_graph_files()).is_err());
+    }
+
+    #[test]
+    fn test_memory_output_sink() {
+        let mut fs = MemoryFs::new();
+        block_on(fs.write_file("pages/a.html", b"<p>a</p>")).unwrap();
+        block_on(fs.write_file("pages/a.html.gz", &[0x1f, 0x8b])).unwrap();
+
+        assert_eq!(fs.len(), 2);
+        assert_eq!(fs.get_str("pages/a.html"), Some("<p>a</p>"));
+        assert_eq!(fs.get_str("pages/a.html.gz"), None);
+        assert_eq!(fs.get("pages/a.html.gz"), Some(&[0x1f, 0x8b][..]));
+
+        assert_eq!(block_on(fs.read_file("pages/a.html")).unwrap(), Some(b"<p>a</p>".to_vec()));
+        block_on(fs.remove_file("pages/a.html")).unwrap();
+        block_on(fs.remove_file("pages/missing.html")).unwrap();
+        assert_eq!(block_on(fs.read_file("pages/a.html")).unwrap(), None);
+    }
+
+    #[test]
+    fn test_memory_transactions() {
+        let mut fs: MemoryFs = [("index.html", "old"), ("stale.html", "stale")].into_iter().collect();
+
+        block_on(fs.begin()).unwrap();
+        block_on(fs.write_file("index.html", b"new")).unwrap();
+        block_on(fs.remove_file("stale.html")).unwrap();
+        // The build sees its own writes, the committed files are unchanged
+        assert_eq!(block_on(fs.read_file("index.html")).unwrap(), Some(b"new".to_vec()));
+        assert_eq!(fs.get_str("index.html"), Some("old"));
+        block_on(fs.rollback()).unwrap();
+        assert_eq!(fs.get_str("index.html"), Some("old"));
+        assert_eq!(fs.get_str("stale.html"), Some("stale"));
+
+        block_on(fs.begin()).unwrap();
+        block_on(fs.write_file("index.html", b"new")).unwrap();
+        block_on(fs.remove_file("stale.html")).unwrap();
+        block_on(fs.commit()).unwrap();
+        assert_eq!(fs.get_str("index.html"), Some("new"));
+        assert_eq!(fs.get("stale.html"), None);
+    }
+}
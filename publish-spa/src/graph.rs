@@ -1,10 +1,18 @@
-use crate::parser::Page;
+use crate::parser::{Block, Page};
+use regex::Regex;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+/// Pages linking, linked from or tagged alike beyond this many don't relate them: the
+/// pairs grow quadratically while the shared hub or tag (say `#journal`) says little
+const MAX_RELATED_GROUP: usize = 100;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Graph {
-    pages: HashMap<String, Page>,
+    /// Pages by path; a sorted map, so everything derived from iterating pages is
+    /// independent of the order they were added in
+    pages: BTreeMap<String, Page>,
+    /// Lowercased link names to the sorted paths of the pages linking them
     backlinks: HashMap<String, Vec<String>>,
 }
 
@@ -16,10 +24,46 @@ pub struct GraphStats {
     pub orphan_pages: usize,
 }
 
+/// A block that mentions a page's title or alias without linking to it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnlinkedReference {
+    /// Path of the page containing the mention
+    pub source: String,
+    /// ID of the mentioning block
+    pub block_id: String,
+    /// Raw content of the mentioning block
+    pub content: String,
+}
+
+/// A page that is linked to but has no source file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MissingPage {
+    /// Link name as first written in the graph
+    pub name: String,
+    /// Paths of the pages linking to it
+    pub referenced_by: Vec<String>,
+}
+
+/// A page related to another without a direct link between them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelatedPage {
+    /// Path of the related page
+    pub path: String,
+    pub title: String,
+    /// Relatedness: co-citations plus shared links plus shared tags
+    pub score: usize,
+    /// Pages linking to both pages
+    pub co_citations: usize,
+    /// Pages both pages link to
+    pub shared_links: usize,
+    /// Lowercased tags both pages have
+    pub shared_tags: Vec<String>,
+}
+
 impl Graph {
     pub fn new() -> Self {
         Self {
-            pages: HashMap::new(),
+            pages: BTreeMap::new(),
             backlinks: HashMap::new(),
         }
     }
@@ -27,12 +71,12 @@ impl Graph {
     pub fn add_page(&mut self, page: Page) {
         let path = page.path.clone();
 
-        // Update backlinks for all links in this page
+        // Update backlinks for all links in this page, keyed case-insensitively
         for link in &page.links {
-            self.backlinks
-                .entry(link.clone())
-                .or_insert_with(Vec::new)
-                .push(path.clone());
+            let sources = self.backlinks.entry(link.to_lowercase()).or_default();
+            if let Err(position) = sources.binary_search(&path) {
+                sources.insert(position, path.clone());
+            }
         }
 
         self.pages.insert(path, page);
@@ -43,11 +87,22 @@ impl Graph {
         self.pages.get(path)
     }
 
-    pub fn get_backlinks(&self, path: &str) -> Vec<String> {
-        self.backlinks
-            .get(path)
-            .cloned()
-            .unwrap_or_default()
+    /// Paths of pages linking to `name`, in path order; `name` is either a link name
+    /// or the path of a page whose title and aliases are then looked up as well
+    pub fn get_backlinks(&self, name: &str) -> Vec<String> {
+        let mut keys = vec![name.to_lowercase()];
+        if let Some(page) = self.pages.get(name) {
+            keys.push(page.title.to_lowercase());
+            keys.extend(page.aliases().iter().map(|alias| alias.to_lowercase()));
+        }
+
+        let mut backlinks: Vec<String> = keys
+            .iter()
+            .flat_map(|key| self.backlinks.get(key).into_iter().flatten().cloned())
+            .collect();
+        backlinks.sort();
+        backlinks.dedup();
+        backlinks
     }
 
     #[allow(dead_code)]
@@ -55,10 +110,218 @@ impl Graph {
         self.pages.len()
     }
 
+    /// All pages in path order
     pub fn pages(&self) -> impl Iterator<Item = &Page> {
         self.pages.values()
     }
 
+    /// Map lowercased page titles and aliases to the paths of the pages they name
+    pub fn name_index(&self) -> HashMap<String, Vec<String>> {
+        let mut index: HashMap<String, Vec<String>> = HashMap::new();
+
+        for page in self.pages.values() {
+            let names = std::iter::once(page.title.clone()).chain(page.aliases());
+            for name in names {
+                let paths = index.entry(name.to_lowercase()).or_default();
+                if !paths.contains(&page.path) {
+                    paths.push(page.path.clone());
+                }
+            }
+        }
+
+        index
+    }
+
+    /// Pages that are linked to but have no source file, sorted by name
+    pub fn missing_pages(&self) -> Vec<MissingPage> {
+        let names = self.name_index();
+        let mut missing: Vec<MissingPage> = Vec::new();
+        let mut positions: HashMap<String, usize> = HashMap::new();
+
+        for source in self.pages.values() {
+            for link in &source.links {
+                let key = link.to_lowercase();
+                if names.contains_key(&key) {
+                    continue;
+                }
+
+                let position = *positions.entry(key).or_insert_with(|| {
+                    missing.push(MissingPage {
+                        name: link.clone(),
+                        referenced_by: Vec::new(),
+                    });
+                    missing.len() - 1
+                });
+
+                let referenced_by = &mut missing[position].referenced_by;
+                if !referenced_by.contains(&source.path) {
+                    referenced_by.push(source.path.clone());
+                }
+            }
+        }
+
+        missing.sort_by_key(|page| page.name.to_lowercase());
+        missing
+    }
+
+    /// Find blocks mentioning each page's title or alias without linking it.
+    ///
+    /// Matching is case-insensitive and whole-word; text inside code spans,
+    /// wiki-links, tags, markdown links and URLs is ignored, as are blocks that
+    /// already link the page. At most `max_per_page` references are kept per
+    /// page, keyed by the mentioned page's path.
+    pub fn unlinked_references(&self, max_per_page: usize) -> HashMap<String, Vec<UnlinkedReference>> {
+        let mut results: HashMap<String, Vec<UnlinkedReference>> = HashMap::new();
+        let names = self.name_index();
+        let max_words = names.keys()
+            .map(|name| word_spans(name).len())
+            .max()
+            .unwrap_or(0);
+
+        if max_per_page == 0 || max_words == 0 {
+            return results;
+        }
+
+        let mask_regex = Regex::new(r"`[^`]*`|#?\[\[[^\]]*\]\]|#[\w-]+|\[[^\]]*\]\([^)]*\)|https?://\S+").unwrap();
+        let link_regex = Regex::new(r"#?\[\[([^\]]+)\]\]|#([\w-]+)").unwrap();
+
+        // Sources are visited in path order, so the capped result set is stable
+        for source in self.pages.values() {
+            for_each_block(&source.blocks, &mut |block| {
+                let linked: HashSet<&String> = link_regex.captures_iter(&block.content)
+                    .filter_map(|cap| cap.get(1).or_else(|| cap.get(2)))
+                    .filter_map(|name| names.get(&name.as_str().to_lowercase()))
+                    .flatten()
+                    .collect();
+
+                let masked = mask_regex.replace_all(&block.content, |caps: &regex::Captures| {
+                    " ".repeat(caps[0].len())
+                });
+                let words = word_spans(&masked);
+                let mut mentioned: Vec<&String> = Vec::new();
+
+                for start in 0..words.len() {
+                    for len in 1..=max_words.min(words.len() - start) {
+                        let candidate = masked[words[start].0..words[start + len - 1].1].to_lowercase();
+                        let Some(targets) = names.get(&candidate) else {
+                            continue;
+                        };
+
+                        for target in targets {
+                            if *target != source.path && !linked.contains(target) && !mentioned.contains(&target) {
+                                mentioned.push(target);
+                            }
+                        }
+                    }
+                }
+
+                for target in mentioned {
+                    let references = results.entry(target.clone()).or_default();
+                    if references.len() < max_per_page {
+                        references.push(UnlinkedReference {
+                            source: source.path.clone(),
+                            block_id: block.id.clone(),
+                            content: block.content.clone(),
+                        });
+                    }
+                }
+            });
+        }
+
+        results
+    }
+
+    /// Find pages related to each page but not linked to or from it.
+    ///
+    /// Two pages are related by co-citation (other pages link to both), by
+    /// bibliographic coupling (both link to the same pages) and by shared tags;
+    /// their score counts each of these. Pages scoring below `min_score` are
+    /// dropped and at most `max_per_page` are kept per page, highest score
+    /// first and then by path, keyed by page path. Links and tags shared by
+    /// more than [`MAX_RELATED_GROUP`] pages are ignored.
+    pub fn related_pages(&self, min_score: usize, max_per_page: usize) -> HashMap<String, Vec<RelatedPage>> {
+        let mut results: HashMap<String, Vec<RelatedPage>> = HashMap::new();
+        if max_per_page == 0 {
+            return results;
+        }
+
+        let pages: Vec<&Page> = self.pages.values().collect();
+        let positions: HashMap<&str, usize> = pages.iter()
+            .enumerate()
+            .map(|(i, page)| (page.path.as_str(), i))
+            .collect();
+        let names = self.name_index();
+
+        // Resolved link targets of each page, and the pages linking to each page
+        let targets: Vec<BTreeSet<usize>> = pages.iter()
+            .enumerate()
+            .map(|(i, page)| {
+                page.links.iter()
+                    .filter_map(|link| names.get(&link.to_lowercase()))
+                    .flatten()
+                    .map(|path| positions[path.as_str()])
+                    .filter(|&target| target != i)
+                    .collect()
+            })
+            .collect();
+        let mut sources: Vec<BTreeSet<usize>> = vec![BTreeSet::new(); pages.len()];
+        for (source, page_targets) in targets.iter().enumerate() {
+            for &target in page_targets {
+                sources[target].insert(source);
+            }
+        }
+
+        let mut tagged: BTreeMap<String, BTreeSet<usize>> = BTreeMap::new();
+        for (i, page) in pages.iter().enumerate() {
+            for tag in &page.tags {
+                tagged.entry(tag.to_lowercase()).or_default().insert(i);
+            }
+        }
+        let (tags, tagged): (Vec<String>, Vec<BTreeSet<usize>>) = tagged.into_iter().unzip();
+
+        // Co-citations, shared links and indices of the shared tags of each pair (a, b) with a < b
+        let mut pairs: HashMap<(usize, usize), (usize, usize, Vec<usize>)> = HashMap::new();
+        for group in &targets {
+            for_each_pair(group, |pair| pairs.entry(pair).or_default().0 += 1);
+        }
+        for group in &sources {
+            for_each_pair(group, |pair| pairs.entry(pair).or_default().1 += 1);
+        }
+        for (tag, group) in tagged.iter().enumerate() {
+            for_each_pair(group, |pair| pairs.entry(pair).or_default().2.push(tag));
+        }
+
+        for ((a, b), (co_citations, shared_links, shared_tags)) in pairs {
+            if targets[a].contains(&b) || targets[b].contains(&a) {
+                continue;
+            }
+
+            let score = co_citations + shared_links + shared_tags.len();
+            if score < min_score.max(1) {
+                continue;
+            }
+            let shared_tags: Vec<String> = shared_tags.iter().map(|&tag| tags[tag].clone()).collect();
+
+            for (page, other) in [(a, b), (b, a)] {
+                results.entry(pages[page].path.clone()).or_default().push(RelatedPage {
+                    path: pages[other].path.clone(),
+                    title: pages[other].title.clone(),
+                    score,
+                    co_citations,
+                    shared_links,
+                    shared_tags: shared_tags.clone(),
+                });
+            }
+        }
+
+        for related in results.values_mut() {
+            related.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.path.cmp(&b.path)));
+            related.truncate(max_per_page);
+        }
+
+        results
+    }
+
     pub fn stats(&self) -> GraphStats {
         let total_blocks: usize = self.pages.values()
             .map(|p| count_blocks(&p.blocks))
@@ -70,8 +333,7 @@ impl Graph {
 
         let orphan_pages = self.pages.values()
             .filter(|p| {
-                p.links.is_empty() &&
-                self.backlinks.get(&p.path).map_or(true, |bl| bl.is_empty())
+                p.links.is_empty() && self.get_backlinks(&p.path).is_empty()
             })
             .count();
 
@@ -90,17 +352,71 @@ fn count_blocks(blocks: &[crate::parser::Block]) -> usize {
         .sum()
 }
 
+/// Call `visit` with every block in `blocks` and below, depth first
+pub(crate) fn for_each_block(blocks: &[Block], visit: &mut impl FnMut(&Block)) {
+    for block in blocks {
+        visit(block);
+        for_each_block(&block.children, visit);
+    }
+}
+
+/// Call `visit` with every pair `(a, b)` of members of `group` where `a < b`, unless
+/// the group is larger than [`MAX_RELATED_GROUP`]
+fn for_each_pair(group: &BTreeSet<usize>, mut visit: impl FnMut((usize, usize))) {
+    if group.len() > MAX_RELATED_GROUP {
+        return;
+    }
+    for (i, &a) in group.iter().enumerate() {
+        for &b in group.iter().skip(i + 1) {
+            visit((a, b));
+        }
+    }
+}
+
+/// Byte ranges of the words in `text`, where a word is a run of alphanumerics, `_` or `-`
+fn word_spans(text: &str) -> Vec<(usize, usize)> {
+    let mut spans = Vec::new();
+    let mut start = None;
+
+    for (i, c) in text.char_indices() {
+        let is_word = c.is_alphanumeric() || c == '_' || c == '-';
+        match (is_word, start) {
+            (true, None) => start = Some(i),
+            (false, Some(s)) => {
+                spans.push((s, i));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+
+    if let Some(s) = start {
+        spans.push((s, text.len()));
+    }
+
+    spans
+}
+
 impl Default for Graph {
     fn default() -> Self {
         Self::new()
     }
 }
 
+/// A graph of `(path, content)` pages
+#[cfg(test)]
+pub(crate) fn test_graph(pages: &[(&str, &str)]) -> Graph {
+    let mut graph = Graph::new();
+    for (path, content) in pages {
+        graph.add_page(crate::parser::parse_logseq_page(content, path).unwrap());
+    }
+    graph
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::parser::Page;
-    use std::collections::HashMap;
 
     #[test]
     fn test_graph_creation() {
@@ -115,10 +431,11 @@ mod tests {
         let page1 = Page {
             path: "page1.md".to_string(),
             title: "Page 1".to_string(),
-            properties: HashMap::new(),
+            properties: Default::default(),
             blocks: Vec::new(),
             tags: Vec::new(),
             links: vec!["page2.md".to_string()],
+            modified: None,
         };
 
         graph.add_page(page1);
@@ -127,6 +444,122 @@ mod tests {
         assert_eq!(backlinks, vec!["page1.md"]);
     }
 
+    #[test]
+    fn test_backlinks_resolve_titles_and_aliases() {
+        let mut graph = Graph::new();
+        graph.add_page(crate::parser::parse_logseq_page("alias:: RL\n- Target", "pages/Rust Lang.md").unwrap());
+        graph.add_page(crate::parser::parse_logseq_page("- See [[rust lang]]", "a.md").unwrap());
+        graph.add_page(crate::parser::parse_logseq_page("- See [[RL]]", "b.md").unwrap());
+
+        assert_eq!(graph.get_backlinks("pages/Rust Lang.md"), vec!["a.md", "b.md"]);
+        assert_eq!(graph.get_backlinks("Rust Lang"), vec!["a.md"]);
+    }
+
+    #[test]
+    fn test_backlinks_independent_of_add_order() {
+        let pages = [("c.md", "- [[Hub]]"), ("a.md", "- [[hub]]"), ("b.md", "- [[Hub]] and [[hub]]")];
+
+        let mut forward = Graph::new();
+        let mut backward = Graph::new();
+        for (path, content) in pages {
+            forward.add_page(crate::parser::parse_logseq_page(content, path).unwrap());
+        }
+        for (path, content) in pages.iter().rev() {
+            backward.add_page(crate::parser::parse_logseq_page(content, path).unwrap());
+        }
+
+        assert_eq!(forward.get_backlinks("Hub"), vec!["a.md", "b.md", "c.md"]);
+        assert_eq!(backward.get_backlinks("Hub"), forward.get_backlinks("Hub"));
+        let paths = |graph: &Graph| graph.pages().map(|page| page.path.clone()).collect::<Vec<_>>();
+        assert_eq!(paths(&forward), paths(&backward));
+    }
+
+    #[test]
+    fn test_missing_pages() {
+        let mut graph = Graph::new();
+        graph.add_page(crate::parser::parse_logseq_page("- [[Some Concept]] and [[b]]", "a.md").unwrap());
+        graph.add_page(crate::parser::parse_logseq_page("- [[some concept]]", "b.md").unwrap());
+
+        let missing = graph.missing_pages();
+        assert_eq!(missing.len(), 1);
+        assert_eq!(missing[0].name, "Some Concept");
+        assert_eq!(missing[0].referenced_by, vec!["a.md", "b.md"]);
+    }
+
+    #[test]
+    fn test_unlinked_references() {
+        let mut graph = Graph::new();
+        let target = crate::parser::parse_logseq_page("alias:: RL\n- About Rust", "pages/Rust Lang.md").unwrap();
+        let source = crate::parser::parse_logseq_page(
+            "- I like rust lang a lot\n- Linked [[Rust Lang]] and rust lang\n- Code `rust lang` and [[rust lang notes]]\n- Short rl form\n- rustlanguage is not a match",
+            "notes.md",
+        ).unwrap();
+        graph.add_page(target);
+        graph.add_page(source);
+
+        let unlinked = graph.unlinked_references(10);
+        let references = &unlinked["pages/Rust Lang.md"];
+        assert_eq!(references.len(), 2);
+        assert_eq!(references[0].source, "notes.md");
+        assert_eq!(references[0].content, "I like rust lang a lot");
+        assert_eq!(references[1].content, "Short rl form");
+
+        let capped = graph.unlinked_references(1);
+        assert_eq!(capped["pages/Rust Lang.md"].len(), 1);
+    }
+
+    #[test]
+    fn test_related_pages() {
+        let mut graph = Graph::new();
+        for (path, content) in [
+            ("rust.md", "- [[Cargo]] and [[Ownership]] #lang"),
+            ("go.md", "- [[Modules]] and [[Ownership]] #lang"),
+            ("cargo.md", "- Build tool"),
+            ("ownership.md", "- Borrowing"),
+            ("modules.md", "- Packages"),
+            ("intro.md", "- Compare [[Cargo]] with [[Modules]]"),
+        ] {
+            graph.add_page(crate::parser::parse_logseq_page(content, path).unwrap());
+        }
+
+        let related = graph.related_pages(1, 10);
+        let rust = &related["rust.md"];
+        assert_eq!(rust.len(), 2);
+        assert_eq!(rust[0].path, "go.md");
+        assert_eq!((rust[0].score, rust[0].shared_links, rust[0].co_citations), (2, 1, 0));
+        assert_eq!(rust[0].shared_tags, vec!["lang"]);
+        // Both link to cargo.md; pages linked from rust.md are never related to it
+        assert_eq!(rust[1].path, "intro.md");
+        assert_eq!(rust[1].score, 1);
+
+        // Co-cited by rust.md and intro.md respectively
+        let cargo: Vec<(&str, usize)> = related["cargo.md"].iter()
+            .map(|page| (page.path.as_str(), page.co_citations))
+            .collect();
+        assert_eq!(cargo, vec![("modules.md", 1), ("ownership.md", 1)]);
+
+        let strict = graph.related_pages(2, 10);
+        assert_eq!(strict["rust.md"].len(), 1);
+        assert!(!strict.contains_key("cargo.md"));
+        assert!(graph.related_pages(1, 0).is_empty());
+    }
+
+    #[test]
+    fn test_related_pages_ignore_large_groups() {
+        let mut graph = Graph::new();
+        for i in 0..=MAX_RELATED_GROUP {
+            let page = crate::parser::parse_logseq_page("- Entry #journal", &format!("day-{}.md", i)).unwrap();
+            graph.add_page(page);
+        }
+        graph.add_page(crate::parser::parse_logseq_page("- #journal #lang", "rust.md").unwrap());
+        graph.add_page(crate::parser::parse_logseq_page("- #journal #lang", "go.md").unwrap());
+
+        let related = graph.related_pages(1, 10);
+        assert!(!related.contains_key("day-0.md"));
+        assert_eq!(related["rust.md"].len(), 1);
+        assert_eq!(related["rust.md"][0].shared_tags, vec!["lang"]);
+    }
+
     #[test]
     fn test_stats() {
         let graph = Graph::new();
@@ -135,4 +568,14 @@ mod tests {
         assert_eq!(stats.total_blocks, 0);
         assert_eq!(stats.total_links, 0);
     }
+
+    #[test]
+    fn test_orphans_linked_by_title_are_not_counted() {
+        let mut graph = Graph::new();
+        graph.add_page(crate::parser::parse_logseq_page("- See [[Target Page]]", "a.md").unwrap());
+        graph.add_page(crate::parser::parse_logseq_page("- Linked by title", "pages/Target Page.md").unwrap());
+        graph.add_page(crate::parser::parse_logseq_page("- Nobody links here", "lonely.md").unwrap());
+
+        assert_eq!(graph.stats().orphan_pages, 1);
+    }
 }
@@ -0,0 +1,286 @@
+//! Graph health checks: broken links, orphans, dead ends, duplicate titles,
+//! empty pages and stale block references
+
+use crate::feed;
+use crate::graph::{for_each_block, Graph};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::sync::LazyLock;
+
+/// Output path of the JSON report
+pub const REPORT_JSON_PATH: &str = "health-report.json";
+/// Output path of the HTML report
+pub const REPORT_HTML_PATH: &str = "health-report.html";
+
+static BLOCK_REF_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\(\(([0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12})\)\)").unwrap()
+});
+static BLOCK_ID_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?m)^\s*id::\s*([0-9a-fA-F-]{36})\s*$").unwrap()
+});
+
+/// A kind of graph health problem
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum HealthRule {
+    /// A `[[link]]` to a page that doesn't exist
+    BrokenLink,
+    /// A page no other page links to; journals are exempt
+    OrphanPage,
+    /// A page that links to no existing page
+    DeadEnd,
+    /// Pages whose titles are equal ignoring case
+    DuplicateTitle,
+    /// A page without any block content
+    EmptyPage,
+    /// A `((block ref))` to a block id no page declares
+    StaleBlockRef,
+}
+
+impl HealthRule {
+    pub const ALL: [HealthRule; 6] = [
+        HealthRule::BrokenLink,
+        HealthRule::OrphanPage,
+        HealthRule::DeadEnd,
+        HealthRule::DuplicateTitle,
+        HealthRule::EmptyPage,
+        HealthRule::StaleBlockRef,
+    ];
+}
+
+impl fmt::Display for HealthRule {
+    /// The rule's name as used in configs, e.g. `broken-link`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            HealthRule::BrokenLink => "broken-link",
+            HealthRule::OrphanPage => "orphan-page",
+            HealthRule::DeadEnd => "dead-end",
+            HealthRule::DuplicateTitle => "duplicate-title",
+            HealthRule::EmptyPage => "empty-page",
+            HealthRule::StaleBlockRef => "stale-block-ref",
+        })
+    }
+}
+
+/// One problem found in the graph
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HealthIssue {
+    pub rule: HealthRule,
+    /// Path of the page the issue is in
+    pub page: String,
+    /// The broken link name, block id or duplicate page path, when there is one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    pub message: String,
+}
+
+/// All issues found in a graph
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HealthReport {
+    pub page_count: usize,
+    /// Number of issues per rule, including rules without issues
+    pub counts: BTreeMap<HealthRule, usize>,
+    /// Issues ordered by rule, page and target
+    pub issues: Vec<HealthIssue>,
+}
+
+impl HealthReport {
+    /// Number of issues breaking any of `rules`
+    pub fn count(&self, rules: &[HealthRule]) -> usize {
+        rules.iter()
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .map(|rule| self.counts.get(rule).copied().unwrap_or(0))
+            .sum()
+    }
+
+    pub fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize health report: {}", e))
+    }
+}
+
+/// Run every health check on the graph
+pub fn check(graph: &Graph) -> HealthReport {
+    let mut issues = Vec::new();
+    let names = graph.name_index();
+
+    for missing in graph.missing_pages() {
+        let key = missing.name.to_lowercase();
+        for source in &missing.referenced_by {
+            // Report the link as written on the linking page
+            let name = graph.get_page(source)
+                .and_then(|page| page.links.iter().find(|link| link.to_lowercase() == key))
+                .unwrap_or(&missing.name);
+            issues.push(HealthIssue {
+                rule: HealthRule::BrokenLink,
+                page: source.clone(),
+                target: Some(name.clone()),
+                message: format!("Links to [[{}]], which has no page", name),
+            });
+        }
+    }
+
+    let mut declared_ids: HashSet<String> = HashSet::new();
+    for page in graph.pages() {
+        if let Some(id) = page.properties.get("id") {
+            declared_ids.insert(id.to_lowercase());
+        }
+        for_each_block(&page.blocks, &mut |block| {
+            declared_ids.extend(BLOCK_ID_REGEX.captures_iter(&block.content).map(|cap| cap[1].to_lowercase()));
+        });
+    }
+
+    for page in graph.pages() {
+        let incoming = graph.get_backlinks(&page.path)
+            .iter()
+            .any(|source| *source != page.path);
+        if !incoming && !feed::is_journal(page) {
+            issues.push(HealthIssue {
+                rule: HealthRule::OrphanPage,
+                page: page.path.clone(),
+                target: None,
+                message: "No other page links here".to_string(),
+            });
+        }
+
+        let outgoing = page.links.iter()
+            .filter_map(|link| names.get(&link.to_lowercase()))
+            .flatten()
+            .any(|target| *target != page.path);
+        if !outgoing {
+            issues.push(HealthIssue {
+                rule: HealthRule::DeadEnd,
+                page: page.path.clone(),
+                target: None,
+                message: "Links to no other page".to_string(),
+            });
+        }
+
+        let mut has_content = false;
+        let mut stale_refs: Vec<String> = Vec::new();
+        for_each_block(&page.blocks, &mut |block| {
+            has_content |= !block.content.trim().is_empty();
+            for cap in BLOCK_REF_REGEX.captures_iter(&block.content) {
+                let id = cap[1].to_lowercase();
+                if !declared_ids.contains(&id) && !stale_refs.contains(&id) {
+                    stale_refs.push(id);
+                }
+            }
+        });
+
+        if !has_content {
+            issues.push(HealthIssue {
+                rule: HealthRule::EmptyPage,
+                page: page.path.clone(),
+                target: None,
+                message: "Has no content".to_string(),
+            });
+        }
+
+        for id in stale_refs {
+            issues.push(HealthIssue {
+                rule: HealthRule::StaleBlockRef,
+                page: page.path.clone(),
+                message: format!("References block (({})), which no page declares", id),
+                target: Some(id),
+            });
+        }
+    }
+
+    let mut titles: HashMap<String, Vec<&str>> = HashMap::new();
+    for page in graph.pages() {
+        titles.entry(page.title.to_lowercase()).or_default().push(&page.path);
+    }
+    for paths in titles.values().filter(|paths| paths.len() > 1) {
+        for path in paths {
+            for other in paths.iter().filter(|other| *other != path) {
+                issues.push(HealthIssue {
+                    rule: HealthRule::DuplicateTitle,
+                    page: path.to_string(),
+                    target: Some(other.to_string()),
+                    message: format!("Has the same title as {}, so links to it are ambiguous", other),
+                });
+            }
+        }
+    }
+
+    issues.sort_by(|a, b| {
+        a.rule.cmp(&b.rule)
+            .then_with(|| a.page.cmp(&b.page))
+            .then_with(|| a.target.cmp(&b.target))
+    });
+
+    let mut counts: BTreeMap<HealthRule, usize> = HealthRule::ALL.iter().map(|rule| (*rule, 0)).collect();
+    for issue in &issues {
+        *counts.entry(issue.rule).or_default() += 1;
+    }
+
+    HealthReport {
+        page_count: graph.pages().count(),
+        counts,
+        issues,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::test_graph;
+
+    fn issues(report: &HealthReport, rule: HealthRule) -> Vec<(&str, Option<&str>)> {
+        report.issues.iter()
+            .filter(|issue| issue.rule == rule)
+            .map(|issue| (issue.page.as_str(), issue.target.as_deref()))
+            .collect()
+    }
+
+    #[test]
+    fn test_health_checks() {
+        let graph = test_graph(&[
+            ("pages/hub.md", "- See [[Rust]], [[Nowhere]] and ((00000000-0000-0000-0000-0000000000aa))\n- Also ((00000000-0000-0000-0000-0000000000bb))"),
+            ("pages/Rust.md", "- Systems language [[hub]]\n  id:: 00000000-0000-0000-0000-0000000000AA"),
+            ("notes/rust.md", "- Another rust"),
+            ("pages/blank.md", "title:: Blank\n"),
+            ("journals/2024_01_01.md", "- Wrote about [[nowhere]]"),
+        ]);
+
+        let report = check(&graph);
+        assert_eq!(report.page_count, 5);
+        assert_eq!(issues(&report, HealthRule::BrokenLink), vec![
+            ("journals/2024_01_01.md", Some("nowhere")),
+            ("pages/hub.md", Some("Nowhere")),
+        ]);
+        // Journals are never orphans, and notes/rust.md is linked by its title
+        assert_eq!(issues(&report, HealthRule::OrphanPage), vec![("pages/blank.md", None)]);
+        assert_eq!(issues(&report, HealthRule::DeadEnd), vec![
+            ("journals/2024_01_01.md", None),
+            ("notes/rust.md", None),
+            ("pages/blank.md", None),
+        ]);
+        assert_eq!(issues(&report, HealthRule::DuplicateTitle), vec![
+            ("notes/rust.md", Some("pages/Rust.md")),
+            ("pages/Rust.md", Some("notes/rust.md")),
+        ]);
+        assert_eq!(issues(&report, HealthRule::EmptyPage), vec![("pages/blank.md", None)]);
+        assert_eq!(issues(&report, HealthRule::StaleBlockRef), vec![
+            ("pages/hub.md", Some("00000000-0000-0000-0000-0000000000bb")),
+        ]);
+
+        assert_eq!(report.counts[&HealthRule::BrokenLink], 2);
+        assert_eq!(report.count(&[HealthRule::EmptyPage, HealthRule::StaleBlockRef]), 2);
+        assert_eq!(report.count(&[]), 0);
+    }
+
+    #[test]
+    fn test_report_json() {
+        let report = check(&test_graph(&[("a.md", "- [[missing]]")]));
+        let json: serde_json::Value = serde_json::from_str(&report.to_json().unwrap()).unwrap();
+        assert_eq!(json["counts"]["broken-link"], 1);
+        assert_eq!(json["counts"]["stale-block-ref"], 0);
+        assert_eq!(json["issues"][0]["rule"], "broken-link");
+        assert_eq!(json["issues"][0]["target"], "missing");
+    }
+}
@@ -1,13 +1,40 @@
 use wasm_bindgen::prelude::*;
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 
+mod cache;
 mod converter;
 mod parser;
 mod graph;
+mod health;
 mod exporter;
+mod feed;
+mod metadata;
+mod parallel;
+mod postprocess;
+mod sitemap;
+mod slug;
+mod theme;
+mod urls;
+mod zip;
+pub mod diagnostics;
 pub mod errors;
+pub mod fs;
+pub mod progress;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod cli;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod native;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod serve;
 
+pub use converter::{BrowserFiles, NodeDir, SourceFile};
+pub use diagnostics::{Diagnostic, ErrorPolicy, Severity, Span};
 pub use errors::PublishError;
+pub use fs::{GraphFiles, GraphSource, MemoryFs, OutputSink};
+pub use health::{HealthReport, HealthRule};
+pub use progress::{Phase, Progress, ProgressEvent};
+pub use zip::ZipWriter;
 
 /// Initialize panic hook for better error messages in WASM
 #[wasm_bindgen(start)]
@@ -17,28 +44,111 @@ pub fn init() {
 }
 
 /// Console logging helper
+#[cfg(target_arch = "wasm32")]
 #[wasm_bindgen]
 extern "C" {
     #[wasm_bindgen(js_namespace = console)]
     fn log(s: &str);
 }
 
+/// Native builds log warnings to stderr
+#[cfg(not(target_arch = "wasm32"))]
+fn log(s: &str) {
+    eprintln!("{}", s);
+}
+
 /// Configuration for publishing
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[wasm_bindgen]
 pub struct PublishConfig {
     #[wasm_bindgen(skip)]
+    #[serde(default)]
     pub input_dir: String,
     #[wasm_bindgen(skip)]
+    #[serde(default)]
     pub output_dir: String,
     #[wasm_bindgen(skip)]
     pub theme: String,
     #[wasm_bindgen(skip)]
+    #[serde(default)]
+    pub theme_mode: theme::ThemeMode,
+    #[wasm_bindgen(skip)]
+    #[serde(default)]
+    pub accent_color: theme::AccentColor,
+    #[wasm_bindgen(skip)]
     pub include_backlinks: bool,
     #[wasm_bindgen(skip)]
     pub include_graph_view: bool,
     #[wasm_bindgen(skip)]
     pub custom_css: Option<String>,
+    #[wasm_bindgen(skip)]
+    #[serde(default)]
+    pub include_unlinked_references: bool,
+    #[wasm_bindgen(skip)]
+    #[serde(default = "exporter::default_max_unlinked_references")]
+    pub max_unlinked_references: usize,
+    #[wasm_bindgen(skip)]
+    #[serde(default)]
+    pub include_related_pages: bool,
+    #[wasm_bindgen(skip)]
+    #[serde(default = "exporter::default_min_related_score")]
+    pub min_related_score: usize,
+    #[wasm_bindgen(skip)]
+    #[serde(default = "exporter::default_max_related_pages")]
+    pub max_related_pages: usize,
+    #[wasm_bindgen(skip)]
+    #[serde(default = "exporter::default_generate_stub_pages")]
+    pub generate_stub_pages: bool,
+    #[wasm_bindgen(skip)]
+    #[serde(default)]
+    pub base_url: Option<String>,
+    #[wasm_bindgen(skip)]
+    #[serde(default)]
+    pub generate_feed: bool,
+    #[wasm_bindgen(skip)]
+    #[serde(default = "exporter::default_feed_entries")]
+    pub feed_entries: usize,
+    #[wasm_bindgen(skip)]
+    #[serde(default)]
+    pub tag_feeds: bool,
+    #[wasm_bindgen(skip)]
+    #[serde(default)]
+    pub link_style: urls::LinkStyle,
+    #[wasm_bindgen(skip)]
+    #[serde(default)]
+    pub base_path: Option<String>,
+    #[wasm_bindgen(skip)]
+    #[serde(default)]
+    pub slug_strategy: slug::SlugStrategy,
+    #[wasm_bindgen(skip)]
+    #[serde(default)]
+    pub redirect_from: Vec<slug::SlugStrategy>,
+    #[wasm_bindgen(skip)]
+    #[serde(default = "exporter::default_minify")]
+    pub minify: bool,
+    #[wasm_bindgen(skip)]
+    #[serde(default)]
+    pub precompress: bool,
+    /// Keep a build cache in the output directory and only regenerate what changed
+    #[wasm_bindgen(skip)]
+    #[serde(default)]
+    pub incremental: bool,
+    /// Write health-report.json listing broken links, orphans and other graph problems
+    #[wasm_bindgen(skip)]
+    #[serde(default)]
+    pub health_report: bool,
+    /// Also write the report as health-report.html
+    #[wasm_bindgen(skip)]
+    #[serde(default)]
+    pub health_report_html: bool,
+    /// Health rules that fail the publish when the graph breaks them
+    #[wasm_bindgen(skip)]
+    #[serde(default)]
+    pub fail_on: Vec<health::HealthRule>,
+    /// Whether parse errors and other diagnostics fail the publish
+    #[wasm_bindgen(skip)]
+    #[serde(default)]
+    pub error_policy: ErrorPolicy,
 }
 
 #[wasm_bindgen]
@@ -49,9 +159,32 @@ impl PublishConfig {
             input_dir,
             output_dir,
             theme: "default".to_string(),
+            theme_mode: theme::ThemeMode::default(),
+            accent_color: theme::AccentColor::default(),
             include_backlinks: true,
             include_graph_view: false,
             custom_css: None,
+            include_unlinked_references: false,
+            max_unlinked_references: exporter::default_max_unlinked_references(),
+            include_related_pages: false,
+            min_related_score: exporter::default_min_related_score(),
+            max_related_pages: exporter::default_max_related_pages(),
+            generate_stub_pages: exporter::default_generate_stub_pages(),
+            base_url: None,
+            generate_feed: false,
+            feed_entries: exporter::default_feed_entries(),
+            tag_feeds: false,
+            link_style: urls::LinkStyle::default(),
+            base_path: None,
+            slug_strategy: slug::SlugStrategy::default(),
+            redirect_from: Vec::new(),
+            minify: exporter::default_minify(),
+            precompress: false,
+            incremental: false,
+            health_report: false,
+            health_report_html: false,
+            fail_on: Vec::new(),
+            error_policy: ErrorPolicy::default(),
         }
     }
 
@@ -65,6 +198,30 @@ impl PublishConfig {
         self.theme = theme;
     }
 
+    /// `light` or `dark`
+    #[wasm_bindgen(getter)]
+    pub fn theme_mode(&self) -> String {
+        variant_name(&self.theme_mode)
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_theme_mode(&mut self, mode: String) -> Result<(), JsValue> {
+        self.theme_mode = parse_variant(mode, "theme mode")?;
+        Ok(())
+    }
+
+    /// One of the Logseq accent colours, e.g. `blue` or `tomato`
+    #[wasm_bindgen(getter)]
+    pub fn accent_color(&self) -> String {
+        variant_name(&self.accent_color)
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_accent_color(&mut self, color: String) -> Result<(), JsValue> {
+        self.accent_color = parse_variant(color, "accent color")?;
+        Ok(())
+    }
+
     #[wasm_bindgen(getter)]
     pub fn include_backlinks(&self) -> bool {
         self.include_backlinks
@@ -84,6 +241,247 @@ impl PublishConfig {
     pub fn set_include_graph_view(&mut self, include: bool) {
         self.include_graph_view = include;
     }
+
+    #[wasm_bindgen(getter)]
+    pub fn include_unlinked_references(&self) -> bool {
+        self.include_unlinked_references
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_include_unlinked_references(&mut self, include: bool) {
+        self.include_unlinked_references = include;
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn max_unlinked_references(&self) -> usize {
+        self.max_unlinked_references
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_max_unlinked_references(&mut self, max: usize) {
+        self.max_unlinked_references = max;
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn include_related_pages(&self) -> bool {
+        self.include_related_pages
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_include_related_pages(&mut self, include: bool) {
+        self.include_related_pages = include;
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn min_related_score(&self) -> usize {
+        self.min_related_score
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_min_related_score(&mut self, score: usize) {
+        self.min_related_score = score;
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn max_related_pages(&self) -> usize {
+        self.max_related_pages
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_max_related_pages(&mut self, max: usize) {
+        self.max_related_pages = max;
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn generate_stub_pages(&self) -> bool {
+        self.generate_stub_pages
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_generate_stub_pages(&mut self, generate: bool) {
+        self.generate_stub_pages = generate;
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn base_url(&self) -> Option<String> {
+        self.base_url.clone()
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_base_url(&mut self, base_url: Option<String>) {
+        self.base_url = base_url;
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn generate_feed(&self) -> bool {
+        self.generate_feed
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_generate_feed(&mut self, generate: bool) {
+        self.generate_feed = generate;
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn feed_entries(&self) -> usize {
+        self.feed_entries
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_feed_entries(&mut self, entries: usize) {
+        self.feed_entries = entries;
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn tag_feeds(&self) -> bool {
+        self.tag_feeds
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_tag_feeds(&mut self, include: bool) {
+        self.tag_feeds = include;
+    }
+
+    /// `relative` or `absolute`
+    #[wasm_bindgen(getter)]
+    pub fn link_style(&self) -> String {
+        variant_name(&self.link_style)
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_link_style(&mut self, style: String) -> Result<(), JsValue> {
+        self.link_style = parse_variant(style, "link style")?;
+        Ok(())
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn base_path(&self) -> Option<String> {
+        self.base_path.clone()
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_base_path(&mut self, base_path: Option<String>) {
+        self.base_path = base_path;
+    }
+
+    /// `preserve`, `lowercase-kebab` or `pretty`
+    #[wasm_bindgen(getter)]
+    pub fn slug_strategy(&self) -> String {
+        variant_name(&self.slug_strategy)
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_slug_strategy(&mut self, strategy: String) -> Result<(), JsValue> {
+        self.slug_strategy = parse_variant(strategy, "slug strategy")?;
+        Ok(())
+    }
+
+    /// Slug strategies whose page paths redirect to the current ones
+    #[wasm_bindgen(getter)]
+    pub fn redirect_from(&self) -> Vec<String> {
+        self.redirect_from.iter().map(variant_name).collect()
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_redirect_from(&mut self, strategies: Vec<String>) -> Result<(), JsValue> {
+        self.redirect_from = strategies
+            .into_iter()
+            .map(|strategy| parse_variant(strategy, "slug strategy"))
+            .collect::<Result<_, _>>()?;
+        Ok(())
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn minify(&self) -> bool {
+        self.minify
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_minify(&mut self, minify: bool) {
+        self.minify = minify;
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn precompress(&self) -> bool {
+        self.precompress
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_precompress(&mut self, precompress: bool) {
+        self.precompress = precompress;
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn incremental(&self) -> bool {
+        self.incremental
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_incremental(&mut self, incremental: bool) {
+        self.incremental = incremental;
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn health_report(&self) -> bool {
+        self.health_report
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_health_report(&mut self, write: bool) {
+        self.health_report = write;
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn health_report_html(&self) -> bool {
+        self.health_report_html
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_health_report_html(&mut self, write: bool) {
+        self.health_report_html = write;
+    }
+
+    /// Health rules such as `broken-link` or `orphan-page`
+    #[wasm_bindgen(getter)]
+    pub fn fail_on(&self) -> Vec<String> {
+        self.fail_on.iter().map(variant_name).collect()
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_fail_on(&mut self, rules: Vec<String>) -> Result<(), JsValue> {
+        self.fail_on = rules
+            .into_iter()
+            .map(|rule| parse_variant(rule, "health rule"))
+            .collect::<Result<_, _>>()?;
+        Ok(())
+    }
+
+    /// `"warn"`, `"strict"` or `{ threshold: n }`
+    #[wasm_bindgen(getter)]
+    pub fn error_policy(&self) -> Result<JsValue, JsValue> {
+        serde_wasm_bindgen::to_value(&self.error_policy).map_err(|e| PublishError::from(e).into())
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_error_policy(&mut self, policy: JsValue) -> Result<(), JsValue> {
+        self.error_policy = serde_wasm_bindgen::from_value(policy)
+            .map_err(|e| PublishError::invalid_input(format!("Invalid error policy: {}", e)))?;
+        Ok(())
+    }
+}
+
+/// Name of a unit enum variant in its serde form, e.g. `lowercase-kebab`
+fn variant_name<T: Serialize>(value: &T) -> String {
+    serde_json::to_value(value)
+        .ok()
+        .and_then(|value| value.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+/// Parse a unit enum variant from its serde name, naming `what` in the error
+fn parse_variant<T: serde::de::DeserializeOwned>(name: String, what: &str) -> Result<T, PublishError> {
+    serde_json::from_value(serde_json::Value::String(name))
+        .map_err(|e| PublishError::invalid_input(format!("Invalid {}: {}", what, e)))
 }
 
 /// Publishing statistics
@@ -94,76 +492,465 @@ pub struct PublishStats {
     pub total_blocks: usize,
     pub total_links: usize,
     pub orphan_pages: usize,
+    /// Pages renamed because their output path was already taken
+    pub slug_collisions: usize,
+    /// Total size of the text outputs after minification
+    pub output_bytes: usize,
+    pub minify_saved_bytes: usize,
+    /// Number of outputs written with `.gz` and `.br` siblings
+    pub compressed_files: usize,
+    pub gzip_bytes: usize,
+    pub brotli_bytes: usize,
+    /// Pages whose output from the previous incremental build was still current
+    pub reused_pages: usize,
+    /// Problems found by the graph health checks; they only run when a health
+    /// report is written or `fail_on` is set
+    pub health_issues: usize,
+    /// Pages that failed to parse and other problems with source files
+    #[wasm_bindgen(skip)]
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Parse source files into a graph, skipping pages that fail to parse and
+/// returning a diagnostic for each
+fn build_graph(files: HashMap<String, converter::SourceFile>) -> Result<(graph::Graph, Vec<Diagnostic>), PublishError> {
+    build_graph_cached(files, &HashMap::new(), None, &mut ())
+}
+
+/// Like `build_graph`, but reuse parsed pages from `cache` whose source hash is unchanged
+fn build_graph_cached(
+    files: HashMap<String, converter::SourceFile>,
+    source_hashes: &HashMap<String, String>,
+    cache: Option<&cache::BuildCache>,
+    progress: &mut dyn Progress,
+) -> Result<(graph::Graph, Vec<Diagnostic>), PublishError> {
+    // Parse in parallel, then add pages in path order so backlinks don't depend on scheduling
+    let mut files: Vec<(String, converter::SourceFile)> = files.into_iter().collect();
+    files.sort_by(|a, b| a.0.cmp(&b.0));
+    let total = files.len();
+    let parsed = parallel::map_with_progress(files, |(path, file)| {
+        let cached = cache.zip(source_hashes.get(&path))
+            .and_then(|(cache, hash)| cache.page(&path, hash))
+            .cloned();
+        let parsed = match cached {
+            Some(page) => Ok(page),
+            None => parser::parse_logseq_page(&file.content, &path),
+        };
+        (path, file.modified, parsed)
+    }, |done| progress::step(progress, Phase::Parse, done, total).is_ok())
+    .ok_or(PublishError::Cancelled)?;
+
+    let mut graph = graph::Graph::new();
+    let mut diagnostics = Vec::new();
+    let total = parsed.iter().filter(|(_, _, parsed)| parsed.is_ok()).count();
+    let mut done = 0;
+    for (_, modified, parsed) in parsed {
+        match parsed {
+            Ok(mut page) => {
+                page.modified = modified;
+                graph.add_page(page);
+                done += 1;
+                progress::step(progress, Phase::Graph, done, total)?;
+            }
+            Err(diagnostic) => diagnostics.push(diagnostic),
+        }
+    }
+    Ok((graph, diagnostics))
+}
+
+/// Pages whose output paths collide under the slug strategy, returning the number of
+/// collisions and a warning for each renamed page
+fn report_slug_collisions(graph: &graph::Graph, strategy: slug::SlugStrategy) -> (usize, Vec<Diagnostic>) {
+    let output = slug::assign_output_paths(graph.pages().map(|page| page.path.as_str()), strategy);
+    let diagnostics = output.collisions
+        .iter()
+        .flat_map(|collision| {
+            let paths = &output.paths;
+            collision.sources.iter().skip(1).map(move |source| {
+                Diagnostic::warning("slug-collision", source.clone(), format!(
+                    "Output path {} is already taken by {}; published as {}",
+                    collision.path, collision.sources[0], paths.get(source).map_or("", String::as_str),
+                ))
+            })
+        })
+        .collect();
+    (output.collisions.len(), diagnostics)
+}
+
+/// Diagnostics of all passes, ordered by file
+fn merge_diagnostics(mut diagnostics: Vec<Diagnostic>, more: Vec<Diagnostic>) -> Vec<Diagnostic> {
+    diagnostics.extend(more);
+    diagnostics.sort_by(|a, b| a.file.cmp(&b.file).then_with(|| a.code.cmp(&b.code)));
+    diagnostics
+}
+
+impl PublishConfig {
+    fn export_config(&self) -> exporter::ExportConfig {
+        exporter::ExportConfig {
+            theme: self.theme.clone(),
+            theme_mode: self.theme_mode,
+            accent_color: self.accent_color,
+            include_backlinks: self.include_backlinks,
+            include_graph_view: self.include_graph_view,
+            custom_css: self.custom_css.clone(),
+            include_unlinked_references: self.include_unlinked_references,
+            max_unlinked_references: self.max_unlinked_references,
+            include_related_pages: self.include_related_pages,
+            min_related_score: self.min_related_score,
+            max_related_pages: self.max_related_pages,
+            generate_stub_pages: self.generate_stub_pages,
+            base_url: self.base_url.clone(),
+            generate_feed: self.generate_feed,
+            feed_entries: self.feed_entries,
+            tag_feeds: self.tag_feeds,
+            link_style: self.link_style,
+            base_path: self.base_path.clone(),
+            slug_strategy: self.slug_strategy,
+            redirect_from: self.redirect_from.clone(),
+            minify: self.minify,
+            precompress: self.precompress,
+        }
+    }
+}
+
+/// Files generated for a site: text outputs and their precompressed siblings
+struct SiteOutput {
+    files: HashMap<String, String>,
+    compressed_files: HashMap<String, Vec<u8>>,
+    /// Files of the previous build that are no longer generated
+    removed_files: Vec<String>,
+    /// Build cache describing the site after this build
+    cache: cache::BuildCache,
+    health: health::HealthReport,
+    stats: PublishStats,
+}
+
+/// Build the graph, export it and post-process the outputs, without touching the file system
+///
+/// With the `previous` build's cache, unchanged sources are not re-parsed, pages whose
+/// render inputs are unchanged are not re-rendered, and outputs identical to the
+/// previous build are left out of `files`.
+fn render_site(
+    config: &PublishConfig,
+    files: fs::GraphFiles,
+    previous: Option<&cache::BuildCache>,
+    progress: &mut dyn Progress,
+) -> Result<SiteOutput, PublishError> {
+    let export_config = config.export_config();
+    let mut build_cache = cache::BuildCache::new(cache::config_hash(&export_config));
+    // Pages and outputs are only reusable when built with the same config
+    let reusable = previous.filter(|previous| previous.config_hash == build_cache.config_hash);
+
+    // Build graph from files
+    let fs::GraphFiles { files, unreadable } = files;
+    let source_hashes: HashMap<String, String> = files.iter()
+        .map(|(path, file)| (path.clone(), cache::content_hash(file.content.as_bytes())))
+        .collect();
+    let (graph, parse_diagnostics) = build_graph_cached(files, &source_hashes, reusable, progress)?;
+
+    // Export to HTML, re-rendering only pages whose inputs changed
+    let (page_collisions, slug_diagnostics) = report_slug_collisions(&graph, config.slug_strategy);
+    let previous_keys: HashMap<String, String> = reusable
+        .map(|previous| previous.render_keys().map(|(path, key)| (path.clone(), key.clone())).collect())
+        .unwrap_or_default();
+    let export = exporter::export_graph_incremental(&graph, &export_config, &source_hashes, &previous_keys, |done, total| {
+        progress::step(progress, Phase::Render, done, total).is_ok()
+    });
+    if progress.is_cancelled() {
+        return Err(PublishError::Cancelled);
+    }
+    let export = export.map_err(PublishError::export)?;
+    let slug_collisions = page_collisions + export.collisions.len();
+    let mut diagnostics = merge_diagnostics(parse_diagnostics, slug_diagnostics);
+    diagnostics = merge_diagnostics(diagnostics, export.collisions);
+    diagnostics = merge_diagnostics(diagnostics, unreadable);
+
+    let mut html_files = export.files;
+
+    // Graph health checks, run only when a report or failure threshold needs them
+    let health = if config.health_report || config.health_report_html || !config.fail_on.is_empty() {
+        health::check(&graph)
+    } else {
+        health::HealthReport::default()
+    };
+    if config.health_report || config.health_report_html {
+        html_files.insert(health::REPORT_JSON_PATH.to_string(), health.to_json().map_err(PublishError::export)?);
+    }
+    if config.health_report_html {
+        let links = exporter::LinkResolver::new(&graph, &export_config);
+        html_files.insert(health::REPORT_HTML_PATH.to_string(), exporter::export_health_report_to_html(&health, &links));
+    }
+
+    for (path, content) in &html_files {
+        build_cache.outputs.insert(path.clone(), cache::content_hash(content.as_bytes()));
+    }
+    if let Some(previous) = reusable {
+        for path in &export.reused {
+            if let Some(hash) = previous.outputs.get(path) {
+                build_cache.outputs.insert(path.clone(), hash.clone());
+            }
+        }
+        // Outputs identical to the previous build are already in place
+        html_files.retain(|path, _| previous.outputs.get(path) != build_cache.outputs.get(path));
+    }
+
+    // Minify and precompress outputs
+    let (compressed_files, post_stats) =
+        postprocess::post_process(&mut html_files, config.minify, config.precompress)
+            .map_err(PublishError::export)?;
+
+    if config.precompress {
+        build_cache.compressed = build_cache.outputs.keys()
+            .flat_map(|path| postprocess::precompressed_paths(path))
+            .collect();
+    }
+    for page in graph.pages() {
+        build_cache.pages.insert(page.path.clone(), cache::CachedPage {
+            source_hash: source_hashes.get(&page.path).cloned().unwrap_or_default(),
+            render_key: export.render_keys.get(&page.path).cloned().unwrap_or_default(),
+            output: export.page_outputs.get(&page.path).cloned().unwrap_or_default(),
+            page: page.clone(),
+        });
+    }
+
+    // Files of any previous build that this build no longer generates
+    let written: HashSet<&String> = build_cache.written_paths().collect();
+    let removed_files = previous
+        .map(|previous| previous.written_paths().filter(|path| !written.contains(path)).cloned().collect())
+        .unwrap_or_default();
+
+    let stats = graph.stats();
+    Ok(SiteOutput {
+        files: html_files,
+        compressed_files,
+        removed_files,
+        cache: build_cache,
+        stats: PublishStats {
+            page_count: stats.page_count,
+            total_blocks: stats.total_blocks,
+            total_links: stats.total_links,
+            orphan_pages: stats.orphan_pages,
+            slug_collisions,
+            output_bytes: post_stats.output_bytes,
+            minify_saved_bytes: post_stats.minify_saved_bytes,
+            compressed_files: post_stats.compressed_files,
+            gzip_bytes: post_stats.gzip_bytes,
+            brotli_bytes: post_stats.brotli_bytes,
+            reused_pages: export.reused.len(),
+            health_issues: health.issues.len(),
+            diagnostics,
+        },
+        health,
+    })
+}
+
+/// Read a graph from `source`, export it and write the site to `sink`
+///
+/// The config's `input_dir` and `output_dir` are not used; `source` and `sink` say where
+/// files come from and go to.
+pub async fn publish_to(
+    config: &PublishConfig,
+    source: &impl GraphSource,
+    sink: &mut impl OutputSink,
+) -> Result<PublishStats, PublishError> {
+    publish_with_progress(config, source, sink, &mut ()).await
+}
+
+/// Like [`publish_to`], reporting each phase to `progress` and stopping when it is cancelled
+pub async fn publish_with_progress(
+    config: &PublishConfig,
+    source: &impl GraphSource,
+    sink: &mut impl OutputSink,
+    progress: &mut dyn Progress,
+) -> Result<PublishStats, PublishError> {
+    let files = source.read_graph_files().await?;
+    progress::step(progress, Phase::Read, files.files.len(), files.files.len())?;
+
+    // An unreadable or outdated cache just means a full rebuild
+    let previous = if config.incremental {
+        sink.read_file(cache::CACHE_PATH).await?
+            .and_then(|data| cache::BuildCache::from_json(&data))
+    } else {
+        None
+    };
+    let site = render_site(config, files, previous.as_ref(), progress)?;
+
+    // Nothing is written when the diagnostics break the error policy
+    if !config.error_policy.allows(&site.stats.diagnostics) {
+        let lines: Vec<String> = site.stats.diagnostics.iter().map(|d| format!("  {}", d)).collect();
+        return Err(PublishError::graph(format!(
+            "{} diagnostics exceed error policy {}:\n{}",
+            lines.len(), config.error_policy, lines.join("\n"),
+        )));
+    }
+
+    // Write output files in path order, each page before its precompressed siblings
+    let mut outputs: Vec<(String, Vec<u8>)> = site.files
+        .into_iter()
+        .map(|(path, content)| (path, content.into_bytes()))
+        .chain(site.compressed_files)
+        .collect();
+    outputs.sort_by(|a, b| a.0.cmp(&b.0));
+    let cache = if config.incremental { Some(site.cache.to_json()?) } else { None };
+
+    // Stage the changed files and only move them into place once all are written,
+    // so a failed write leaves the previous site in place
+    sink.begin().await?;
+    if let Err(e) = write_site(sink, outputs, &site.removed_files, cache.as_deref(), progress).await {
+        // Report the write error; a failed cleanup only leaves the staging area behind
+        let _ = sink.rollback().await;
+        return Err(e);
+    }
+    sink.commit().await?;
+
+    // The site and report are written either way, so failures can be inspected
+    let failures = site.health.count(&config.fail_on);
+    if failures > 0 {
+        let broken: Vec<String> = site.health.counts.iter()
+            .filter(|(rule, count)| **count > 0 && config.fail_on.contains(rule))
+            .map(|(rule, count)| format!("{} {}", count, rule))
+            .collect();
+        return Err(PublishError::graph(format!("Health check failed: {}", broken.join(", "))));
+    }
+
+    Ok(site.stats)
+}
+
+/// Write a build's outputs, remove files it no longer generates, then record its cache
+async fn write_site(
+    sink: &mut impl OutputSink,
+    outputs: Vec<(String, Vec<u8>)>,
+    removed_files: &[String],
+    cache: Option<&[u8]>,
+    progress: &mut dyn Progress,
+) -> Result<(), PublishError> {
+    let total = outputs.len() + removed_files.len();
+    let mut done = 0;
+    progress::step(progress, Phase::Write, done, total)?;
+    for (path, content) in outputs {
+        sink.write_file(&path, &content).await?;
+        done += 1;
+        progress::step(progress, Phase::Write, done, total)?;
+    }
+    for path in removed_files {
+        sink.remove_file(path).await?;
+        done += 1;
+        progress::step(progress, Phase::Write, done, total)?;
+    }
+
+    // Record the build last, so an interrupted build is redone next time
+    if let Some(cache) = cache {
+        sink.write_file(cache::CACHE_PATH, cache).await?;
+    }
+    Ok(())
 }
 
 /// Main publish function - Entry point from JavaScript
 ///
 /// # Arguments
 /// * `config_obj` - JavaScript object containing configuration
+/// * `on_progress` - Optional function called with `{ phase, done, total }` as publishing
+///   goes through the read, parse, graph, render and write phases
+/// * `signal` - Optional `AbortSignal`; aborting it rejects the promise and leaves the
+///   output directory as it was. It is checked between phases and while writing, since
+///   parsing, graph building and rendering don't yield to the event loop
 ///
 /// # Returns
 /// Promise that resolves to PublishStats
 #[wasm_bindgen]
-pub async fn publish(config_obj: JsValue) -> Result<JsValue, JsValue> {
+pub async fn publish(
+    config_obj: JsValue,
+    on_progress: Option<js_sys::Function>,
+    signal: Option<JsValue>,
+) -> Result<JsValue, JsValue> {
     let config: PublishConfig = serde_wasm_bindgen::from_value(config_obj)
         .map_err(|e| PublishError::invalid_input(format!("Invalid config: {}", e)))?;
 
-    // Read all markdown files from input directory
-    let files = converter::read_graph_files(&config.input_dir).await?;
+    // Read markdown files from the input directory and write the site to the output directory
+    let source = NodeDir::new(config.input_dir.clone());
+    let mut sink = NodeDir::new(config.output_dir.clone());
+    let mut progress = progress::JsProgress::new(on_progress, signal);
+    let stats = publish_with_progress(&config, &source, &mut sink, &mut progress).await?;
 
-    // Build graph from files
-    let mut graph = graph::Graph::new();
-    for (path, content) in files {
-        match parser::parse_logseq_page(&content, &path) {
-            Ok(page) => graph.add_page(page),
-            Err(e) => {
-                log(&format!("Warning: Failed to parse {}: {}", path, e));
-                continue;
-            }
-        }
-    }
+    // Return statistics
+    serde_wasm_bindgen::to_value(&stats)
+        .map_err(|e| PublishError::from(e).into())
+}
 
-    // Export to HTML
-    let export_config = exporter::ExportConfig {
-        theme: config.theme.clone(),
-        include_backlinks: config.include_backlinks,
-        include_graph_view: config.include_graph_view,
-        custom_css: config.custom_css.clone(),
-    };
+/// Publish a graph folder picked or dropped in the browser as a downloadable zip archive
+///
+/// # Arguments
+/// * `config_obj` - JavaScript object containing configuration; `input_dir` and `output_dir` are ignored
+/// * `input` - The folder's `FileList` (e.g. from `<input type="file" webkitdirectory>`),
+///   an array of `File`s or a `FileSystemDirectoryHandle`
+///
+/// # Returns
+/// Promise that resolves to the zip archive's bytes
+#[wasm_bindgen]
+pub async fn publish_zip(config_obj: JsValue, input: JsValue) -> Result<js_sys::Uint8Array, JsValue> {
+    let config: PublishConfig = serde_wasm_bindgen::from_value(config_obj)
+        .map_err(|e| PublishError::invalid_input(format!("Invalid config: {}", e)))?;
 
-    let html_files = exporter::export_graph_to_html(&graph, &export_config)?;
+    let source = BrowserFiles::new(input);
+    let mut zip = ZipWriter::new();
+    let stats = publish_to(&config, &source, &mut zip).await?;
+    log(&format!("Published {} pages", stats.page_count));
 
-    // Write output files
-    converter::write_output_files(&config.output_dir, html_files).await?;
+    let data = zip.finish()?;
+    Ok(js_sys::Uint8Array::from(data.as_slice()))
+}
 
-    // Return statistics
-    let stats = graph.stats();
-    let pub_stats = PublishStats {
-        page_count: stats.page_count,
-        total_blocks: stats.total_blocks,
-        total_links: stats.total_links,
-        orphan_pages: stats.orphan_pages,
-    };
+/// Publish a graph held in memory, such as files picked in the browser
+///
+/// # Arguments
+/// * `config_obj` - JavaScript object containing configuration; `input_dir` and `output_dir` are ignored
+/// * `files_obj` - Object mapping graph-relative paths to markdown content
+///
+/// # Returns
+/// Promise that resolves to `{ stats, files }`, where `files` maps output paths to `Uint8Array`s
+#[wasm_bindgen]
+pub async fn publish_files(config_obj: JsValue, files_obj: JsValue) -> Result<JsValue, JsValue> {
+    let config: PublishConfig = serde_wasm_bindgen::from_value(config_obj)
+        .map_err(|e| PublishError::invalid_input(format!("Invalid config: {}", e)))?;
+    let files: HashMap<String, String> = serde_wasm_bindgen::from_value(files_obj)
+        .map_err(|e| PublishError::invalid_input(format!("Invalid files: {}", e)))?;
 
-    serde_wasm_bindgen::to_value(&pub_stats)
-        .map_err(|e| PublishError::from(e).into())
+    let source: MemoryFs = files.into_iter().collect();
+    let mut site = MemoryFs::new();
+    let stats = publish_to(&config, &source, &mut site).await?;
+
+    let output = js_sys::Object::new();
+    for (path, content) in site.iter() {
+        js_sys::Reflect::set(&output, &JsValue::from_str(path), &js_sys::Uint8Array::from(content))?;
+    }
+
+    let result = js_sys::Object::new();
+    js_sys::Reflect::set(&result, &JsValue::from_str("stats"), &serde_wasm_bindgen::to_value(&stats).map_err(PublishError::from)?)?;
+    js_sys::Reflect::set(&result, &JsValue::from_str("files"), &output)?;
+    Ok(result.into())
 }
 
 /// Parse a Logseq graph and return statistics
+///
+/// Output statistics are zero, and so is `health_issues`: health checks only run as
+/// part of a publish that writes a health report or sets `fail_on`.
+///
+/// # Arguments
+/// * `input_dir` - Graph directory
+/// * `slug_strategy` - Optional slug strategy the graph will be published with, which
+///   decides what output paths collide (default: `preserve`)
 #[wasm_bindgen]
-pub async fn parse_graph(input_dir: String) -> Result<JsValue, JsValue> {
+pub async fn parse_graph(input_dir: String, slug_strategy: Option<String>) -> Result<JsValue, JsValue> {
+    let strategy: slug::SlugStrategy = match slug_strategy {
+        Some(name) => parse_variant(name, "slug strategy")?,
+        None => slug::SlugStrategy::default(),
+    };
     let files = converter::read_graph_files(&input_dir).await?;
 
-    let mut graph = graph::Graph::new();
-    for (path, content) in files {
-        match parser::parse_logseq_page(&content, &path) {
-            Ok(page) => graph.add_page(page),
-            Err(e) => {
-                log(&format!("Warning: Failed to parse {}: {}", path, e));
-                continue;
-            }
-        }
-    }
+    let (graph, parse_diagnostics) = build_graph(files.files)?;
+    let (slug_collisions, slug_diagnostics) = report_slug_collisions(&graph, strategy);
+    let mut diagnostics = merge_diagnostics(parse_diagnostics, slug_diagnostics);
+    diagnostics = merge_diagnostics(diagnostics, files.unreadable);
 
     let stats = graph.stats();
     let pub_stats = PublishStats {
@@ -171,6 +958,15 @@ pub async fn parse_graph(input_dir: String) -> Result<JsValue, JsValue> {
         total_blocks: stats.total_blocks,
         total_links: stats.total_links,
         orphan_pages: stats.orphan_pages,
+        slug_collisions,
+        output_bytes: 0,
+        minify_saved_bytes: 0,
+        compressed_files: 0,
+        gzip_bytes: 0,
+        brotli_bytes: 0,
+        reused_pages: 0,
+        health_issues: 0,
+        diagnostics,
     };
 
     serde_wasm_bindgen::to_value(&pub_stats)
@@ -182,16 +978,7 @@ pub async fn parse_graph(input_dir: String) -> Result<JsValue, JsValue> {
 pub async fn get_backlinks(input_dir: String, page_path: String) -> Result<JsValue, JsValue> {
     let files = converter::read_graph_files(&input_dir).await?;
 
-    let mut graph = graph::Graph::new();
-    for (path, content) in files {
-        match parser::parse_logseq_page(&content, &path) {
-            Ok(page) => graph.add_page(page),
-            Err(e) => {
-                log(&format!("Warning: Failed to parse {}: {}", path, e));
-                continue;
-            }
-        }
-    }
+    let (graph, _) = build_graph(files.files)?;
 
     let backlinks = graph.get_backlinks(&page_path);
     serde_wasm_bindgen::to_value(&backlinks)
@@ -210,4 +997,44 @@ mod tests {
         assert_eq!(config.theme, "default");
         assert!(config.include_backlinks);
     }
+
+    #[test]
+    fn test_link_style_accessors() {
+        let mut config = PublishConfig::new(String::new(), String::new());
+        assert_eq!(config.link_style(), "relative");
+        config.set_link_style("absolute".to_string()).unwrap();
+        assert_eq!(config.link_style, urls::LinkStyle::Absolute);
+        assert_eq!(config.link_style(), "absolute");
+    }
+
+    #[test]
+    fn test_theme_accessors() {
+        let mut config = PublishConfig::new(String::new(), String::new());
+        assert_eq!((config.theme_mode().as_str(), config.accent_color().as_str()), ("light", "blue"));
+        config.set_theme_mode("dark".to_string()).unwrap();
+        config.set_accent_color("tomato".to_string()).unwrap();
+        assert_eq!(config.theme_mode, theme::ThemeMode::Dark);
+        assert_eq!(config.accent_color, theme::AccentColor::Tomato);
+    }
+
+    #[test]
+    fn test_fail_on_accessors() {
+        let mut config = PublishConfig::new(String::new(), String::new());
+        assert!(config.fail_on().is_empty());
+        config.set_fail_on(vec!["broken-link".to_string(), "orphan-page".to_string()]).unwrap();
+        assert_eq!(config.fail_on, [HealthRule::BrokenLink, HealthRule::OrphanPage]);
+        assert_eq!(config.fail_on(), ["broken-link", "orphan-page"]);
+    }
+
+    #[test]
+    fn test_slug_accessors() {
+        let mut config = PublishConfig::new(String::new(), String::new());
+        assert_eq!(config.slug_strategy(), "preserve");
+        config.set_slug_strategy("lowercase-kebab".to_string()).unwrap();
+        assert_eq!(config.slug_strategy, slug::SlugStrategy::LowercaseKebab);
+
+        config.set_redirect_from(vec!["preserve".to_string(), "pretty".to_string()]).unwrap();
+        assert_eq!(config.redirect_from, [slug::SlugStrategy::Preserve, slug::SlugStrategy::Pretty]);
+        assert_eq!(config.redirect_from(), ["preserve", "pretty"]);
+    }
 }
@@ -0,0 +1,241 @@
+//! Social and structured metadata (OpenGraph, Twitter cards, schema.org JSON-LD) derived from page properties
+
+use crate::feed;
+use crate::parser::{Block, Page};
+use crate::sitemap;
+use regex::Regex;
+use serde_json::{json, Map, Value};
+use std::sync::LazyLock;
+
+/// Maximum length of a description derived from block text
+const DESCRIPTION_LENGTH: usize = 160;
+
+/// Metadata describing a published page
+#[derive(Debug, Clone, PartialEq)]
+pub struct PageMetadata {
+    pub title: String,
+    pub description: Option<String>,
+    /// Image URL, absolute when a base URL is configured
+    pub image: Option<String>,
+    pub author: Option<String>,
+    /// Canonical URL of the page, when a base URL is configured
+    pub url: Option<String>,
+    pub date_published: Option<String>,
+    pub date_modified: Option<String>,
+    /// Journals are described as blog posts rather than articles
+    pub is_journal: bool,
+}
+
+/// Collect metadata for the page published at `html_path`.
+///
+/// `description::`, `image::` and `author::` properties take precedence, falling
+/// back to the first block's text and the first image in the page.
+pub fn page_metadata(page: &Page, html_path: &str, base_url: Option<&str>) -> PageMetadata {
+    let description = property(page, "description")
+        .or_else(|| first_block_text(&page.blocks))
+        .map(|text| truncate(&text, DESCRIPTION_LENGTH));
+
+    let image = property(page, "image")
+        .or_else(|| first_image(&page.blocks))
+        .map(|src| absolute_url(base_url, html_path, &src));
+
+    let date_modified = sitemap::last_modified(page);
+
+    PageMetadata {
+        title: page.title.clone(),
+        description,
+        image,
+        author: property(page, "author"),
+        url: base_url.map(|base_url| sitemap::canonical_url(base_url, html_path)),
+        date_published: feed::page_date(page).or_else(|| date_modified.clone()),
+        date_modified,
+        is_journal: feed::is_journal(page),
+    }
+}
+
+/// Metadata for the stub page of a link target without a source file, which only has
+/// a title and, with a base URL, a canonical URL
+pub fn stub_metadata(name: &str, html_path: &str, base_url: Option<&str>) -> PageMetadata {
+    PageMetadata {
+        title: name.to_string(),
+        description: None,
+        image: None,
+        author: None,
+        url: base_url.map(|base_url| sitemap::canonical_url(base_url, html_path)),
+        date_published: None,
+        date_modified: None,
+        is_journal: false,
+    }
+}
+
+/// schema.org JSON-LD for the page, safe to embed in a `<script>` element
+pub fn json_ld(meta: &PageMetadata) -> String {
+    let mut object = Map::new();
+    object.insert("@context".to_string(), json!("https://schema.org"));
+    object.insert(
+        "@type".to_string(),
+        json!(if meta.is_journal { "BlogPosting" } else { "Article" }),
+    );
+    object.insert("headline".to_string(), json!(meta.title));
+
+    let optional = [
+        ("description", &meta.description),
+        ("image", &meta.image),
+        ("url", &meta.url),
+        ("datePublished", &meta.date_published),
+        ("dateModified", &meta.date_modified),
+    ];
+    for (key, value) in optional {
+        if let Some(value) = value {
+            object.insert(key.to_string(), json!(value));
+        }
+    }
+
+    if let Some(author) = &meta.author {
+        object.insert("author".to_string(), json!({ "@type": "Person", "name": author }));
+    }
+
+    // Prevent the content from closing the surrounding script element
+    Value::Object(object).to_string().replace("</", "<\\/")
+}
+
+fn property(page: &Page, key: &str) -> Option<String> {
+    page.properties
+        .get(key)
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+static IMAGE_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"!\[[^\]]*\]\([^)]*\)").unwrap());
+static IMAGE_SOURCE_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"!\[[^\]]*\]\(([^)\s]+)[^)]*\)").unwrap());
+static MARKDOWN_LINK_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\[([^\]]*)\]\([^)]*\)").unwrap());
+
+/// Plain text of the first block with any text, stripped of Logseq and markdown syntax
+fn first_block_text(blocks: &[Block]) -> Option<String> {
+    blocks.iter().find_map(|block| {
+        let text = IMAGE_REGEX.replace_all(&block.content, "");
+        let text = MARKDOWN_LINK_REGEX.replace_all(&text, "$1");
+        let text = text
+            .replace("[[", "")
+            .replace("]]", "")
+            .replace("**", "")
+            .replace('`', "");
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+
+        if text.is_empty() || (text.starts_with('#') && !text.contains(' ')) {
+            first_block_text(&block.children)
+        } else {
+            Some(text)
+        }
+    })
+}
+
+/// Source of the first markdown image in the blocks
+fn first_image(blocks: &[Block]) -> Option<String> {
+    blocks.iter().find_map(|block| {
+        IMAGE_SOURCE_REGEX
+            .captures(&block.content)
+            .map(|caps| caps[1].to_string())
+            .or_else(|| first_image(&block.children))
+    })
+}
+
+/// Resolve an image source relative to the page, made absolute when the base URL is known
+fn absolute_url(base_url: Option<&str>, html_path: &str, src: &str) -> String {
+    let is_absolute = src.contains("://") || src.starts_with("//") || src.starts_with("data:");
+    match base_url {
+        Some(base_url) if !is_absolute => {
+            let resolved = if let Some(rooted) = src.strip_prefix('/') {
+                rooted.to_string()
+            } else {
+                resolve_relative(html_path, src)
+            };
+            sitemap::canonical_url(base_url, &resolved)
+        }
+        _ => src.to_string(),
+    }
+}
+
+/// Resolve `src` against the directory of `from`, both relative to the output root
+fn resolve_relative(from: &str, src: &str) -> String {
+    let mut segments: Vec<&str> = from.split('/').collect();
+    segments.pop();
+
+    for segment in src.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            _ => segments.push(segment),
+        }
+    }
+
+    segments.join("/")
+}
+
+/// Truncate text at a word boundary, appending an ellipsis when shortened
+fn truncate(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+
+    let cut: String = text.chars().take(max_chars).collect();
+    let trimmed = match cut.rfind(' ') {
+        Some(space) if space > 0 => &cut[..space],
+        _ => cut.as_str(),
+    };
+    format!("{}…", trimmed.trim_end_matches(|c: char| c.is_ascii_punctuation()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_logseq_page;
+
+    #[test]
+    fn test_metadata_from_properties() {
+        let page = parse_logseq_page(
+            "description:: A guide\nimage:: ../assets/cover.png\nauthor:: Ada\n- Body",
+            "pages/guide.md",
+        ).unwrap();
+
+        let meta = page_metadata(&page, "pages/guide.html", Some("https://example.com"));
+        assert_eq!(meta.description.as_deref(), Some("A guide"));
+        assert_eq!(meta.image.as_deref(), Some("https://example.com/assets/cover.png"));
+        assert_eq!(meta.author.as_deref(), Some("Ada"));
+        assert_eq!(meta.url.as_deref(), Some("https://example.com/pages/guide.html"));
+        assert!(!meta.is_journal);
+    }
+
+    #[test]
+    fn test_metadata_fallbacks() {
+        let page = parse_logseq_page(
+            "- #draft\n- See [[Rust]] and **bold** ![shot](../assets/shot.png)",
+            "journals/2023_04_11.md",
+        ).unwrap();
+
+        let meta = page_metadata(&page, "journals/2023_04_11.html", None);
+        assert_eq!(meta.description.as_deref(), Some("See Rust and bold"));
+        assert_eq!(meta.image.as_deref(), Some("../assets/shot.png"));
+        assert_eq!(meta.date_published.as_deref(), Some("2023-04-11"));
+        assert!(meta.is_journal);
+    }
+
+    #[test]
+    fn test_json_ld() {
+        let page = parse_logseq_page("author:: Ada\n- Entry </script>", "journals/2023_04_11.md").unwrap();
+        let meta = page_metadata(&page, "journals/2023_04_11.html", None);
+
+        let json = json_ld(&meta);
+        assert!(json.contains("\"@type\":\"BlogPosting\""));
+        assert!(json.contains("\"author\":{\"@type\":\"Person\",\"name\":\"Ada\"}"));
+        assert!(!json.contains("</script>"));
+    }
+
+    #[test]
+    fn test_truncate() {
+        assert_eq!(truncate("short", 10), "short");
+        assert_eq!(truncate("one two three four", 12), "one two…");
+    }
+}
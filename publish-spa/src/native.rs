@@ -0,0 +1,486 @@
+//! Native file system access, used by the `publish-spa` binary instead of the Node.js helpers
+//!
+//! The input and output directories come from whoever runs the binary, so unlike the
+//! JavaScript API they may be absolute or contain `..`. Paths of the files read and
+//! written below them are still validated.
+
+use crate::converter::{self, SourceFile};
+use crate::diagnostics::{self, Diagnostic};
+use crate::errors::PublishError;
+use crate::fs::{GraphFiles, GraphSource, OutputSink, Staged, Staging, BACKUP_DIR, STAGING_DIR};
+use crate::{publish_to, PublishConfig, PublishStats};
+use std::fs;
+use std::future::Future;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::pin::pin;
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake};
+use std::thread::{self, Thread};
+use std::time::UNIX_EPOCH;
+use walkdir::{DirEntry, WalkDir};
+
+/// Directories that never contain graph pages
+const SKIPPED_DIRS: [&str; 2] = ["node_modules", ".git"];
+
+/// Read a graph, export it and write the site to `config.output_dir`
+pub fn publish(config: &PublishConfig) -> Result<PublishStats, PublishError> {
+    let source = NativeDir::new(&config.input_dir);
+    let mut sink = NativeDir::new(&config.output_dir);
+    block_on(publish_to(config, &source, &mut sink))
+}
+
+/// Render diagnostics for a terminal, with code frames from the files under `input_dir`
+pub fn render_diagnostics(input_dir: &str, diagnostics: &[Diagnostic]) -> String {
+    diagnostics.iter()
+        .map(|diagnostic| {
+            let source = fs::read_to_string(Path::new(input_dir).join(&diagnostic.file)).ok();
+            diagnostics::render(diagnostic, source.as_deref())
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Run a future to completion on the current thread
+///
+/// [`NativeDir`] and [`crate::fs::MemoryFs`] never suspend, so this is all the executor they need.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    struct ThreadWaker(Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    let mut future = pin!(future);
+    let waker = Arc::new(ThreadWaker(thread::current())).into();
+    let mut cx = Context::from_waker(&waker);
+    loop {
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
+
+/// A directory on the native file system
+///
+/// As an output sink, the files a build writes go to a `.publish-staging` directory
+/// inside it, and on commit each is renamed over its counterpart, which replaces it
+/// atomically. Files the build doesn't touch are left alone. The replaced and removed
+/// files are kept in `.publish-backup` until the commit finishes, and if it fails
+/// partway they are put back, so the output is either all old or all new.
+#[derive(Debug, Clone)]
+pub struct NativeDir {
+    root: PathBuf,
+    /// Changes of the build in progress, between `begin` and `commit`
+    staging: Option<Staging>,
+}
+
+impl NativeDir {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into(), staging: None }
+    }
+
+    fn staging_dir(&self) -> PathBuf {
+        self.root.join(STAGING_DIR)
+    }
+
+    /// Apply one staged change, returning whether the file it replaces or removes was
+    /// backed up
+    fn apply(&self, path: &str, change: Staged) -> Result<bool, PublishError> {
+        let target = self.root.join(path);
+        let backed_up = backup_file(&target, &self.root.join(BACKUP_DIR).join(path))?;
+        match change {
+            Staged::Written => rename_file(&self.staging_dir().join(path), &target)?,
+            Staged::Removed => remove_file(&target)?,
+        }
+        Ok(backed_up)
+    }
+
+    /// Undo a change made by [`apply`](Self::apply)
+    fn undo(&self, path: &str, backed_up: bool) -> Result<(), PublishError> {
+        let target = self.root.join(path);
+        if backed_up {
+            rename_file(&self.root.join(BACKUP_DIR).join(path), &target)
+        } else {
+            remove_file(&target)
+        }
+    }
+}
+
+impl GraphSource for NativeDir {
+    async fn read_graph_files(&self) -> Result<GraphFiles, PublishError> {
+        let root = self.root.as_path();
+        if !root.is_dir() {
+            return Err(PublishError::invalid_input(format!("Graph directory '{}' does not exist", root.display())));
+        }
+
+        let mut files = GraphFiles::default();
+        let walker = WalkDir::new(root).into_iter().filter_entry(|entry| !is_skipped_dir(entry));
+        for entry in walker {
+            let entry = entry
+                .map_err(|e| PublishError::io(format!("Failed to read directory '{}': {}", root.display(), e)))?;
+            if !entry.file_type().is_file() || !is_markdown(entry.path()) {
+                continue;
+            }
+
+            let path = relative_path(root, entry.path());
+            converter::validate_file_path(&path)?;
+
+            let content = match fs::read_to_string(entry.path()) {
+                Ok(content) => content,
+                Err(e) => {
+                    files.push_unreadable(path, e);
+                    continue;
+                }
+            };
+            let modified = entry
+                .metadata()
+                .ok()
+                .and_then(|metadata| metadata.modified().ok())
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map(|duration| duration.as_millis() as u64);
+
+            files.files.insert(path, SourceFile { content, modified });
+        }
+
+        Ok(files)
+    }
+}
+
+impl OutputSink for NativeDir {
+    async fn write_file(&mut self, path: &str, content: &[u8]) -> Result<(), PublishError> {
+        converter::validate_file_path(path)?;
+
+        let output_path = match &mut self.staging {
+            Some(staging) => {
+                staging.record(path, Staged::Written);
+                self.root.join(STAGING_DIR).join(path)
+            }
+            None => self.root.join(path),
+        };
+        write_file(&output_path, content)
+    }
+
+    async fn read_file(&self, path: &str) -> Result<Option<Vec<u8>>, PublishError> {
+        converter::validate_file_path(path)?;
+
+        let file_path = match self.staging.as_ref().and_then(|staging| staging.get(path)) {
+            Some(Staged::Removed) => return Ok(None),
+            Some(Staged::Written) => self.staging_dir().join(path),
+            None => self.root.join(path),
+        };
+        match fs::read(&file_path) {
+            Ok(content) => Ok(Some(content)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(PublishError::io(format!("Failed to read file '{}': {}", file_path.display(), e))),
+        }
+    }
+
+    async fn remove_file(&mut self, path: &str) -> Result<(), PublishError> {
+        converter::validate_file_path(path)?;
+
+        match &mut self.staging {
+            Some(staging) => {
+                if staging.record(path, Staged::Removed) == Some(Staged::Written) {
+                    remove_file(&self.root.join(STAGING_DIR).join(path))?;
+                }
+                Ok(())
+            }
+            None => remove_file(&self.root.join(path)),
+        }
+    }
+
+    async fn begin(&mut self) -> Result<(), PublishError> {
+        // Left over by a build that was killed before it could clean up
+        remove_dir(&self.staging_dir())?;
+        remove_dir(&self.root.join(BACKUP_DIR))?;
+        self.staging = Some(Staging::default());
+        Ok(())
+    }
+
+    async fn commit(&mut self) -> Result<(), PublishError> {
+        let Some(staging) = self.staging.take() else {
+            return Ok(());
+        };
+        let mut applied = Vec::new();
+        let result = staging.changes().try_for_each(|(path, change)| {
+            applied.push((path, self.apply(path, change)?));
+            Ok(())
+        });
+        if result.is_err() {
+            // Put the previous files back, newest change first; the commit error is the
+            // one worth reporting
+            for (path, backed_up) in applied.into_iter().rev() {
+                let _ = self.undo(path, backed_up);
+            }
+        }
+        let cleaned = remove_dir(&self.staging_dir()).and(remove_dir(&self.root.join(BACKUP_DIR)));
+        result.and(cleaned)
+    }
+
+    async fn rollback(&mut self) -> Result<(), PublishError> {
+        match self.staging.take() {
+            Some(_) => remove_dir(&self.staging_dir()),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Write a file, creating its directory as needed
+fn write_file(path: &Path, content: &[u8]) -> Result<(), PublishError> {
+    create_parent_dir(path)?;
+    fs::write(path, content)
+        .map_err(|e| PublishError::io(format!("Failed to write file '{}': {}", path.display(), e)))
+}
+
+fn create_parent_dir(path: &Path) -> Result<(), PublishError> {
+    match path.parent() {
+        Some(parent) => fs::create_dir_all(parent)
+            .map_err(|e| PublishError::io(format!("Failed to create directory '{}': {}", parent.display(), e))),
+        None => Ok(()),
+    }
+}
+
+/// Move a file over another, which is replaced atomically, creating its directory as needed
+fn rename_file(from: &Path, to: &Path) -> Result<(), PublishError> {
+    create_parent_dir(to)?;
+    fs::rename(from, to)
+        .map_err(|e| PublishError::io(format!("Failed to move '{}' to '{}': {}", from.display(), to.display(), e)))
+}
+
+/// Keep a copy of the file at `path` in `backup`, returning whether there was a file
+///
+/// The copy is a hard link where the file system allows it, so nothing is copied.
+fn backup_file(path: &Path, backup: &Path) -> Result<bool, PublishError> {
+    if !path.is_file() {
+        return Ok(false);
+    }
+    create_parent_dir(backup)?;
+    fs::hard_link(path, backup)
+        .or_else(|_| fs::copy(path, backup).map(|_| ()))
+        .map_err(|e| PublishError::io(format!("Failed to back up '{}': {}", path.display(), e)))?;
+    Ok(true)
+}
+
+/// Remove a file; a missing file is not an error
+fn remove_file(path: &Path) -> Result<(), PublishError> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != ErrorKind::NotFound => {
+            Err(PublishError::io(format!("Failed to remove file '{}': {}", path.display(), e)))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Remove a directory and everything in it; a missing directory is not an error
+fn remove_dir(path: &Path) -> Result<(), PublishError> {
+    match fs::remove_dir_all(path) {
+        Err(e) if e.kind() != ErrorKind::NotFound => {
+            Err(PublishError::io(format!("Failed to remove directory '{}': {}", path.display(), e)))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// `path` relative to `root`, with `/` separators on every platform
+fn relative_path(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+pub(crate) fn is_markdown(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "md" || ext == "markdown")
+}
+
+pub(crate) fn is_skipped_dir(entry: &DirEntry) -> bool {
+    entry.depth() > 0
+        && entry.file_type().is_dir()
+        && SKIPPED_DIRS.iter().any(|name| entry.file_name() == *name)
+}
+
+/// A fresh directory under the system temp dir, removed when dropped
+#[cfg(test)]
+pub(crate) struct TempDir(pub(crate) PathBuf);
+
+#[cfg(test)]
+impl TempDir {
+    pub(crate) fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("publish-spa-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        TempDir(path)
+    }
+
+    pub(crate) fn write(&self, path: &str, content: &str) {
+        let path = self.0.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    pub(crate) fn path(&self) -> String {
+        self.0.to_string_lossy().into_owned()
+    }
+}
+
+#[cfg(test)]
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_graph_files() {
+        let graph = TempDir::new("read");
+        graph.write("pages/Rust.md", "- Links to [[Cargo]]");
+        graph.write("journals/2024_01_01.markdown", "- Entry");
+        graph.write("logseq/config.edn", "{}");
+        graph.write("node_modules/pkg/README.md", "# Not a page");
+        fs::write(graph.0.join("pages/Binary.md"), [0xff, 0xfe]).unwrap();
+
+        let files = block_on(NativeDir::new(graph.path()).read_graph_files()).unwrap();
+        let mut paths: Vec<&str> = files.files.keys().map(String::as_str).collect();
+        paths.sort();
+        assert_eq!(paths, ["journals/2024_01_01.markdown", "pages/Rust.md"]);
+        assert!(files.files["pages/Rust.md"].modified.is_some());
+        assert_eq!(files.unreadable.len(), 1);
+        assert_eq!((files.unreadable[0].code.as_str(), files.unreadable[0].file.as_str()), ("unreadable-file", "pages/Binary.md"));
+
+        assert!(block_on(NativeDir::new(graph.0.join("missing")).read_graph_files()).is_err());
+    }
+
+    #[test]
+    fn test_publish_writes_site() {
+        let graph = TempDir::new("graph");
+        graph.write("pages/Rust.md", "- Links to [[Cargo]]");
+        let output = TempDir::new("output");
+
+        let mut config = PublishConfig::new(graph.path(), output.path());
+        config.precompress = true;
+        let stats = publish(&config).unwrap();
+
+        assert_eq!(stats.page_count, 1);
+        assert!(stats.compressed_files > 0);
+        assert!(output.0.join("index.html").is_file());
+        assert!(output.0.join("pages/Rust.html").is_file());
+        assert!(output.0.join("style.css.br").is_file());
+    }
+
+    /// Inode of every file in the output, which changes whenever a file is replaced
+    #[cfg(unix)]
+    fn inodes(dir: &TempDir) -> std::collections::BTreeMap<String, u64> {
+        use std::os::unix::fs::MetadataExt;
+
+        WalkDir::new(&dir.0)
+            .into_iter()
+            .map(Result::unwrap)
+            .filter(|entry| entry.file_type().is_file())
+            .map(|entry| {
+                let path = entry.path().strip_prefix(&dir.0).unwrap().to_string_lossy().into_owned();
+                (path, entry.metadata().unwrap().ino())
+            })
+            .collect()
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_incremental_publish_touches_only_changed_files() {
+        let graph = TempDir::new("incremental-graph");
+        for page in ["a", "b", "c"] {
+            graph.write(&format!("pages/{}.md", page), &format!("- Page {}", page));
+        }
+        let output = TempDir::new("incremental-output");
+        let mut config = PublishConfig::new(graph.path(), output.path());
+        config.incremental = true;
+        config.precompress = true;
+
+        publish(&config).unwrap();
+        let before = inodes(&output);
+        graph.write("pages/c.md", "- Page c, edited");
+        publish(&config).unwrap();
+        let after = inodes(&output);
+
+        assert_eq!(before.keys().collect::<Vec<_>>(), after.keys().collect::<Vec<_>>());
+        let replaced: Vec<&str> = after.iter()
+            .filter(|(path, inode)| before[*path] != **inode)
+            .map(|(path, _)| path.as_str())
+            .collect();
+        // Only the edited page and the cache are replaced; the other files keep their inodes
+        assert_eq!(replaced, [".publish-cache.json", "pages/c.html", "pages/c.html.br", "pages/c.html.gz"]);
+    }
+
+    #[test]
+    fn test_transactional_writes() {
+        let output = TempDir::new("transaction");
+        output.write("index.html", "old");
+        output.write("CNAME", "notes.example.com");
+        let read = |path: &str| fs::read_to_string(output.0.join(path)).ok();
+
+        let mut dir = NativeDir::new(&output.0);
+        block_on(dir.begin()).unwrap();
+        block_on(dir.write_file("index.html", b"new")).unwrap();
+        block_on(dir.write_file("pages/a.html", b"a")).unwrap();
+        assert_eq!(read("index.html").as_deref(), Some("old"));
+        block_on(dir.rollback()).unwrap();
+        assert_eq!(read("index.html").as_deref(), Some("old"));
+        assert_eq!(read("pages/a.html"), None);
+
+        block_on(dir.begin()).unwrap();
+        block_on(dir.write_file("index.html", b"new")).unwrap();
+        block_on(dir.write_file("pages/a.html", b"a")).unwrap();
+        block_on(dir.write_file("pages/b.html", b"b")).unwrap();
+        block_on(dir.remove_file("pages/b.html")).unwrap();
+        assert_eq!(block_on(dir.read_file("index.html")).unwrap(), Some(b"new".to_vec()));
+        assert_eq!(block_on(dir.read_file("pages/b.html")).unwrap(), None);
+        block_on(dir.commit()).unwrap();
+        assert_eq!(read("index.html").as_deref(), Some("new"));
+        assert_eq!(read("pages/a.html").as_deref(), Some("a"));
+        assert_eq!(read("pages/b.html"), None);
+        assert_eq!(read("CNAME").as_deref(), Some("notes.example.com"));
+
+        block_on(dir.begin()).unwrap();
+        block_on(dir.remove_file("pages/a.html")).unwrap();
+        assert_eq!(read("pages/a.html").as_deref(), Some("a"));
+        block_on(dir.commit()).unwrap();
+        assert_eq!(read("pages/a.html"), None);
+
+        assert!(!output.0.join(STAGING_DIR).exists());
+        assert!(!output.0.join(BACKUP_DIR).exists());
+    }
+
+    #[test]
+    fn test_failed_commit_restores_previous_files() {
+        let output = TempDir::new("failed-commit");
+        output.write("index.html", "old");
+        output.write("pages/old.html", "old page");
+        // A directory where the build writes a file, so moving that file into place fails
+        output.write("pages/c.html/keep", "");
+        let read = |path: &str| fs::read_to_string(output.0.join(path)).ok();
+
+        let mut dir = NativeDir::new(&output.0);
+        block_on(dir.begin()).unwrap();
+        block_on(dir.write_file("index.html", b"new")).unwrap();
+        block_on(dir.remove_file("pages/old.html")).unwrap();
+        block_on(dir.write_file("pages/b.html", b"b")).unwrap();
+        block_on(dir.write_file("pages/c.html", b"c")).unwrap();
+        assert!(block_on(dir.commit()).is_err());
+
+        assert_eq!(read("index.html").as_deref(), Some("old"));
+        assert_eq!(read("pages/old.html").as_deref(), Some("old page"));
+        assert_eq!(read("pages/b.html"), None);
+        assert!(output.0.join("pages/c.html/keep").is_file());
+        assert!(!output.0.join(STAGING_DIR).exists());
+        assert!(!output.0.join(BACKUP_DIR).exists());
+    }
+}
@@ -0,0 +1,106 @@
+//! Data parallelism for large graphs
+//!
+//! Native builds spread work over rayon's thread pool. WASM has no threads without a
+//! worker pool, so there the same calls run sequentially. Either way results come back
+//! in input order, so the output never depends on scheduling.
+
+/// Apply `f` to every item, in parallel on native builds, returning results in input order
+#[cfg(not(target_arch = "wasm32"))]
+pub fn map<T: Send, R: Send>(items: Vec<T>, f: impl Fn(T) -> R + Sync + Send) -> Vec<R> {
+    use rayon::prelude::*;
+    items.into_par_iter().map(f).collect()
+}
+
+/// Apply `f` to every item, returning results in input order
+#[cfg(target_arch = "wasm32")]
+pub fn map<T, R>(items: Vec<T>, f: impl Fn(T) -> R) -> Vec<R> {
+    items.into_iter().map(f).collect()
+}
+
+/// Like [`map`], calling `on_done` with the number of finished items after each one
+///
+/// Stops early and returns `None` once `on_done` returns false. `on_done` runs on the
+/// calling thread, so it needn't be `Send`; items already started still finish.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn map_with_progress<T: Send, R: Send>(
+    items: Vec<T>,
+    f: impl Fn(T) -> R + Sync + Send,
+    mut on_done: impl FnMut(usize) -> bool,
+) -> Option<Vec<R>> {
+    use rayon::prelude::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::mpsc;
+
+    let cancelled = AtomicBool::new(false);
+    let (sender, finished) = mpsc::channel();
+    std::thread::scope(|scope| {
+        let worker = scope.spawn(|| {
+            items.into_par_iter()
+                .map_with(sender, |sender, item| {
+                    if cancelled.load(Ordering::Relaxed) {
+                        return None;
+                    }
+                    let result = f(item);
+                    let _ = sender.send(());
+                    Some(result)
+                })
+                .collect::<Option<Vec<R>>>()
+        });
+        // Ends once the workers are done and have dropped their senders
+        for done in finished.iter().enumerate().map(|(i, ())| i + 1) {
+            if !on_done(done) {
+                cancelled.store(true, Ordering::Relaxed);
+                break;
+            }
+        }
+        let results = worker.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic));
+        results.filter(|_| !cancelled.load(Ordering::Relaxed))
+    })
+}
+
+/// Like [`map`], calling `on_done` with the number of finished items after each one and
+/// returning `None` as soon as it returns false
+#[cfg(target_arch = "wasm32")]
+pub fn map_with_progress<T, R>(
+    items: Vec<T>,
+    f: impl Fn(T) -> R,
+    mut on_done: impl FnMut(usize) -> bool,
+) -> Option<Vec<R>> {
+    items.into_iter()
+        .enumerate()
+        .map(|(i, item)| {
+            let result = f(item);
+            on_done(i + 1).then_some(result)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_map_keeps_order() {
+        let items: Vec<usize> = (0..1000).collect();
+        let squares = map(items, |n| n * n);
+        assert!(squares.iter().enumerate().all(|(n, square)| *square == n * n));
+    }
+
+    #[test]
+    fn test_map_with_progress() {
+        let mut reported = Vec::new();
+        let squares = map_with_progress((0..100).collect(), |n: usize| n * n, |done| {
+            reported.push(done);
+            true
+        });
+        assert_eq!(squares, Some((0..100).map(|n| n * n).collect()));
+        assert_eq!(reported, (1..=100).collect::<Vec<_>>());
+
+        let mut reported = 0;
+        let cancelled = map_with_progress((0..1000).collect(), |n: usize| n, |done| {
+            reported = done;
+            done < 10
+        });
+        assert_eq!((cancelled, reported), (None, 10));
+    }
+}
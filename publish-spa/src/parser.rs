@@ -1,16 +1,23 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use indexmap::IndexMap;
 use regex::Regex;
-use crate::errors::PublishError;
+use std::sync::LazyLock;
+use crate::diagnostics::{Diagnostic, Span};
+
+/// Property names and values, in the order they appear in the source
+pub type Properties = IndexMap<String, String>;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Page {
     pub path: String,
     pub title: String,
-    pub properties: HashMap<String, String>,
+    pub properties: Properties,
     pub blocks: Vec<Block>,
     pub tags: Vec<String>,
     pub links: Vec<String>,
+    /// Source file modification time in milliseconds since the Unix epoch, when known
+    #[serde(default)]
+    pub modified: Option<u64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -18,19 +25,47 @@ pub struct Block {
     pub id: String,
     pub content: String,
     pub children: Vec<Block>,
-    pub properties: HashMap<String, String>,
+    pub properties: Properties,
     pub level: usize,
+    /// Source lines of the block and its children
+    #[serde(default)]
+    pub lines: LineRange,
+}
+
+/// 1-based, inclusive range of source lines; zero when unknown
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LineRange {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Page {
+    /// Alternative names declared through the `alias` page property
+    pub fn aliases(&self) -> Vec<String> {
+        self.properties
+            .get("alias")
+            .map(|value| {
+                value
+                    .split(',')
+                    .map(|alias| alias.trim().trim_start_matches("[[").trim_end_matches("]]").trim())
+                    .filter(|alias| !alias.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
 }
 
 /// Parse a Logseq markdown page
-pub fn parse_logseq_page(content: &str, path: &str) -> Result<Page, PublishError> {
+pub fn parse_logseq_page(content: &str, path: &str) -> Result<Page, Diagnostic> {
     let mut page = Page {
         path: path.to_string(),
         title: extract_title(path),
-        properties: HashMap::new(),
+        properties: Properties::new(),
         blocks: Vec::new(),
         tags: Vec::new(),
         links: Vec::new(),
+        modified: None,
     };
 
     let lines: Vec<&str> = content.lines().collect();
@@ -39,15 +74,20 @@ pub fn parse_logseq_page(content: &str, path: &str) -> Result<Page, PublishError
     // Parse frontmatter properties
     if lines.first() == Some(&"---") {
         i = parse_properties(&lines[1..], &mut page.properties)
-            .map_err(|e| PublishError::parse(path, e))?;
+            .map_err(|e| Diagnostic::error("unclosed-frontmatter", path, e).with_span(Span::line(content, 1)))?;
         i += 2; // Skip the opening and closing ---
     }
 
-    // Parse blocks
-    if i < lines.len() {
-        page.blocks = parse_blocks(&lines[i..], 0)
-            .map_err(|e| PublishError::parse(path, e))?;
-    }
+    // Parse Logseq-style `key:: value` page properties preceding the first block
+    i += parse_page_properties(&lines[i..], &mut page.properties);
+
+    // Parse blocks, numbering lines from 1
+    let numbered: Vec<(usize, &str)> = lines.iter()
+        .enumerate()
+        .skip(i)
+        .map(|(index, line)| (index + 1, *line))
+        .collect();
+    page.blocks = parse_blocks(&numbered, 0);
 
     // Extract tags and links from blocks
     extract_tags_and_links(&page.blocks, &mut page.tags, &mut page.links);
@@ -56,15 +96,15 @@ pub fn parse_logseq_page(content: &str, path: &str) -> Result<Page, PublishError
 }
 
 fn extract_title(path: &str) -> String {
-    path.split('/')
-        .last()
+    path.rsplit('/')
+        .next()
         .unwrap_or(path)
         .trim_end_matches(".md")
         .trim_end_matches(".markdown")
         .to_string()
 }
 
-fn parse_properties(lines: &[&str], properties: &mut HashMap<String, String>) -> Result<usize, String> {
+fn parse_properties(lines: &[&str], properties: &mut Properties) -> Result<usize, String> {
     for (i, line) in lines.iter().enumerate() {
         if *line == "---" {
             return Ok(i);
@@ -78,15 +118,51 @@ fn parse_properties(lines: &[&str], properties: &mut HashMap<String, String>) ->
         }
     }
 
-    Err("Unclosed frontmatter".to_string())
+    Err("Frontmatter opened here is never closed with `---`".to_string())
 }
 
-fn parse_blocks(lines: &[&str], base_level: usize) -> Result<Vec<Block>, String> {
+/// Parse leading `key:: value` lines into page properties, returning how many lines were consumed
+fn parse_page_properties(lines: &[&str], properties: &mut Properties) -> usize {
+    let mut consumed = 0;
+
+    for line in lines {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            consumed += 1;
+            continue;
+        }
+
+        match parse_property_line(trimmed) {
+            Some((key, value)) => {
+                properties.insert(key, value);
+                consumed += 1;
+            }
+            None => break,
+        }
+    }
+
+    consumed
+}
+
+/// Parse a single `key:: value` property line
+fn parse_property_line(line: &str) -> Option<(String, String)> {
+    let (key, value) = line.split_once("::")?;
+    let key = key.trim();
+
+    if key.is_empty() || !key.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_') {
+        return None;
+    }
+
+    Some((key.to_lowercase(), value.trim().to_string()))
+}
+
+/// Parse blocks from lines paired with their line numbers
+fn parse_blocks(lines: &[(usize, &str)], base_level: usize) -> Vec<Block> {
     let mut blocks = Vec::new();
     let mut i = 0;
 
     while i < lines.len() {
-        let line = lines[i];
+        let (number, line) = lines[i];
 
         // Skip empty lines
         if line.trim().is_empty() {
@@ -105,8 +181,9 @@ fn parse_blocks(lines: &[&str], base_level: usize) -> Result<Vec<Block>, String>
             id: format!("block-{}-{}", base_level, blocks.len()),
             content: content.to_string(),
             children: Vec::new(),
-            properties: HashMap::new(),
+            properties: Properties::new(),
             level,
+            lines: LineRange { start: number, end: number },
         };
 
         // Look ahead for child blocks
@@ -114,12 +191,12 @@ fn parse_blocks(lines: &[&str], base_level: usize) -> Result<Vec<Block>, String>
         let mut j = i + 1;
         while j < lines.len() {
             let next_line = lines[j];
-            if next_line.trim().is_empty() {
+            if next_line.1.trim().is_empty() {
                 j += 1;
                 continue;
             }
 
-            let next_indent = count_indent(next_line);
+            let next_indent = count_indent(next_line.1);
             if next_indent > indent {
                 child_lines.push(next_line);
                 j += 1;
@@ -128,8 +205,9 @@ fn parse_blocks(lines: &[&str], base_level: usize) -> Result<Vec<Block>, String>
             }
         }
 
-        if !child_lines.is_empty() {
-            block.children = parse_blocks(&child_lines, level + 1)?;
+        if let Some(&(last, _)) = child_lines.last() {
+            block.children = parse_blocks(&child_lines, level + 1);
+            block.lines.end = last;
             i = j;
         } else {
             i += 1;
@@ -138,7 +216,7 @@ fn parse_blocks(lines: &[&str], base_level: usize) -> Result<Vec<Block>, String>
         blocks.push(block);
     }
 
-    Ok(blocks)
+    blocks
 }
 
 fn count_indent(line: &str) -> usize {
@@ -172,24 +250,19 @@ fn extract_block_content(line: &str) -> &str {
     let trimmed = line.trim();
 
     // Handle different bullet styles
-    if trimmed.starts_with("- ") {
-        &trimmed[2..]
-    } else if trimmed.starts_with("* ") {
-        &trimmed[2..]
-    } else if trimmed.starts_with("+ ") {
-        &trimmed[2..]
-    } else {
-        trimmed
-    }
+    ["- ", "* ", "+ "]
+        .iter()
+        .find_map(|bullet| trimmed.strip_prefix(bullet))
+        .unwrap_or(trimmed)
 }
 
-fn extract_tags_and_links(blocks: &[Block], tags: &mut Vec<String>, links: &mut Vec<String>) {
-    let tag_regex = Regex::new(r"#(\w+)").unwrap();
-    let link_regex = Regex::new(r"\[\[([^\]]+)\]\]").unwrap();
+static TAG_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"#(\w+)").unwrap());
+static LINK_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\[\[([^\]]+)\]\]").unwrap());
 
+fn extract_tags_and_links(blocks: &[Block], tags: &mut Vec<String>, links: &mut Vec<String>) {
     for block in blocks {
         // Extract tags
-        for cap in tag_regex.captures_iter(&block.content) {
+        for cap in TAG_REGEX.captures_iter(&block.content) {
             let tag = cap[1].to_string();
             if !tags.contains(&tag) {
                 tags.push(tag);
@@ -197,7 +270,7 @@ fn extract_tags_and_links(blocks: &[Block], tags: &mut Vec<String>, links: &mut
         }
 
         // Extract wiki-style links
-        for cap in link_regex.captures_iter(&block.content) {
+        for cap in LINK_REGEX.captures_iter(&block.content) {
             let link = cap[1].to_string();
             if !links.contains(&link) {
                 links.push(link);
@@ -241,6 +314,37 @@ title: Test Page
         assert_eq!(page.blocks[0].children[0].children.len(), 1);
     }
 
+    #[test]
+    fn test_parse_page_properties_and_aliases() {
+        let content = "alias:: [[Rust Lang]], rustlang\ntype:: language\n\n- First block";
+
+        let page = parse_logseq_page(content, "rust.md").unwrap();
+        assert_eq!(page.properties.get("type"), Some(&"language".to_string()));
+        assert_eq!(page.aliases(), vec!["Rust Lang", "rustlang"]);
+        assert_eq!(page.properties.keys().collect::<Vec<_>>(), ["alias", "type"]);
+        assert_eq!(page.blocks.len(), 1);
+        assert_eq!(page.blocks[0].content, "First block");
+    }
+
+    #[test]
+    fn test_block_line_ranges() {
+        let content = "type:: note\n\n- First\n  - Child\n\n    - Grandchild\n- Second";
+
+        let page = parse_logseq_page(content, "lines.md").unwrap();
+        assert_eq!(page.blocks[0].lines, LineRange { start: 3, end: 6 });
+        assert_eq!(page.blocks[0].children[0].lines, LineRange { start: 4, end: 6 });
+        assert_eq!(page.blocks[0].children[0].children[0].lines, LineRange { start: 6, end: 6 });
+        assert_eq!(page.blocks[1].lines, LineRange { start: 7, end: 7 });
+    }
+
+    #[test]
+    fn test_unclosed_frontmatter_diagnostic() {
+        let error = parse_logseq_page("---\ntitle: Draft\n- Block", "draft.md").unwrap_err();
+        assert_eq!(error.code, "unclosed-frontmatter");
+        assert_eq!(error.file, "draft.md");
+        assert_eq!(error.span, Some(Span { start_line: 1, start_column: 1, end_line: 1, end_column: 4 }));
+    }
+
     #[test]
     fn test_extract_title() {
         assert_eq!(extract_title("test.md"), "test");
@@ -0,0 +1,190 @@
+//! Output post-processing: minification and precompressed `.gz`/`.br` siblings
+
+use logseq_minify as minify;
+use crate::parallel;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::collections::HashMap;
+use std::io::Write;
+
+/// Extensions of outputs that get precompressed siblings
+const TEXT_EXTENSIONS: [&str; 7] = ["html", "css", "js", "xml", "txt", "json", "svg"];
+
+/// Brotli quality (0-11); outputs are compressed once and served many times
+const BROTLI_QUALITY: u32 = 11;
+const BROTLI_WINDOW: u32 = 22;
+
+/// What post-processing did to the output files
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PostProcessStats {
+    /// Total size of the text outputs after minification
+    pub output_bytes: usize,
+    pub minify_saved_bytes: usize,
+    /// Number of text outputs with `.gz` and `.br` siblings
+    pub compressed_files: usize,
+    pub gzip_bytes: usize,
+    pub brotli_bytes: usize,
+}
+
+/// Minify HTML, CSS and JavaScript outputs in place when `minify` is set and,
+/// when `precompress` is set, return gzip and brotli siblings of every text output
+/// keyed by their paths (`page.html.gz`, `page.html.br`).
+pub fn post_process(
+    files: &mut HashMap<String, String>,
+    minify: bool,
+    precompress: bool,
+) -> Result<(HashMap<String, Vec<u8>>, PostProcessStats), String> {
+    let mut stats = PostProcessStats::default();
+    let mut compressed = HashMap::new();
+
+    // Process files in parallel, then merge in path order so errors are reported deterministically
+    let mut entries: Vec<(String, String)> = files.drain().collect();
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+    let processed = parallel::map(entries, |(path, content)| process_file(path, content, minify, precompress));
+
+    for processed in processed {
+        let processed = processed?;
+        stats.minify_saved_bytes += processed.minify_saved_bytes;
+        stats.output_bytes += processed.content.len();
+
+        if let Some((gzip, brotli)) = processed.compressed {
+            stats.compressed_files += 1;
+            stats.gzip_bytes += gzip.len();
+            stats.brotli_bytes += brotli.len();
+            compressed.insert(format!("{}.gz", processed.path), gzip);
+            compressed.insert(format!("{}.br", processed.path), brotli);
+        }
+        files.insert(processed.path, processed.content);
+    }
+
+    Ok((compressed, stats))
+}
+
+/// One output file after post-processing
+struct ProcessedFile {
+    path: String,
+    content: String,
+    minify_saved_bytes: usize,
+    /// Gzip and brotli encodings of `content`
+    compressed: Option<(Vec<u8>, Vec<u8>)>,
+}
+
+fn process_file(path: String, mut content: String, minify: bool, precompress: bool) -> Result<ProcessedFile, String> {
+    let extension = extension(&path);
+    let mut minify_saved_bytes = 0;
+
+    if minify {
+        let minified = match extension.as_str() {
+            "html" => Some(minify::minify_html(&content)),
+            "css" => Some(minify::minify_css(&content)),
+            "js" => Some(minify::minify_js(&content)),
+            _ => None,
+        };
+        if let Some(minified) = minified {
+            minify_saved_bytes = content.len().saturating_sub(minified.len());
+            content = minified;
+        }
+    }
+
+    let compressed = if precompress && TEXT_EXTENSIONS.contains(&extension.as_str()) {
+        let gzip = gzip(content.as_bytes())
+            .map_err(|e| format!("Failed to gzip {}: {}", path, e))?;
+        let brotli = brotli(content.as_bytes())
+            .map_err(|e| format!("Failed to brotli-compress {}: {}", path, e))?;
+        Some((gzip, brotli))
+    } else {
+        None
+    };
+
+    Ok(ProcessedFile { path, content, minify_saved_bytes, compressed })
+}
+
+/// Paths of the `.gz` and `.br` siblings `post_process` writes for `path` when precompressing
+pub fn precompressed_paths(path: &str) -> Vec<String> {
+    if TEXT_EXTENSIONS.contains(&extension(path).as_str()) {
+        vec![format!("{}.gz", path), format!("{}.br", path)]
+    } else {
+        Vec::new()
+    }
+}
+
+fn extension(path: &str) -> String {
+    path.rsplit_once('.').map_or("", |(_, ext)| ext).to_ascii_lowercase()
+}
+
+/// Gzip at maximum compression; the header carries no timestamp, so output is reproducible
+fn gzip(data: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+    encoder.write_all(data)?;
+    encoder.finish()
+}
+
+fn brotli(data: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut output = Vec::new();
+    {
+        let mut writer = brotli::CompressorWriter::new(&mut output, 4096, BROTLI_QUALITY, BROTLI_WINDOW);
+        writer.write_all(data)?;
+        writer.flush()?;
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    fn outputs() -> HashMap<String, String> {
+        let page = format!("<div>\n  <p>{}</p>\n</div>\n", "Repeated text. ".repeat(50));
+        HashMap::from([
+            ("index.html".to_string(), page),
+            ("style.css".to_string(), "body {\n  margin: 0;\n}\n".to_string()),
+            ("redirects.json".to_string(), "{\n  \"a.html\": \"b.html\"\n}".to_string()),
+        ])
+    }
+
+    #[test]
+    fn test_minify_without_compression() {
+        let mut files = outputs();
+        let (compressed, stats) = post_process(&mut files, true, false).unwrap();
+
+        assert!(compressed.is_empty());
+        assert!(files["index.html"].starts_with("<div><p>Repeated text."));
+        assert_eq!(files["style.css"], "body{margin:0}");
+        assert_eq!(files["redirects.json"], "{\n  \"a.html\": \"b.html\"\n}");
+        assert_eq!(stats.output_bytes, files.values().map(String::len).sum::<usize>());
+        assert!(stats.minify_saved_bytes > 0);
+    }
+
+    #[test]
+    fn test_precompressed_siblings() {
+        let mut files = outputs();
+        let (compressed, stats) = post_process(&mut files, false, true).unwrap();
+
+        assert_eq!(stats.compressed_files, 3);
+        assert_eq!(compressed.len(), 6);
+        for path in files.keys() {
+            assert!(precompressed_paths(path).iter().all(|sibling| compressed.contains_key(sibling)));
+        }
+        assert!(precompressed_paths("photo.png").is_empty());
+        assert_eq!(stats.minify_saved_bytes, 0);
+
+        let mut decoded = String::new();
+        GzDecoder::new(compressed["index.html.gz"].as_slice())
+            .read_to_string(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, files["index.html"]);
+
+        let mut decoded = String::new();
+        brotli::Decompressor::new(compressed["index.html.br"].as_slice(), 4096)
+            .read_to_string(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, files["index.html"]);
+        assert!(compressed["index.html.br"].len() < files["index.html"].len());
+
+        // Compression is reproducible
+        let (again, _) = post_process(&mut outputs(), false, true).unwrap();
+        assert_eq!(again, compressed);
+    }
+}
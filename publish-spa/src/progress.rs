@@ -0,0 +1,120 @@
+//! Progress reporting and cancellation of a publish
+
+use crate::errors::PublishError;
+use js_sys::{Function, Reflect};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::JsValue;
+
+/// A step of the publish pipeline, in the order they run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Phase {
+    /// Reading source files
+    Read,
+    /// Parsing pages
+    Parse,
+    /// Building the link graph
+    Graph,
+    /// Rendering output files
+    Render,
+    /// Writing output files
+    Write,
+}
+
+/// `done` of `total` items of a phase are finished
+///
+/// Read reports once when it completes. Parse, Graph and Render report once per page
+/// and Write once per file, after also reporting `done: 0` when it starts, so the
+/// number of files is known up front.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProgressEvent {
+    pub phase: Phase,
+    pub done: usize,
+    pub total: usize,
+}
+
+/// Receives the progress of a publish and decides whether it goes on
+pub trait Progress {
+    fn report(&mut self, event: ProgressEvent);
+
+    /// Checked after every event; a cancelled publish fails with
+    /// [`PublishError::Cancelled`] and leaves the output as it was
+    ///
+    /// In WASM, parsing, graph building and rendering run without returning to the
+    /// JavaScript event loop, so an `AbortSignal` can't become aborted during them:
+    /// there cancellation takes effect at the next phase boundary or while writing.
+    fn is_cancelled(&self) -> bool {
+        false
+    }
+}
+
+/// No progress reporting
+impl Progress for () {
+    fn report(&mut self, _event: ProgressEvent) {}
+}
+
+/// Report that `phase` finished `done` of `total` items, failing if the publish was cancelled
+pub(crate) fn step(progress: &mut dyn Progress, phase: Phase, done: usize, total: usize) -> Result<(), PublishError> {
+    progress.report(ProgressEvent { phase, done, total });
+    if progress.is_cancelled() {
+        return Err(PublishError::Cancelled);
+    }
+    Ok(())
+}
+
+/// Progress passed to a JavaScript callback, cancelled through an `AbortSignal`
+pub struct JsProgress {
+    callback: Option<Function>,
+    signal: Option<JsValue>,
+}
+
+impl JsProgress {
+    pub fn new(callback: Option<Function>, signal: Option<JsValue>) -> Self {
+        Self { callback, signal }
+    }
+}
+
+impl Progress for JsProgress {
+    fn report(&mut self, event: ProgressEvent) {
+        let Some(callback) = &self.callback else {
+            return;
+        };
+        // A failing callback shouldn't fail the publish
+        if let Ok(event) = serde_wasm_bindgen::to_value(&event) {
+            let _ = callback.call1(&JsValue::NULL, &event);
+        }
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.signal.as_ref()
+            .and_then(|signal| Reflect::get(signal, &JsValue::from_str("aborted")).ok())
+            .is_some_and(|aborted| aborted.is_truthy())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct CancelAfter(Vec<ProgressEvent>, usize);
+
+    impl Progress for CancelAfter {
+        fn report(&mut self, event: ProgressEvent) {
+            self.0.push(event);
+        }
+
+        fn is_cancelled(&self) -> bool {
+            self.0.len() >= self.1
+        }
+    }
+
+    #[test]
+    fn test_step() {
+        let mut progress = CancelAfter(Vec::new(), 2);
+        assert!(step(&mut progress, Phase::Read, 3, 3).is_ok());
+        assert!(matches!(step(&mut progress, Phase::Parse, 3, 3), Err(PublishError::Cancelled)));
+        assert_eq!(progress.0[1], ProgressEvent { phase: Phase::Parse, done: 3, total: 3 });
+
+        assert_eq!(serde_json::to_string(&progress.0[0]).unwrap(), r#"{"phase":"read","done":3,"total":3}"#);
+    }
+}
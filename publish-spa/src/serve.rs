@@ -0,0 +1,355 @@
+//! Local development server behind `publish-spa serve`
+//!
+//! The site is published to memory and served over HTTP. A watcher polls the graph
+//! directory and republishes incrementally after every change, then tells open pages
+//! to reload through a server-sent events stream injected into every served HTML page.
+
+use crate::diagnostics::ErrorPolicy;
+use crate::errors::PublishError;
+use crate::fs::MemoryFs;
+use crate::native::{self, block_on, NativeDir};
+use crate::slug::percent_decode;
+use crate::urls::site_base_path;
+use crate::{cache, publish_to, PublishConfig, PublishStats};
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread;
+use std::time::{Duration, SystemTime};
+use walkdir::WalkDir;
+
+/// Event stream telling open pages to reload
+const RELOAD_PATH: &str = "/__reload";
+
+/// Injected before `</body>` of every served HTML page
+const RELOAD_SCRIPT: &str = "<script>new EventSource(\"/__reload\").onmessage = () => location.reload();</script>";
+
+/// How long a reload event may take to reach a page before the page is dropped
+const RELOAD_WRITE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// How often the graph directory is checked for changes
+const POLL_INTERVAL: Duration = Duration::from_millis(300);
+
+/// Serves the published site from memory
+pub struct DevServer {
+    config: PublishConfig,
+    source: NativeDir,
+    /// Site path prefix, see [`site_base_path`]
+    base_path: String,
+    site: RwLock<MemoryFs>,
+    /// Open reload streams
+    clients: Mutex<Vec<TcpStream>>,
+}
+
+/// Answer to a request
+#[derive(Debug)]
+pub struct Response {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: Vec<u8>,
+}
+
+impl DevServer {
+    /// Create a server for the graph at `config.input_dir`; the site is empty until the first [`DevServer::rebuild`]
+    pub fn new(config: &PublishConfig) -> Self {
+        let mut config = config.clone();
+        // The build cache is kept in memory next to the site, so every rebuild is incremental
+        config.incremental = true;
+        config.precompress = false;
+        // Health problems and diagnostics are for CI to fail on; while writing, keep
+        // serving the latest site
+        config.fail_on.clear();
+        config.error_policy = ErrorPolicy::Warn;
+
+        Self {
+            source: NativeDir::new(&config.input_dir),
+            base_path: site_base_path(config.base_path.as_deref(), config.base_url.as_deref()),
+            config,
+            site: RwLock::default(),
+            clients: Mutex::default(),
+        }
+    }
+
+    /// Republish the graph and reload open pages; on failure the previous site is still served
+    pub fn rebuild(&self) -> Result<PublishStats, PublishError> {
+        let mut site = self.site.read().unwrap().clone();
+        let stats = block_on(publish_to(&self.config, &self.source, &mut site))?;
+        *self.site.write().unwrap() = site;
+
+        // Write outside the lock, so a stalled page doesn't hold up pages connecting meanwhile
+        let mut clients = std::mem::take(&mut *self.clients.lock().unwrap());
+        clients.retain_mut(|client| client.write_all(b"data: reload\n\n").and_then(|_| client.flush()).is_ok());
+        self.clients.lock().unwrap().append(&mut clients);
+        Ok(stats)
+    }
+
+    /// The file served for a request path such as `/`, `/pages/rust/` or `/pages/Rust%20Lang.html`
+    pub fn response(&self, request_path: &str) -> Response {
+        let path = request_path.split(['?', '#']).next().unwrap_or_default();
+        let path = percent_decode(path);
+        let site = self.site.read().unwrap();
+
+        let relative = path
+            .strip_prefix(self.base_path.trim_end_matches('/'))
+            .filter(|path| path.is_empty() || path.starts_with('/'))
+            .map(|path| path.trim_start_matches('/'));
+        let candidates = match relative {
+            Some(path) if path.is_empty() || path.ends_with('/') => vec![format!("{}index.html", path)],
+            Some(path) => vec![path.to_string(), format!("{}.html", path), format!("{}/index.html", path)],
+            None => Vec::new(),
+        };
+        let found = candidates
+            .iter()
+            .filter(|path| path.as_str() != cache::CACHE_PATH)
+            .find_map(|path| site.get(path).map(|content| (path, content)));
+
+        match found {
+            Some((path, content)) => {
+                let content_type = content_type(path);
+                let body = if content_type.starts_with("text/html") {
+                    with_reload_script(&String::from_utf8_lossy(content)).into_bytes()
+                } else {
+                    content.to_vec()
+                };
+                Response { status: 200, content_type, body }
+            }
+            // Reloads too, so a page shows up as soon as it is created
+            None => Response {
+                status: 404,
+                content_type: "text/html; charset=utf-8",
+                body: with_reload_script("<!DOCTYPE html>\n<html><body><h1>404 Not Found</h1></body></html>").into_bytes(),
+            },
+        }
+    }
+
+    /// Answer one HTTP connection; reload streams are kept open until the page goes away
+    pub fn handle(&self, mut stream: TcpStream) -> io::Result<()> {
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line)?;
+        let mut header = String::new();
+        while reader.read_line(&mut header)? > 0 && !header.trim().is_empty() {
+            header.clear();
+        }
+
+        let mut parts = request_line.split_whitespace();
+        let method = parts.next().unwrap_or_default();
+        let path = parts.next().unwrap_or_default();
+
+        if method != "GET" && method != "HEAD" {
+            return stream.write_all(b"HTTP/1.1 405 Method Not Allowed\r\nAllow: GET, HEAD\r\nContent-Length: 0\r\nConnection: close\r\n\r\n");
+        }
+        if path == RELOAD_PATH {
+            // Register under the lock, so a rebuild finishing right after the headers still reaches the page
+            let mut clients = self.clients.lock().unwrap();
+            // A page that stops reading is dropped at the next reload instead of blocking it
+            stream.set_write_timeout(Some(RELOAD_WRITE_TIMEOUT))?;
+            stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\n\r\n")?;
+            stream.flush()?;
+            clients.push(stream);
+            return Ok(());
+        }
+
+        let response = self.response(path);
+        write!(
+            stream,
+            "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-cache\r\nConnection: close\r\n\r\n",
+            response.status,
+            if response.status == 200 { "OK" } else { "Not Found" },
+            response.content_type,
+            response.body.len()
+        )?;
+        if method == "GET" {
+            stream.write_all(&response.body)?;
+        }
+        stream.flush()
+    }
+
+    fn report_diagnostics(&self, stats: &PublishStats) {
+        if !stats.diagnostics.is_empty() {
+            eprintln!("{}", native::render_diagnostics(&self.config.input_dir, &stats.diagnostics));
+        }
+    }
+
+    /// Republish whenever a page in the graph directory is added, changed or removed
+    fn watch(&self) {
+        let root = PathBuf::from(&self.config.input_dir);
+        let mut last = snapshot(&root);
+        loop {
+            thread::sleep(POLL_INTERVAL);
+            let current = snapshot(&root);
+            if current == last {
+                continue;
+            }
+            last = current;
+
+            match self.rebuild() {
+                Ok(stats) => {
+                    self.report_diagnostics(&stats);
+                    println!("Rebuilt {} pages ({} unchanged)", stats.page_count, stats.reused_pages);
+                }
+                Err(e) => eprintln!("Error: {}", e),
+            }
+        }
+    }
+}
+
+/// Publish the graph, serve it at `address` and republish on changes until the process is stopped
+pub fn serve(config: &PublishConfig, address: &str) -> Result<(), PublishError> {
+    let listener = TcpListener::bind(address)
+        .map_err(|e| PublishError::io(format!("Failed to listen on {}: {}", address, e)))?;
+    let server = Arc::new(DevServer::new(config));
+    let stats = server.rebuild()?;
+    server.report_diagnostics(&stats);
+
+    let address = listener.local_addr().map_or_else(|_| address.to_string(), |address| address.to_string());
+    println!("Published {} pages ({} blocks, {} links)", stats.page_count, stats.total_blocks, stats.total_links);
+    println!("Serving {} at http://{}{} (Ctrl+C to stop)", config.input_dir, address, server.base_path);
+
+    let watcher = Arc::clone(&server);
+    thread::spawn(move || watcher.watch());
+
+    for stream in listener.incoming().flatten() {
+        let server = Arc::clone(&server);
+        thread::spawn(move || {
+            // Dropped connections are the browser's business
+            let _ = server.handle(stream);
+        });
+    }
+    Ok(())
+}
+
+/// Modification time and size of every page in the graph
+fn snapshot(root: &Path) -> BTreeMap<PathBuf, (Option<SystemTime>, u64)> {
+    WalkDir::new(root)
+        .into_iter()
+        .filter_entry(|entry| !native::is_skipped_dir(entry))
+        .flatten()
+        .filter(|entry| entry.file_type().is_file() && native::is_markdown(entry.path()))
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            Some((entry.into_path(), (metadata.modified().ok(), metadata.len())))
+        })
+        .collect()
+}
+
+fn with_reload_script(html: &str) -> String {
+    match html.rfind("</body>") {
+        Some(end) => format!("{}{}{}", &html[..end], RELOAD_SCRIPT, &html[end..]),
+        None => format!("{}{}", html, RELOAD_SCRIPT),
+    }
+}
+
+fn content_type(path: &str) -> &'static str {
+    match path.rsplit_once('.').map_or("", |(_, extension)| extension) {
+        "html" => "text/html; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "js" => "text/javascript; charset=utf-8",
+        "json" => "application/json",
+        "xml" => "application/xml",
+        "txt" => "text/plain; charset=utf-8",
+        "svg" => "image/svg+xml",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::native::TempDir;
+    use std::io::Read;
+
+    fn config(graph: &TempDir) -> PublishConfig {
+        PublishConfig::new(graph.path(), String::new())
+    }
+
+    #[test]
+    fn test_responses() {
+        let graph = TempDir::new("serve-responses");
+        graph.write("pages/Rust Lang.md", "- Links to [[Cargo]]");
+        let server = DevServer::new(&config(&graph));
+        server.rebuild().unwrap();
+
+        let index = server.response("/");
+        assert_eq!(index.status, 200);
+        assert!(index.content_type.starts_with("text/html"));
+        assert!(String::from_utf8(index.body).unwrap().contains(RELOAD_SCRIPT));
+
+        assert_eq!(server.response("/pages/Rust%20Lang.html?x=1").status, 200);
+        assert_eq!(server.response("/pages/Rust%20Lang").status, 200);
+        assert_eq!(server.response("/style.css").content_type, "text/css; charset=utf-8");
+        assert!(!server.response("/style.css").body.ends_with(RELOAD_SCRIPT.as_bytes()));
+        assert_eq!(server.response("/pages/Missing.html").status, 404);
+        assert_eq!(server.response("/.publish-cache.json").status, 404);
+    }
+
+    #[test]
+    fn test_base_path() {
+        let graph = TempDir::new("serve-base-path");
+        graph.write("pages/Rust.md", "- Text");
+        let mut config = config(&graph);
+        config.base_path = Some("project".to_string());
+        let server = DevServer::new(&config);
+        server.rebuild().unwrap();
+
+        assert_eq!(server.response("/project/").status, 200);
+        assert_eq!(server.response("/project").status, 200);
+        assert_eq!(server.response("/project/pages/Rust.html").status, 200);
+        assert_eq!(server.response("/pages/Rust.html").status, 404);
+        assert_eq!(server.response("/projects/").status, 404);
+    }
+
+    #[test]
+    fn test_rebuild_reloads_clients() {
+        let graph = TempDir::new("serve-reload");
+        graph.write("pages/Rust.md", "- Text");
+        let server = Arc::new(DevServer::new(&config(&graph)));
+        server.rebuild().unwrap();
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let handler = Arc::clone(&server);
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                handler.handle(stream).unwrap();
+            }
+        });
+
+        let mut page = TcpStream::connect(address).unwrap();
+        page.write_all(b"GET /pages/Rust.html HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+        let mut response = String::new();
+        page.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains(RELOAD_SCRIPT));
+
+        let mut events = TcpStream::connect(address).unwrap();
+        events.write_all(b"GET /__reload HTTP/1.1\r\n\r\n").unwrap();
+        let mut reader = BufReader::new(events);
+        let mut line = String::new();
+        while reader.read_line(&mut line).unwrap() > 0 && line != "\r\n" {
+            line.clear();
+        }
+
+        graph.write("pages/Rust.md", "- Edited");
+        let stats = server.rebuild().unwrap();
+        assert_eq!(stats.reused_pages, 0);
+        line.clear();
+        reader.read_line(&mut line).unwrap();
+        assert_eq!(line, "data: reload\n");
+        assert!(String::from_utf8(server.response("/pages/Rust.html").body).unwrap().contains("Edited"));
+    }
+
+    #[test]
+    fn test_snapshot_tracks_pages() {
+        let graph = TempDir::new("serve-snapshot");
+        graph.write("pages/Rust.md", "- Text");
+        graph.write("notes.txt", "ignored");
+        let before = snapshot(&graph.0);
+        assert_eq!(before.len(), 1);
+
+        graph.write("pages/Rust.md", "- Longer text");
+        assert_ne!(snapshot(&graph.0), before);
+    }
+}
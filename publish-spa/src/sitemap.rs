@@ -0,0 +1,190 @@
+//! Search engine support: sitemap.xml, robots.txt and canonical URLs
+
+use crate::parser::Page;
+
+/// Page properties that may carry a last modification date, in priority order
+const LASTMOD_PROPERTIES: [&str; 4] = ["lastmod", "updated", "updated-at", "date"];
+
+/// A single `<url>` entry in the sitemap
+#[derive(Debug, Clone)]
+pub struct SitemapEntry {
+    pub loc: String,
+    pub lastmod: Option<String>,
+}
+
+/// Absolute URL of an output file, given the site's public base URL
+pub fn canonical_url(base_url: &str, html_path: &str) -> String {
+    let base = base_url.trim_end_matches('/');
+    if html_path == "index.html" {
+        format!("{}/", base)
+    } else if let Some(dir) = html_path.strip_suffix("/index.html") {
+        format!("{}/{}/", base, encode_url_path(dir))
+    } else {
+        format!("{}/{}", base, encode_url_path(html_path))
+    }
+}
+
+/// Whether the page opted out of indexing with `noindex:: true`
+pub fn is_noindex(page: &Page) -> bool {
+    page.properties.get("noindex").is_some_and(|v| v.trim().eq_ignore_ascii_case("true"))
+}
+
+/// Whether the page opted out of the sitemap with `sitemap:: false` or `noindex:: true`
+pub fn excluded_from_sitemap(page: &Page) -> bool {
+    is_noindex(page)
+        || page.properties.get("sitemap").is_some_and(|v| v.trim().eq_ignore_ascii_case("false"))
+}
+
+/// Last modification date (`YYYY-MM-DD`) from page properties, falling back to the source file's mtime
+pub fn last_modified(page: &Page) -> Option<String> {
+    LASTMOD_PROPERTIES
+        .iter()
+        .filter_map(|key| page.properties.get(*key))
+        .find_map(|value| parse_date_property(value))
+        .or_else(|| page.modified.map(date_from_millis))
+}
+
+/// Generate sitemap.xml for the given entries
+pub fn generate_sitemap(entries: &[SitemapEntry]) -> String {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str("<urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n");
+
+    for entry in entries {
+        xml.push_str("<url>\n");
+        xml.push_str(&format!("<loc>{}</loc>\n", escape_xml(&entry.loc)));
+        if let Some(lastmod) = &entry.lastmod {
+            xml.push_str(&format!("<lastmod>{}</lastmod>\n", lastmod));
+        }
+        xml.push_str("</url>\n");
+    }
+
+    xml.push_str("</urlset>\n");
+    xml
+}
+
+/// Whether the base URL is the root of its host; crawlers only read robots.txt there
+pub fn is_host_root(base_url: &str) -> bool {
+    let after_scheme = base_url.split_once("://").map_or(base_url, |(_, rest)| rest);
+    after_scheme.split_once('/').is_none_or(|(_, path)| path.is_empty())
+}
+
+/// Generate robots.txt allowing all crawlers and pointing at the sitemap
+pub fn generate_robots_txt(base_url: &str) -> String {
+    format!(
+        "User-agent: *\nAllow: /\n\nSitemap: {}/sitemap.xml\n",
+        base_url.trim_end_matches('/')
+    )
+}
+
+/// Parse a date property, accepting `YYYY-MM-DD` prefixes and millisecond timestamps
+pub fn parse_date_property(value: &str) -> Option<String> {
+    let value = value.trim().trim_start_matches("[[").trim_end_matches("]]");
+
+    if !value.is_empty() && value.chars().all(|c| c.is_ascii_digit()) {
+        return value.parse::<u64>().ok().map(date_from_millis);
+    }
+
+    let date = value.get(..10)?;
+    let bytes = date.as_bytes();
+    let well_formed = bytes.iter().enumerate().all(|(i, b)| match i {
+        4 | 7 => *b == b'-',
+        _ => b.is_ascii_digit(),
+    });
+
+    well_formed.then(|| date.to_string())
+}
+
+/// Format milliseconds since the Unix epoch as a `YYYY-MM-DD` UTC date
+fn date_from_millis(millis: u64) -> String {
+    // Civil-from-days conversion (Howard Hinnant's algorithm)
+    let days = (millis / 86_400_000) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Percent-encode a relative URL path, keeping `/` separators
+pub fn encode_url_path(path: &str) -> String {
+    path.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// Escape text for use in XML content and attribute values
+pub fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_logseq_page;
+
+    #[test]
+    fn test_canonical_url() {
+        assert_eq!(canonical_url("https://example.com/docs/", "index.html"), "https://example.com/docs/");
+        assert_eq!(
+            canonical_url("https://example.com", "pages/Some Page.html"),
+            "https://example.com/pages/Some%20Page.html"
+        );
+        assert_eq!(canonical_url("https://example.com", "pages/page/index.html"), "https://example.com/pages/page/");
+    }
+
+    #[test]
+    fn test_last_modified() {
+        let page = parse_logseq_page("updated:: 2024-03-05T10:00\n- Block", "a.md").unwrap();
+        assert_eq!(last_modified(&page), Some("2024-03-05".to_string()));
+
+        let mut page = parse_logseq_page("- Block", "b.md").unwrap();
+        assert_eq!(last_modified(&page), None);
+        page.modified = Some(1_700_000_000_000);
+        assert_eq!(last_modified(&page), Some("2023-11-14".to_string()));
+    }
+
+    #[test]
+    fn test_sitemap_exclusion() {
+        let hidden = parse_logseq_page("sitemap:: false\n- Block", "a.md").unwrap();
+        let noindex = parse_logseq_page("noindex:: true\n- Block", "b.md").unwrap();
+        let visible = parse_logseq_page("- Block", "c.md").unwrap();
+        assert!(excluded_from_sitemap(&hidden));
+        assert!(excluded_from_sitemap(&noindex));
+        assert!(!excluded_from_sitemap(&visible));
+    }
+
+    #[test]
+    fn test_generate_sitemap_and_robots() {
+        let xml = generate_sitemap(&[SitemapEntry {
+            loc: "https://example.com/a.html?x=1&y=2".to_string(),
+            lastmod: Some("2024-01-01".to_string()),
+        }]);
+        assert!(xml.contains("<loc>https://example.com/a.html?x=1&amp;y=2</loc>"));
+        assert!(xml.contains("<lastmod>2024-01-01</lastmod>"));
+
+        let robots = generate_robots_txt("https://example.com/");
+        assert!(robots.contains("Sitemap: https://example.com/sitemap.xml"));
+    }
+
+    #[test]
+    fn test_is_host_root() {
+        assert!(is_host_root("https://example.com"));
+        assert!(is_host_root("https://example.com/"));
+        assert!(!is_host_root("https://example.com/wiki/"));
+        assert!(!is_host_root("https://example.com/wiki"));
+    }
+}
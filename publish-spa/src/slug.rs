@@ -0,0 +1,280 @@
+//! Output paths for pages: slug strategies, collision detection and redirects between strategies
+
+use deunicode::deunicode;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// Output paths written by the exporter itself, never given to pages
+const RESERVED_PATHS: [&str; 1] = ["index.html"];
+
+/// How page output paths are derived from source file names
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SlugStrategy {
+    /// Source file names with `.md` replaced by `.html`
+    #[default]
+    Preserve,
+    /// Transliterated, lowercase, hyphen-separated file names
+    LowercaseKebab,
+    /// Lowercase-kebab directories holding an `index.html`, linked as `slug/`
+    Pretty,
+}
+
+/// Several sources that map to the same output path
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SlugCollision {
+    pub path: String,
+    /// Colliding source files, sorted; all but the first were given a numbered suffix
+    pub sources: Vec<String>,
+}
+
+/// Output paths assigned to a set of source files
+#[derive(Debug, Clone, Default)]
+pub struct OutputPaths {
+    /// Source path to output path
+    pub paths: HashMap<String, String>,
+    pub collisions: Vec<SlugCollision>,
+}
+
+/// Output path of a page under the given strategy, before collision handling
+pub fn page_output_path(source_path: &str, strategy: SlugStrategy) -> String {
+    let stem = source_path
+        .trim_end_matches(".md")
+        .trim_end_matches(".markdown");
+
+    match strategy {
+        SlugStrategy::Preserve => format!("{}.html", stem),
+        SlugStrategy::LowercaseKebab => format!("{}.html", slug_path(stem)),
+        SlugStrategy::Pretty => format!("{}/index.html", slug_path(stem)),
+    }
+}
+
+/// Output path of the stub page for a missing page name, keeping namespaces as directories
+pub fn stub_output_path(name: &str, strategy: SlugStrategy) -> String {
+    let segments = name
+        .split('/')
+        .map(str::trim)
+        .filter(|segment| !segment.is_empty());
+
+    match strategy {
+        SlugStrategy::Preserve => {
+            let file_name = segments
+                .map(|segment| {
+                    segment.chars()
+                        .map(|c| if c.is_alphanumeric() || matches!(c, ' ' | '-' | '_') { c } else { '_' })
+                        .collect::<String>()
+                })
+                .collect::<Vec<_>>()
+                .join("/");
+
+            if file_name.is_empty() {
+                "_.html".to_string()
+            } else {
+                file_name + ".html"
+            }
+        }
+        SlugStrategy::LowercaseKebab => format!("{}.html", slug_segments(segments)),
+        SlugStrategy::Pretty => format!("{}/index.html", slug_segments(segments)),
+    }
+}
+
+/// Assign output paths to pages, renaming and reporting pages whose paths collide
+/// with each other or with a generated file.
+///
+/// Sources are processed in sorted order, so the same input always gets the same paths.
+pub fn assign_output_paths<'a>(
+    sources: impl IntoIterator<Item = &'a str>,
+    strategy: SlugStrategy,
+) -> OutputPaths {
+    let mut wanted: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for source in sources {
+        wanted.entry(page_output_path(source, strategy))
+            .or_default()
+            .push(source.to_string());
+    }
+
+    let mut taken: HashSet<String> = wanted.keys().cloned().collect();
+    taken.extend(RESERVED_PATHS.iter().map(|path| path.to_string()));
+
+    let mut output = OutputPaths::default();
+    for (path, mut sources) in wanted {
+        sources.sort();
+        let reserved = RESERVED_PATHS.contains(&path.as_str());
+        if sources.len() == 1 && !reserved {
+            output.paths.insert(sources.remove(0), path);
+            continue;
+        }
+
+        for (i, source) in sources.iter().enumerate() {
+            if i == 0 && !reserved {
+                output.paths.insert(source.clone(), path.clone());
+                continue;
+            }
+
+            let renamed = (2..)
+                .map(|n| with_suffix(&path, n))
+                .find(|candidate| !taken.contains(candidate))
+                .unwrap();
+            taken.insert(renamed.clone());
+            output.paths.insert(source.clone(), renamed);
+        }
+
+        output.collisions.push(SlugCollision { path, sources });
+    }
+
+    output
+}
+
+/// Paths pages had under the `previous` strategies, mapped to their current output paths.
+///
+/// Old paths that are still in use, or differ from the current path only in case, are left out.
+pub fn legacy_redirects(
+    paths: &HashMap<String, String>,
+    previous: &[SlugStrategy],
+) -> BTreeMap<String, String> {
+    let current: HashSet<String> = paths.values().map(|path| path.to_lowercase()).collect();
+
+    let mut sources: Vec<(&String, &String)> = paths.iter().collect();
+    sources.sort();
+
+    let mut redirects = BTreeMap::new();
+    for (source, target) in sources {
+        for strategy in previous {
+            let old = page_output_path(source, *strategy);
+            let in_use = current.contains(&old.to_lowercase())
+                || RESERVED_PATHS.contains(&old.as_str());
+            if !in_use {
+                redirects.entry(old).or_insert_with(|| target.clone());
+            }
+        }
+    }
+
+    redirects
+}
+
+/// Slug of a page path without extension; Logseq's `%2F` and `___` namespace
+/// escapes in the file name become directories
+fn slug_path(stem: &str) -> String {
+    let (dirs, file_name) = match stem.rsplit_once('/') {
+        Some((dirs, file_name)) => (Some(dirs), file_name),
+        None => (None, stem),
+    };
+
+    let file_name = percent_decode(&file_name.replace("___", "/"));
+    let segments = dirs
+        .into_iter()
+        .flat_map(|dirs| dirs.split('/'))
+        .chain(file_name.split('/'))
+        .filter(|segment| !segment.is_empty());
+
+    slug_segments(segments)
+}
+
+fn slug_segments<'a>(segments: impl Iterator<Item = &'a str>) -> String {
+    let path = segments.map(slugify).collect::<Vec<_>>().join("/");
+    if path.is_empty() {
+        "untitled".to_string()
+    } else {
+        path
+    }
+}
+
+/// Transliterate to ASCII, lowercase, and join words with hyphens
+fn slugify(segment: &str) -> String {
+    let ascii = deunicode(segment).to_lowercase();
+    let slug = ascii
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+
+    if slug.is_empty() {
+        "untitled".to_string()
+    } else {
+        slug
+    }
+}
+
+/// Decode `%XX` escapes, leaving the text unchanged if the result is not UTF-8
+pub fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8(decoded).unwrap_or_else(|_| s.to_string())
+}
+
+/// `path` with `-n` appended to its file or pretty-URL directory name
+fn with_suffix(path: &str, n: usize) -> String {
+    if let Some(dir) = path.strip_suffix("/index.html") {
+        format!("{}-{}/index.html", dir, n)
+    } else {
+        let stem = path.trim_end_matches(".html");
+        format!("{}-{}.html", stem, n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_page_output_paths() {
+        let source = "pages/Café Notes%2FDraft.md";
+        assert_eq!(page_output_path(source, SlugStrategy::Preserve), "pages/Café Notes%2FDraft.html");
+        assert_eq!(page_output_path(source, SlugStrategy::LowercaseKebab), "pages/cafe-notes/draft.html");
+        assert_eq!(page_output_path(source, SlugStrategy::Pretty), "pages/cafe-notes/draft/index.html");
+        assert_eq!(page_output_path("pages/Project___Plan.md", SlugStrategy::LowercaseKebab), "pages/project/plan.html");
+        assert_eq!(page_output_path("pages/日本.md", SlugStrategy::LowercaseKebab), "pages/ri-ben.html");
+        assert_eq!(page_output_path("pages/???.md", SlugStrategy::LowercaseKebab), "pages/untitled.html");
+    }
+
+    #[test]
+    fn test_stub_output_paths() {
+        assert_eq!(stub_output_path("Project/Plan", SlugStrategy::Preserve), "Project/Plan.html");
+        assert_eq!(stub_output_path("a:b", SlugStrategy::Preserve), "a_b.html");
+        assert_eq!(stub_output_path("Some Concept", SlugStrategy::Pretty), "some-concept/index.html");
+    }
+
+    #[test]
+    fn test_collisions_are_renamed_and_reported() {
+        let output = assign_output_paths(
+            ["pages/Foo Bar.md", "pages/foo-bar.md", "pages/foo_bar.md", "index.md"],
+            SlugStrategy::LowercaseKebab,
+        );
+
+        assert_eq!(output.paths["pages/Foo Bar.md"], "pages/foo-bar.html");
+        assert_eq!(output.paths["pages/foo-bar.md"], "pages/foo-bar-2.html");
+        assert_eq!(output.paths["pages/foo_bar.md"], "pages/foo-bar-3.html");
+        assert_eq!(output.paths["index.md"], "index-2.html");
+        assert_eq!(output.collisions.len(), 2);
+        assert_eq!(output.collisions[1].path, "pages/foo-bar.html");
+        assert_eq!(output.collisions[1].sources.len(), 3);
+    }
+
+    #[test]
+    fn test_legacy_redirects() {
+        let output = assign_output_paths(["pages/My Page.md", "pages/other.md"], SlugStrategy::Pretty);
+        let redirects = legacy_redirects(&output.paths, &[SlugStrategy::Preserve, SlugStrategy::LowercaseKebab]);
+
+        assert_eq!(redirects["pages/My Page.html"], "pages/my-page/index.html");
+        assert_eq!(redirects["pages/my-page.html"], "pages/my-page/index.html");
+        assert_eq!(redirects["pages/other.html"], "pages/other/index.html");
+        assert_eq!(redirects.len(), 3);
+    }
+}
@@ -0,0 +1,119 @@
+//! Colour scheme of the generated stylesheet
+
+use serde::{Deserialize, Serialize};
+
+/// Light or dark colour scheme, matching the Logseq frontend's `theme-mode`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ThemeMode {
+    #[default]
+    Light,
+    Dark,
+}
+
+/// Accent colour used for links and highlights, matching the Logseq frontend's `accent-color`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AccentColor {
+    Tomato,
+    Red,
+    Crimson,
+    Pink,
+    Plum,
+    Purple,
+    Violet,
+    Indigo,
+    #[default]
+    Blue,
+    Cyan,
+    Teal,
+    Green,
+    Grass,
+    Orange,
+}
+
+impl AccentColor {
+    /// Link colour for the accent in the given mode
+    fn hex(self, mode: ThemeMode) -> &'static str {
+        match (self, mode) {
+            (AccentColor::Tomato, ThemeMode::Light) => "#e54d2e",
+            (AccentColor::Tomato, ThemeMode::Dark) => "#f97a5f",
+            (AccentColor::Red, ThemeMode::Light) => "#e53e3e",
+            (AccentColor::Red, ThemeMode::Dark) => "#fc8181",
+            (AccentColor::Crimson, ThemeMode::Light) => "#e93d82",
+            (AccentColor::Crimson, ThemeMode::Dark) => "#f77fab",
+            (AccentColor::Pink, ThemeMode::Light) => "#d53f8c",
+            (AccentColor::Pink, ThemeMode::Dark) => "#f687b3",
+            (AccentColor::Plum, ThemeMode::Light) => "#ab4aba",
+            (AccentColor::Plum, ThemeMode::Dark) => "#d28fdc",
+            (AccentColor::Purple, ThemeMode::Light) => "#805ad5",
+            (AccentColor::Purple, ThemeMode::Dark) => "#b794f4",
+            (AccentColor::Violet, ThemeMode::Light) => "#6e56cf",
+            (AccentColor::Violet, ThemeMode::Dark) => "#a594f9",
+            (AccentColor::Indigo, ThemeMode::Light) => "#5a67d8",
+            (AccentColor::Indigo, ThemeMode::Dark) => "#a3bffa",
+            (AccentColor::Blue, ThemeMode::Light) => "#3182ce",
+            (AccentColor::Blue, ThemeMode::Dark) => "#63b3ed",
+            (AccentColor::Cyan, ThemeMode::Light) => "#00a2c7",
+            (AccentColor::Cyan, ThemeMode::Dark) => "#4ccce6",
+            (AccentColor::Teal, ThemeMode::Light) => "#319795",
+            (AccentColor::Teal, ThemeMode::Dark) => "#4fd1c5",
+            (AccentColor::Green, ThemeMode::Light) => "#38a169",
+            (AccentColor::Green, ThemeMode::Dark) => "#68d391",
+            (AccentColor::Grass, ThemeMode::Light) => "#46a758",
+            (AccentColor::Grass, ThemeMode::Dark) => "#71d083",
+            (AccentColor::Orange, ThemeMode::Light) => "#dd6b20",
+            (AccentColor::Orange, ThemeMode::Dark) => "#f6ad55",
+        }
+    }
+}
+
+/// The `:root` block defining the stylesheet's colour variables
+pub fn css_variables(mode: ThemeMode, accent: AccentColor) -> String {
+    let (scheme, bg, text, muted, border, tag_bg, block_border, surface, hover) = match mode {
+        ThemeMode::Light => ("light", "#ffffff", "#2d3748", "#718096", "#e2e8f0", "#edf2f7", "#cbd5e0", "#f7fafc", "#ebf8ff"),
+        ThemeMode::Dark => ("dark", "#1a202c", "#e2e8f0", "#a0aec0", "#2d3748", "#2d3748", "#4a5568", "#232a38", "#2a4365"),
+    };
+
+    format!(
+        r#":root {{
+    color-scheme: {};
+    --bg-color: {};
+    --text-color: {};
+    --muted-color: {};
+    --link-color: {};
+    --border-color: {};
+    --tag-bg: {};
+    --block-border: {};
+    --surface-bg: {};
+    --hover-bg: {};
+}}
+"#,
+        scheme, bg, text, muted, accent.hex(mode), border, tag_bg, block_border, surface, hover
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_css_variables() {
+        let light = css_variables(ThemeMode::Light, AccentColor::Blue);
+        assert!(light.contains("color-scheme: light;"));
+        assert!(light.contains("--bg-color: #ffffff;"));
+        assert!(light.contains("--link-color: #3182ce;"));
+
+        let dark = css_variables(ThemeMode::Dark, AccentColor::Green);
+        assert!(dark.contains("color-scheme: dark;"));
+        assert!(dark.contains("--bg-color: #1a202c;"));
+        assert!(dark.contains("--link-color: #68d391;"));
+    }
+
+    #[test]
+    fn test_names_match_frontend_options() {
+        assert_eq!(serde_json::from_str::<ThemeMode>("\"dark\"").unwrap(), ThemeMode::Dark);
+        assert_eq!(serde_json::from_str::<AccentColor>("\"crimson\"").unwrap(), AccentColor::Crimson);
+        assert!(serde_json::from_str::<AccentColor>("\"mauve\"").is_err());
+    }
+}
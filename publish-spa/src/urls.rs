@@ -0,0 +1,134 @@
+//! URL generation between output files
+
+use crate::sitemap;
+use serde::{Deserialize, Serialize};
+
+/// How generated pages address other output files
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkStyle {
+    /// Paths relative to the linking page, so the site works from any location
+    #[default]
+    Relative,
+    /// Root-absolute paths prefixed with the site's base path
+    Absolute,
+}
+
+/// Builds hrefs between output files, given each file's path relative to the output root
+#[derive(Debug, Clone)]
+pub struct UrlBuilder {
+    style: LinkStyle,
+    /// Site path prefix such as `/` or `/project/`, always with leading and trailing slashes
+    base_path: String,
+    /// Link to `dir/index.html` as `dir/`, for pretty URLs
+    directory_urls: bool,
+}
+
+impl UrlBuilder {
+    /// Create a builder; `base_path` is the sub-path the site is served under (e.g. a
+    /// GitHub Pages project site), falling back to the path component of `base_url`
+    pub fn new(style: LinkStyle, base_path: Option<&str>, base_url: Option<&str>, directory_urls: bool) -> Self {
+        let base_path = site_base_path(base_path, base_url);
+        Self { style, base_path, directory_urls }
+    }
+
+    /// Href to the output file `to` from a page at output path `from`
+    pub fn href(&self, from: &str, to: &str) -> String {
+        let href = match self.style {
+            LinkStyle::Relative => sitemap::encode_url_path(&relative_path(from, to)),
+            LinkStyle::Absolute => format!("{}{}", self.base_path, sitemap::encode_url_path(to)),
+        };
+
+        match href.strip_suffix("index.html") {
+            Some("") if self.directory_urls => "./".to_string(),
+            Some(dir) if self.directory_urls && dir.ends_with('/') => dir.to_string(),
+            _ => href,
+        }
+    }
+}
+
+/// Sub-path the site is served under, with leading and trailing slashes: `base_path`,
+/// else the path component of `base_url`, else `/`
+pub fn site_base_path(base_path: Option<&str>, base_url: Option<&str>) -> String {
+    let path = base_path
+        .map(str::to_string)
+        .or_else(|| base_url.map(url_path))
+        .unwrap_or_default();
+
+    let trimmed = path.trim_matches('/');
+    if trimmed.is_empty() {
+        "/".to_string()
+    } else {
+        format!("/{}/", trimmed)
+    }
+}
+
+/// Path from the directory of `from` to `to`, both relative to the output root
+fn relative_path(from: &str, to: &str) -> String {
+    let from_dirs: Vec<&str> = from.split('/').collect();
+    let from_dirs = &from_dirs[..from_dirs.len() - 1];
+    let to_segments: Vec<&str> = to.split('/').collect();
+    let (to_dirs, to_file) = to_segments.split_at(to_segments.len() - 1);
+
+    let common = from_dirs
+        .iter()
+        .zip(to_dirs)
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let mut path = "../".repeat(from_dirs.len() - common);
+    for dir in &to_dirs[common..] {
+        path.push_str(dir);
+        path.push('/');
+    }
+    path.push_str(to_file[0]);
+    path
+}
+
+/// Path component of an absolute URL such as `https://user.github.io/project`
+fn url_path(url: &str) -> String {
+    let without_scheme = url.split_once("://").map_or(url, |(_, rest)| rest);
+    without_scheme
+        .find('/')
+        .map(|slash| without_scheme[slash..].to_string())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_relative_hrefs() {
+        let urls = UrlBuilder::new(LinkStyle::Relative, None, None, false);
+        assert_eq!(urls.href("index.html", "style.css"), "style.css");
+        assert_eq!(urls.href("orphan.html", "pages/features.html"), "pages/features.html");
+        assert_eq!(urls.href("pages/a/b.html", "style.css"), "../../style.css");
+        assert_eq!(urls.href("pages/a/b.html", "pages/c.html"), "../c.html");
+        assert_eq!(urls.href("pages/a/b.html", "pages/a/d e.html"), "d%20e.html");
+    }
+
+    #[test]
+    fn test_absolute_hrefs_with_base_path() {
+        let urls = UrlBuilder::new(LinkStyle::Absolute, None, None, false);
+        assert_eq!(urls.href("pages/a/b.html", "style.css"), "/style.css");
+
+        let urls = UrlBuilder::new(LinkStyle::Absolute, Some("project"), None, false);
+        assert_eq!(urls.href("pages/a/b.html", "pages/c.html"), "/project/pages/c.html");
+
+        let urls = UrlBuilder::new(LinkStyle::Absolute, None, Some("https://user.github.io/project/"), false);
+        assert_eq!(urls.href("index.html", "app.js"), "/project/app.js");
+    }
+
+    #[test]
+    fn test_directory_urls() {
+        let urls = UrlBuilder::new(LinkStyle::Relative, None, None, true);
+        assert_eq!(urls.href("pages/a/index.html", "pages/b/index.html"), "../b/");
+        assert_eq!(urls.href("pages/a/index.html", "index.html"), "../../");
+        assert_eq!(urls.href("pages/a/index.html", "pages/a/index.html"), "./");
+        assert_eq!(urls.href("index.html", "style.css"), "style.css");
+
+        let urls = UrlBuilder::new(LinkStyle::Absolute, Some("/project/"), None, true);
+        assert_eq!(urls.href("pages/a/index.html", "index.html"), "/project/");
+    }
+}
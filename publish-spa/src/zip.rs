@@ -0,0 +1,235 @@
+//! Minimal zip archive writer, in pure Rust so it also runs in WASM
+//!
+//! Entries are deflated unless that doesn't make them smaller (as with precompressed
+//! `.gz`/`.br` outputs), names are flagged as UTF-8, and every entry carries the
+//! same fixed timestamp so identical sites produce identical archives.
+
+use crate::errors::PublishError;
+use crate::fs::OutputSink;
+use flate2::write::DeflateEncoder;
+use flate2::{Compression, Crc};
+use std::io::Write;
+
+const LOCAL_HEADER_SIGNATURE: u32 = 0x0403_4b50;
+const CENTRAL_HEADER_SIGNATURE: u32 = 0x0201_4b50;
+const END_OF_CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x0605_4b50;
+
+/// Version 2.0: deflate and directories
+const VERSION: u16 = 20;
+/// General purpose flag bit 11: names are UTF-8
+const UTF8_NAMES: u16 = 1 << 11;
+const METHOD_STORED: u16 = 0;
+const METHOD_DEFLATED: u16 = 8;
+/// MS-DOS time and date of 1980-01-01 00:00, the earliest a zip entry can carry
+const DOS_TIME: u16 = 0;
+const DOS_DATE: u16 = (1 << 5) | 1;
+
+/// Builds a zip archive in memory
+#[derive(Debug, Default)]
+pub struct ZipWriter {
+    data: Vec<u8>,
+    central_directory: Vec<u8>,
+    entries: usize,
+}
+
+impl ZipWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a file; archives are limited to 65535 entries of up to 4 GiB (no zip64)
+    pub fn add_file(&mut self, path: &str, content: &[u8]) -> Result<(), PublishError> {
+        let too_large = || PublishError::export(format!("'{}' is too large for a zip archive", path));
+        if self.entries == usize::from(u16::MAX) {
+            return Err(PublishError::export("Too many files for a zip archive"));
+        }
+
+        let mut crc = Crc::new();
+        crc.update(content);
+        let deflated = deflate(content).map_err(|e| PublishError::export(format!("Failed to compress '{}': {}", path, e)))?;
+        let (method, stored) = if deflated.len() < content.len() {
+            (METHOD_DEFLATED, deflated.as_slice())
+        } else {
+            (METHOD_STORED, content)
+        };
+
+        let name_length = u16::try_from(path.len()).map_err(|_| too_large())?;
+        let compressed_size = u32::try_from(stored.len()).map_err(|_| too_large())?;
+        let size = u32::try_from(content.len()).map_err(|_| too_large())?;
+        let offset = u32::try_from(self.data.len()).map_err(|_| too_large())?;
+
+        let local = &mut self.data;
+        put_u32(local, LOCAL_HEADER_SIGNATURE);
+        put_u16(local, VERSION);
+        put_u16(local, UTF8_NAMES);
+        put_u16(local, method);
+        put_u16(local, DOS_TIME);
+        put_u16(local, DOS_DATE);
+        put_u32(local, crc.sum());
+        put_u32(local, compressed_size);
+        put_u32(local, size);
+        put_u16(local, name_length);
+        put_u16(local, 0); // extra field length
+        local.extend_from_slice(path.as_bytes());
+        local.extend_from_slice(stored);
+
+        let central = &mut self.central_directory;
+        put_u32(central, CENTRAL_HEADER_SIGNATURE);
+        put_u16(central, VERSION); // version made by
+        put_u16(central, VERSION); // version needed to extract
+        put_u16(central, UTF8_NAMES);
+        put_u16(central, method);
+        put_u16(central, DOS_TIME);
+        put_u16(central, DOS_DATE);
+        put_u32(central, crc.sum());
+        put_u32(central, compressed_size);
+        put_u32(central, size);
+        put_u16(central, name_length);
+        put_u16(central, 0); // extra field length
+        put_u16(central, 0); // comment length
+        put_u16(central, 0); // disk number
+        put_u16(central, 0); // internal attributes
+        put_u32(central, 0); // external attributes
+        put_u32(central, offset);
+        central.extend_from_slice(path.as_bytes());
+
+        self.entries += 1;
+        Ok(())
+    }
+
+    /// Write the central directory and return the archive
+    pub fn finish(mut self) -> Result<Vec<u8>, PublishError> {
+        let too_large = || PublishError::export("Site is too large for a zip archive");
+        let entries = u16::try_from(self.entries).map_err(|_| too_large())?;
+        let directory_size = u32::try_from(self.central_directory.len()).map_err(|_| too_large())?;
+        let directory_offset = u32::try_from(self.data.len()).map_err(|_| too_large())?;
+
+        let mut data = std::mem::take(&mut self.data);
+        data.extend_from_slice(&self.central_directory);
+        put_u32(&mut data, END_OF_CENTRAL_DIRECTORY_SIGNATURE);
+        put_u16(&mut data, 0); // this disk
+        put_u16(&mut data, 0); // disk with the central directory
+        put_u16(&mut data, entries); // entries on this disk
+        put_u16(&mut data, entries);
+        put_u32(&mut data, directory_size);
+        put_u32(&mut data, directory_offset);
+        put_u16(&mut data, 0); // comment length
+        Ok(data)
+    }
+}
+
+impl OutputSink for ZipWriter {
+    async fn write_file(&mut self, path: &str, content: &[u8]) -> Result<(), PublishError> {
+        crate::converter::validate_file_path(path)?;
+        self.add_file(path, content)
+    }
+
+    /// Every archive starts empty, so there is no earlier build to read from
+    async fn read_file(&self, _path: &str) -> Result<Option<Vec<u8>>, PublishError> {
+        Ok(None)
+    }
+
+    async fn remove_file(&mut self, _path: &str) -> Result<(), PublishError> {
+        Ok(())
+    }
+}
+
+fn deflate(data: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::best());
+    encoder.write_all(data)?;
+    encoder.finish()
+}
+
+fn put_u16(out: &mut Vec<u8>, value: u16) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_u32(out: &mut Vec<u8>, value: u32) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::DeflateDecoder;
+    use std::io::Read;
+
+    fn u16_at(data: &[u8], offset: usize) -> u16 {
+        u16::from_le_bytes([data[offset], data[offset + 1]])
+    }
+
+    fn u32_at(data: &[u8], offset: usize) -> u32 {
+        u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+    }
+
+    /// Read every entry back by walking the central directory
+    fn read_entries(zip: &[u8]) -> Vec<(String, Vec<u8>)> {
+        let end = zip.len() - 22;
+        assert_eq!(u32_at(zip, end), END_OF_CENTRAL_DIRECTORY_SIGNATURE);
+        let count = u16_at(zip, end + 10) as usize;
+        let mut offset = u32_at(zip, end + 16) as usize;
+
+        let mut entries = Vec::new();
+        for _ in 0..count {
+            assert_eq!(u32_at(zip, offset), CENTRAL_HEADER_SIGNATURE);
+            let method = u16_at(zip, offset + 10);
+            let crc = u32_at(zip, offset + 16);
+            let compressed_size = u32_at(zip, offset + 20) as usize;
+            let name_length = u16_at(zip, offset + 28) as usize;
+            let local = u32_at(zip, offset + 42) as usize;
+            let name = String::from_utf8(zip[offset + 46..offset + 46 + name_length].to_vec()).unwrap();
+
+            assert_eq!(u32_at(zip, local), LOCAL_HEADER_SIGNATURE);
+            let start = local + 30 + u16_at(zip, local + 26) as usize;
+            let stored = &zip[start..start + compressed_size];
+            let content = match method {
+                METHOD_STORED => stored.to_vec(),
+                METHOD_DEFLATED => {
+                    let mut content = Vec::new();
+                    DeflateDecoder::new(stored).read_to_end(&mut content).unwrap();
+                    content
+                }
+                _ => panic!("unexpected method {}", method),
+            };
+
+            let mut actual_crc = Crc::new();
+            actual_crc.update(&content);
+            assert_eq!(actual_crc.sum(), crc);
+
+            entries.push((name, content));
+            offset += 46 + name_length;
+        }
+        entries
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let page = "<p>Repeated text.</p>\n".repeat(100);
+        let mut zip = ZipWriter::new();
+        zip.add_file("index.html", page.as_bytes()).unwrap();
+        zip.add_file("pages/Übersicht.html", b"<p>x</p>").unwrap();
+        zip.add_file("index.html.gz", &[0x1f, 0x8b, 0x08]).unwrap();
+        let data = zip.finish().unwrap();
+
+        let entries = read_entries(&data);
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0], ("index.html".to_string(), page.clone().into_bytes()));
+        assert_eq!(entries[1], ("pages/Übersicht.html".to_string(), b"<p>x</p>".to_vec()));
+        assert_eq!(entries[2].1, [0x1f, 0x8b, 0x08]);
+        assert!(data.len() < page.len());
+
+        // Only the content decides the bytes
+        let mut again = ZipWriter::new();
+        again.add_file("index.html", page.as_bytes()).unwrap();
+        again.add_file("pages/Übersicht.html", b"<p>x</p>").unwrap();
+        again.add_file("index.html.gz", &[0x1f, 0x8b, 0x08]).unwrap();
+        assert_eq!(again.finish().unwrap(), data);
+    }
+
+    #[test]
+    fn test_empty_archive() {
+        let data = ZipWriter::new().finish().unwrap();
+        assert_eq!(data.len(), 22);
+        assert!(read_entries(&data).is_empty());
+    }
+}
@@ -1,6 +1,6 @@
 //! Shared test utilities and helpers
 
-use std::path::{Path, PathBuf};
+use std::path::PathBuf;
 use std::collections::HashMap;
 
 /// Create a temporary test directory structure
@@ -75,6 +75,13 @@ fn test_stats_getters() {
         total_blocks: 20,
         total_links: 10,
         orphan_pages: 1,
+        slug_collisions: 0,
+        output_bytes: 0,
+        minify_saved_bytes: 0,
+        compressed_files: 0,
+        gzip_bytes: 0,
+        brotli_bytes: 0,
+        reused_pages: 0,
     };
 
     assert_eq!(stats.page_count(), 5);
@@ -0,0 +1,400 @@
+//! End-to-end tests of the publish pipeline against an in-memory graph
+//!
+//! These run natively with `cargo test`: the graph is read from and the site is
+//! written to a `MemoryFs`, so no Node.js file system is involved.
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use publish_spa_wasm::native::block_on;
+use publish_spa_wasm::{
+    publish_to, publish_with_progress, ErrorPolicy, HealthReport, HealthRule, MemoryFs, OutputSink, Phase, Progress,
+    ProgressEvent, PublishConfig, PublishError, Severity, Span, ZipWriter,
+};
+
+#[allow(dead_code)]
+mod common;
+use common::{fixtures, html};
+
+fn test_graph() -> MemoryFs {
+    fixtures::test_graph()
+        .into_iter()
+        .map(|(name, content)| (format!("pages/{}", name), content))
+        .collect()
+}
+
+fn config() -> PublishConfig {
+    PublishConfig::new(String::new(), String::new())
+}
+
+#[test]
+fn test_publish_in_memory() {
+    let mut site = MemoryFs::new();
+    let stats = block_on(publish_to(&config(), &test_graph(), &mut site)).unwrap();
+
+    assert_eq!(stats.page_count, 5);
+    assert!(stats.total_blocks > 0);
+    assert_eq!(stats.output_bytes, site.iter().map(|(_, content)| content.len()).sum::<usize>());
+
+    for path in ["index.html", "style.css", "app.js", "pages/target.html", "pages/orphan.html"] {
+        assert!(site.get(path).is_some(), "missing {}", path);
+    }
+    let target = site.get_str("pages/target.html").unwrap();
+    assert!(html::is_valid_document(target));
+    assert!(target.contains("This is the target of backlinks"));
+    assert!(target.contains("href=\"../style.css\""));
+}
+
+#[test]
+fn test_publish_with_precompression() {
+    let mut config = config();
+    config.precompress = true;
+
+    let mut site = MemoryFs::new();
+    let stats = block_on(publish_to(&config, &test_graph(), &mut site)).unwrap();
+
+    assert!(stats.compressed_files > 0);
+    assert_eq!(site.iter().filter(|(path, _)| path.ends_with(".br")).count(), stats.compressed_files);
+    assert!(site.get("index.html.gz").is_some());
+}
+
+#[test]
+fn test_health_report_and_fail_on() {
+    // Without a report or fail_on the checks don't run
+    let mut config = config();
+    let stats = block_on(publish_to(&config, &test_graph(), &mut MemoryFs::new())).unwrap();
+    assert_eq!(stats.health_issues, 0);
+
+    config.health_report_html = true;
+
+    let mut site = MemoryFs::new();
+    let stats = block_on(publish_to(&config, &test_graph(), &mut site)).unwrap();
+    let report: HealthReport = serde_json::from_str(site.get_str("health-report.json").unwrap()).unwrap();
+    assert_eq!(stats.health_issues, report.issues.len());
+    assert!(report.issues.iter().any(|issue| issue.rule == HealthRule::OrphanPage && issue.page == "pages/orphan.md"));
+    assert!(site.get_str("health-report.html").unwrap().contains("Orphan pages"));
+
+    // Failing rules still write the site and report, then return an error
+    config.fail_on = vec![HealthRule::OrphanPage];
+    let mut site = MemoryFs::new();
+    let error = block_on(publish_to(&config, &test_graph(), &mut site)).unwrap_err();
+    assert!(error.to_string().contains("orphan-page"), "{}", error);
+    assert!(site.get("health-report.json").is_some());
+
+    config.fail_on = vec![HealthRule::StaleBlockRef];
+    assert!(block_on(publish_to(&config, &test_graph(), &mut MemoryFs::new())).is_ok());
+}
+
+#[test]
+fn test_diagnostics_for_unparseable_pages() {
+    let mut graph = test_graph();
+    graph.insert("pages/broken.md", "---\ntitle: Broken\n- never closed");
+
+    let mut site = MemoryFs::new();
+    let stats = block_on(publish_to(&config(), &graph, &mut site)).unwrap();
+
+    // The rest of the graph still publishes
+    assert_eq!(stats.page_count, 5);
+    assert_eq!(stats.diagnostics.len(), 1);
+    let diagnostic = &stats.diagnostics[0];
+    assert_eq!(diagnostic.severity, Severity::Error);
+    assert_eq!(diagnostic.code, "unclosed-frontmatter");
+    assert_eq!(diagnostic.file, "pages/broken.md");
+    assert_eq!(diagnostic.span, Some(Span { start_line: 1, start_column: 1, end_line: 1, end_column: 4 }));
+}
+
+#[test]
+fn test_error_policy() {
+    let mut graph = test_graph();
+    graph.insert("pages/broken.md", "---\ntitle: Broken\n- never closed");
+    let mut config = config();
+
+    // Nothing is written when the policy fails
+    config.error_policy = ErrorPolicy::Strict;
+    let mut site = MemoryFs::new();
+    let error = block_on(publish_to(&config, &graph, &mut site)).unwrap_err();
+    assert!(error.to_string().contains("pages/broken.md:1:1: error[unclosed-frontmatter]"), "{}", error);
+    assert!(site.is_empty());
+
+    config.error_policy = ErrorPolicy::Threshold(0);
+    assert!(block_on(publish_to(&config, &graph, &mut MemoryFs::new())).is_err());
+    config.error_policy = ErrorPolicy::Threshold(1);
+    assert!(block_on(publish_to(&config, &graph, &mut MemoryFs::new())).is_ok());
+    config.error_policy = ErrorPolicy::Strict;
+    assert!(block_on(publish_to(&config, &test_graph(), &mut MemoryFs::new())).is_ok());
+}
+
+/// A sink that fails to write one path
+struct FailingSink {
+    site: MemoryFs,
+    failing_path: &'static str,
+}
+
+impl OutputSink for FailingSink {
+    async fn write_file(&mut self, path: &str, content: &[u8]) -> Result<(), PublishError> {
+        if path == self.failing_path {
+            return Err(PublishError::io(format!("Disk full writing {}", path)));
+        }
+        self.site.write_file(path, content).await
+    }
+
+    async fn read_file(&self, path: &str) -> Result<Option<Vec<u8>>, PublishError> {
+        self.site.read_file(path).await
+    }
+
+    async fn remove_file(&mut self, path: &str) -> Result<(), PublishError> {
+        self.site.remove_file(path).await
+    }
+
+    async fn begin(&mut self) -> Result<(), PublishError> {
+        self.site.begin().await
+    }
+
+    async fn commit(&mut self) -> Result<(), PublishError> {
+        self.site.commit().await
+    }
+
+    async fn rollback(&mut self) -> Result<(), PublishError> {
+        self.site.rollback().await
+    }
+}
+
+#[test]
+fn test_failed_write_keeps_previous_site() {
+    let mut previous = MemoryFs::new();
+    block_on(publish_to(&config(), &test_graph(), &mut previous)).unwrap();
+
+    let mut graph = test_graph();
+    graph.insert("pages/new.md", "- A new page");
+    let mut sink = FailingSink { site: previous.clone(), failing_path: "pages/target.html" };
+    let error = block_on(publish_to(&config(), &graph, &mut sink)).unwrap_err();
+    assert!(error.to_string().contains("Disk full"), "{}", error);
+
+    // index.html and pages/new.html were written before the failure, but rolled back
+    assert_eq!(sink.site, previous);
+}
+
+/// Records progress events, cancelling once `cancel_after` have been reported
+struct RecordedProgress {
+    events: Vec<ProgressEvent>,
+    cancel_after: usize,
+}
+
+impl Progress for RecordedProgress {
+    fn report(&mut self, event: ProgressEvent) {
+        self.events.push(event);
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.events.len() >= self.cancel_after
+    }
+}
+
+#[test]
+fn test_progress_events() {
+    let mut progress = RecordedProgress { events: Vec::new(), cancel_after: usize::MAX };
+    let mut site = MemoryFs::new();
+    block_on(publish_with_progress(&config(), &test_graph(), &mut site, &mut progress)).unwrap();
+
+    let mut phases: Vec<Phase> = progress.events.iter().map(|event| event.phase).collect();
+    phases.dedup();
+    assert_eq!(phases, [Phase::Read, Phase::Parse, Phase::Graph, Phase::Render, Phase::Write]);
+    assert_eq!(progress.events[0], ProgressEvent { phase: Phase::Read, done: 5, total: 5 });
+
+    // Parse, Graph and Render report every page
+    for phase in [Phase::Parse, Phase::Graph, Phase::Render] {
+        let events: Vec<&ProgressEvent> = progress.events.iter().filter(|event| event.phase == phase).collect();
+        assert_eq!(events.len(), 5, "{:?}", phase);
+        assert!(events.iter().enumerate().all(|(i, event)| event.done == i + 1 && event.total == 5));
+    }
+
+    let writes: Vec<&ProgressEvent> = progress.events.iter().filter(|event| event.phase == Phase::Write).collect();
+    assert_eq!(writes.len(), site.len() + 1);
+    assert_eq!(writes[0].done, 0);
+    assert!(writes.iter().enumerate().all(|(i, event)| event.done == i && event.total == site.len()));
+}
+
+#[test]
+fn test_cancelled_publish_keeps_previous_site() {
+    let mut previous = MemoryFs::new();
+    block_on(publish_to(&config(), &test_graph(), &mut previous)).unwrap();
+
+    let mut graph = test_graph();
+    graph.insert("pages/new.md", "- A new page");
+    // Cancelled after reading, while parsing, building the graph, rendering and writing
+    for cancel_after in [1, 4, 9, 14, 20] {
+        let mut progress = RecordedProgress { events: Vec::new(), cancel_after };
+        let mut site = previous.clone();
+        let error = block_on(publish_with_progress(&config(), &graph, &mut site, &mut progress)).unwrap_err();
+        assert!(matches!(error, PublishError::Cancelled), "{}", error);
+        assert_eq!(site, previous);
+    }
+}
+
+#[test]
+fn test_reproducible_output() {
+    // Properties, tags, backlinks and pages sharing a title all used to follow hash map order
+    let mut graph: MemoryFs = (0..20)
+        .map(|i| (format!("pages/page_{:02}.md", i), format!("- Page {} links to [[hub]] #tag{} #shared", i, i % 3)))
+        .collect();
+    graph.insert("pages/hub.md", "zeta:: last\nalpha:: first\nmiddle:: second\n\n- The hub #shared");
+    graph.insert("journals/hub.md", "title:: hub\n- Another page titled hub");
+    let mut config = config();
+    config.incremental = true;
+    config.generate_feed = true;
+    config.tag_feeds = true;
+    config.base_url = Some("https://example.com/".to_string());
+
+    let build = || {
+        let mut site = MemoryFs::new();
+        block_on(publish_to(&config, &graph, &mut site)).unwrap();
+        site
+    };
+
+    let first = build();
+    for _ in 0..3 {
+        assert_eq!(build(), first);
+    }
+
+    let hub = first.get_str("pages/hub.html").unwrap();
+    let position = |text: &str| hub.find(text).unwrap();
+    assert!(position("zeta:") < position("alpha:") && position("alpha:") < position("middle:"));
+    assert!(position("page_00.html") < position("page_01.html") && position("page_01.html") < position("page_19.html"));
+}
+
+#[test]
+fn test_output_independent_of_thread_count() {
+    // Many pages linking to one hub, so the hub's backlinks come from pages parsed on different threads
+    let mut graph: MemoryFs = (0..200)
+        .map(|i| (format!("pages/page_{}.md", i), format!("- Page {} links to [[hub]] and [[page_{}]]", i, (i + 1) % 200)))
+        .collect();
+    graph.insert("pages/hub.md", "- The hub");
+    let config = config();
+
+    let publish = |threads| {
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap();
+        pool.install(|| {
+            let mut site = MemoryFs::new();
+            block_on(publish_to(&config, &graph, &mut site)).unwrap();
+            site
+        })
+    };
+
+    let sequential = publish(1);
+    assert!(sequential.get_str("pages/hub.html").unwrap().contains("page_199.html"));
+    assert_eq!(publish(8), sequential);
+}
+
+#[test]
+fn test_publish_rejects_paths_outside_graph() {
+    let graph: MemoryFs = [("../outside.md", "- Escaped")].into_iter().collect();
+    let mut site = MemoryFs::new();
+
+    assert!(block_on(publish_to(&config(), &graph, &mut site)).is_err());
+    assert!(site.is_empty());
+}
+
+#[test]
+fn test_publish_zip() {
+    let mut site = MemoryFs::new();
+    block_on(publish_to(&config(), &test_graph(), &mut site)).unwrap();
+
+    let mut zip = ZipWriter::new();
+    block_on(publish_to(&config(), &test_graph(), &mut zip)).unwrap();
+    let data = zip.finish().unwrap();
+
+    assert!(data.starts_with(b"PK\x03\x04"));
+    // The end of central directory record counts one entry per output file
+    let end = &data[data.len() - 22..];
+    assert!(end.starts_with(b"PK\x05\x06"));
+    assert_eq!(u16::from_le_bytes([end[10], end[11]]) as usize, site.len());
+}
+
+/// Output sink recording which files each build writes and removes
+#[derive(Default)]
+struct RecordingSink {
+    site: MemoryFs,
+    written: Vec<String>,
+    removed: Vec<String>,
+}
+
+impl RecordingSink {
+    fn publish(&mut self, config: &PublishConfig, graph: &MemoryFs) -> usize {
+        self.written.clear();
+        self.removed.clear();
+        block_on(publish_to(config, graph, self)).unwrap().reused_pages
+    }
+
+    fn wrote(&self, path: &str) -> bool {
+        self.written.iter().any(|written| written == path)
+    }
+}
+
+impl OutputSink for RecordingSink {
+    async fn write_file(&mut self, path: &str, content: &[u8]) -> Result<(), PublishError> {
+        self.written.push(path.to_string());
+        self.site.write_file(path, content).await
+    }
+
+    async fn read_file(&self, path: &str) -> Result<Option<Vec<u8>>, PublishError> {
+        self.site.read_file(path).await
+    }
+
+    async fn remove_file(&mut self, path: &str) -> Result<(), PublishError> {
+        self.removed.push(path.to_string());
+        self.site.remove_file(path).await
+    }
+}
+
+#[test]
+fn test_incremental_rebuilds() {
+    let mut config = config();
+    config.incremental = true;
+    config.precompress = true;
+
+    let mut graph: MemoryFs = [
+        ("pages/a.md", "- Links to [[b]]"),
+        ("pages/b.md", "- Page b"),
+        ("pages/c.md", "- Page c"),
+    ]
+    .into_iter()
+    .collect();
+    let mut sink = RecordingSink::default();
+
+    assert_eq!(sink.publish(&config, &graph), 0);
+    assert!(sink.site.get(".publish-cache.json").is_some());
+
+    // Nothing changed: no page is rendered and only the cache is rewritten
+    assert_eq!(sink.publish(&config, &graph), 3);
+    assert_eq!(sink.written, [".publish-cache.json"]);
+    assert!(sink.removed.is_empty());
+
+    // An edit re-renders only the edited page
+    graph.insert("pages/c.md", "- Page c, edited");
+    assert_eq!(sink.publish(&config, &graph), 2);
+    assert!(sink.wrote("pages/c.html") && sink.wrote("pages/c.html.br"));
+    assert!(!sink.wrote("pages/a.html") && !sink.wrote("pages/b.html"));
+
+    // A new link also re-renders its target, which gains a backlink
+    graph.insert("pages/a.md", "- Links to [[b]] and [[c]]");
+    assert_eq!(sink.publish(&config, &graph), 1);
+    assert!(sink.wrote("pages/a.html") && sink.wrote("pages/c.html"));
+    assert!(sink.site.get_str("pages/c.html").unwrap().contains("a.html"));
+
+    // Deleting a page removes its outputs and re-renders the pages linking to it
+    let graph: MemoryFs = graph.iter().filter(|(path, _)| *path != "pages/b.md").collect();
+    sink.publish(&config, &graph);
+    assert!(sink.wrote("pages/a.html"));
+    assert!(sink.removed.iter().any(|path| path == "pages/b.html"));
+    assert!(sink.removed.iter().any(|path| path == "pages/b.html.gz"));
+    assert!(sink.site.get("pages/b.html").is_none());
+
+    // A config change rebuilds everything
+    config.minify = false;
+    assert_eq!(sink.publish(&config, &graph), 0);
+    assert!(sink.wrote("pages/c.html"));
+
+    // The result matches a clean build
+    let mut clean = MemoryFs::new();
+    block_on(publish_to(&config, &graph, &mut clean)).unwrap();
+    assert_eq!(sink.site, clean);
+}